@@ -1,7 +1,15 @@
+use crate::board::{Board, ObjectId, TextMeasure as _};
+use crate::config::{ConfigImportMode, SharedConfig};
+use crate::document::{DOCUMENT_EXTENSION, SerializableAppState};
+use crate::preferences::Preferences;
 use crate::state::{
-    AppState, CanvasImage, CanvasObject, CanvasShape, CanvasShapeType, CanvasText, CanvasTool,
-    DynamicBrushWidthMode, RenderUpdateMode, ResizeAnchor, ResizeOperation, RotationOperation,
-    ThemeMode, WindowMode,
+    AccessibilitySettings, ActiveStroke, AppState, ArrowHeads, CanvasExportArea, CanvasImage,
+    CanvasObject, CanvasResizeBehavior, CanvasShape, CanvasShapeType, CanvasStats, CanvasText,
+    CanvasTool, CropOperation, DynamicBrushWidthMode, HoldState, ImageAction, PointerInputType,
+    ProtractorMeasurement, QuickToggleMode, RadialMenuItem, RenderUpdateMode, ResizeOperation,
+    RotationOperation, RotationPivot, RulerDrag, ShapeEndpoint, ShapeSizeEntry, SizePreviewStyle,
+    StrokeInsertPosition, TextAlign, TextFont, ThemeMode, ToolSettings, ToolSwitchStrokeBehavior,
+    ToolbarPosition, TraceReference, WindowMode,
 };
 use crate::utils::AppUtils;
 use eframe::Frame;
@@ -16,14 +24,6 @@ pub struct App {
     scale_factor: f32,
 }
 
-// struct SerializableAppState {
-//     canvas_objects: Vec<crate::state::CanvasObject>,
-//     brush_color: Color32,
-//     brush_width: f32,
-//     background_color: Color32,
-//     theme_mode: ThemeMode,
-// }
-
 impl Default for App {
     fn default() -> Self {
         Self {
@@ -34,8 +34,36 @@ impl Default for App {
     }
 }
 
+// 新插入形状的默认尺寸（像素）
+const DEFAULT_SHAPE_SIZE: f32 = 100.0;
+
+// 荧光笔：笔头宽度为画笔宽度的倍数，不透明度在画笔不透明度基础上再降低
+const HIGHLIGHTER_WIDTH_SCALE: f32 = 4.0;
+const HIGHLIGHTER_OPACITY: f32 = 0.4;
+
+// 画笔宽度上限的默认值：快捷宽度按该上限设计，上限调大时按比例放大
+const DEFAULT_MAX_BRUSH_WIDTH: f32 = 20.0;
+
+// 鼠标（单指针）输入绘制的笔画 ID，与触控 ID 区分
+const POINTER_STROKE_ID: u64 = u64::MAX;
+
+// 按住 Shift 吸附角度时的间隔（15°）：旋转形状、拖动直线端点、旋转直尺与距离测量
+const ROTATION_SNAP_STEP: f32 = std::f32::consts::PI / 12.0;
+
+// 开启厘米单位时的初始校准值（96 DPI 屏幕上 1 厘米的像素数）
+const DEFAULT_PIXELS_PER_CM: f32 = 96.0 / 2.54;
+
 impl App {
-    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+    // eframe 的应用名称，同时决定持久化存储的位置
+    pub const APP_NAME: &str = "eframe template";
+
+    // 创建窗口时使用的垂直同步模式，在 eframe 的 wgpu 设置中传入，并原样传给 new
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn startup_present_mode() -> PresentMode {
+        Preferences::startup_present_mode(Self::APP_NAME)
+    }
+
+    pub fn new(cc: &eframe::CreationContext<'_>, present_mode: PresentMode) -> Self {
         let ctx = &cc.egui_ctx;
 
         let mut fonts = egui::FontDefinitions::default();
@@ -91,20 +119,40 @@ impl App {
             eprintln!("Cannot find CJK font, falling back to default");
         }
 
+        // 画布文本的等宽与默认字体：优先使用 egui 自带字体，缺少的字形回退到中文字体
+        let builtin_fonts = egui::FontDefinitions::default();
+        for (name, base) in [
+            (TextFont::MONOSPACE_FAMILY, egui::FontFamily::Monospace),
+            (TextFont::DEFAULT_FAMILY, egui::FontFamily::Proportional),
+        ] {
+            let mut family = builtin_fonts
+                .families
+                .get(&base)
+                .cloned()
+                .unwrap_or_default();
+            if font_loaded {
+                family.push("cjk_font".to_owned());
+            }
+            fonts
+                .families
+                .insert(egui::FontFamily::Name(name.into()), family);
+        }
+
         ctx.set_fonts(fonts);
 
-        // Load previous app state (if any)
-        // if let Some(storage) = cc.storage {
-        //     eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default()
-        // } else {
-        Default::default()
-        // }
-    }
+        // Restore preferences, window geometry and the last tool saved by eframe's persistent storage
+        let mut app = Self::default();
+        if let Some(preferences) = cc
+            .storage
+            .and_then(|storage| eframe::get_value::<Preferences>(storage, eframe::APP_KEY))
+        {
+            preferences.apply(ctx, &mut app.state);
+        }
+        app.state.present_mode = present_mode;
+        app.state.active_present_mode = present_mode;
 
-    // fn apply_present_mode(&mut self) {
-    //     // Note: In eframe, present mode is handled by the framework
-    //     // This is a placeholder for future implementation if needed
-    // }
+        app
+    }
 
     // fn handle_resized(&mut self, width: u32, height: u32) {
     //     // In eframe, resizing is handled automatically
@@ -124,11 +172,17 @@ impl App {
 }
 
 impl eframe::App for App {
-    // fn save(&mut self, storage: &mut dyn eframe::Storage) {
-    //     eframe::set_value(storage, eframe::APP_KEY, self);
-    // }
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(
+            storage,
+            eframe::APP_KEY,
+            &Preferences::from_state(&self.state),
+        );
+    }
 
     fn update(&mut self, ctx: &egui::Context, frame: &mut Frame) {
+        self.state.window_geometry.capture(ctx);
+
         // self.window = Some(Arc::new(frame));
         // self.scale_factor = frame.scale_factor() as f32;
 
@@ -156,30 +210,218 @@ impl eframe::App for App {
             ThemeMode::Dark => {
                 ctx.set_visuals(egui::Visuals::dark());
             }
+            ThemeMode::Custom => {
+                ctx.set_visuals(self.state.custom_theme.visuals());
+            }
+        }
+
+        // Apply accessibility overrides on top of the theme, starting from
+        // the default style so scaling does not accumulate across frames
+        let mut style = egui::Style {
+            visuals: ctx.style().visuals.clone(),
+            ..Default::default()
+        };
+        self.state.accessibility.apply(&mut style);
+        ctx.set_style(style);
+
+        self.update_fading_strokes(ctx);
+
+        // Keep correction pen strokes in sync with the background color
+        if self.state.synced_background_color != self.state.background_color {
+            for object in self.state.board.objects_mut() {
+                if let CanvasObject::Stroke(stroke) = object
+                    && stroke.follow_background
+                {
+                    stroke.color = self.state.background_color;
+                }
+            }
+            self.state.synced_background_color = self.state.background_color;
         }
 
-        // Toolbar window
-        let content_rect = ctx.available_rect();
-        let margin = 20.0;
+        // Toolbar
+        match self.state.toolbar_position {
+            ToolbarPosition::Floating => {
+                let content_rect = ctx.available_rect();
+                let margin = 20.0;
 
-        egui::Window::new("工具栏")
-            .resizable(false)
-            .pivot(egui::Align2::CENTER_BOTTOM)
-            .default_pos([content_rect.center().x, content_rect.max.y - margin])
-            .show(ctx, |ui| {
-                self.render_toolbar(ui);
+                egui::Window::new("工具栏")
+                    .resizable(false)
+                    .pivot(egui::Align2::CENTER_BOTTOM)
+                    .default_pos([content_rect.center().x, content_rect.max.y - margin])
+                    .show(ctx, |ui| {
+                        self.render_toolbar(ui);
+                    });
+            }
+            ToolbarPosition::Top => {
+                egui::TopBottomPanel::top("toolbar").show(ctx, |ui| {
+                    self.render_toolbar(ui);
+                });
+            }
+            ToolbarPosition::Bottom => {
+                egui::TopBottomPanel::bottom("toolbar").show(ctx, |ui| {
+                    self.render_toolbar(ui);
+                });
+            }
+            ToolbarPosition::Left => {
+                egui::SidePanel::left("toolbar").show(ctx, |ui| {
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        self.render_toolbar(ui);
+                    });
+                });
+            }
+            ToolbarPosition::Right => {
+                egui::SidePanel::right("toolbar").show(ctx, |ui| {
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        self.render_toolbar(ui);
+                    });
+                });
+            }
+        }
+
+        if ctx.input(|i| i.modifiers.command && i.key_pressed(egui::Key::F)) {
+            self.state.show_text_search = true;
+        }
+        if !ctx.wants_keyboard_input()
+            && ctx.input(|i| i.modifiers.is_none() && i.key_pressed(egui::Key::Q))
+        {
+            self.quick_toggle_tool();
+        }
+        if !ctx.wants_keyboard_input() {
+            let (undo, redo) = ctx.input(|i| {
+                let command = i.modifiers.command;
+                (
+                    command && !i.modifiers.shift && i.key_pressed(egui::Key::Z),
+                    command
+                        && (i.key_pressed(egui::Key::Y)
+                            || (i.modifiers.shift && i.key_pressed(egui::Key::Z))),
+                )
+            });
+            if undo {
+                self.undo();
+            } else if redo {
+                self.redo();
+            }
+
+            // 复制、剪切与粘贴选中的对象（系统快捷键以事件形式传入）
+            let (copy, cut, paste) = ctx.input(|i| {
+                i.events.iter().fold(
+                    (false, false, false),
+                    |(copy, cut, paste), event| match event {
+                        egui::Event::Copy => (true, cut, paste),
+                        egui::Event::Cut => (copy, true, paste),
+                        egui::Event::Paste(_) => (copy, cut, true),
+                        _ => (copy, cut, paste),
+                    },
+                )
+            });
+            if copy {
+                self.copy_selected();
+            }
+            if cut {
+                self.cut_selected();
+            }
+            if paste {
+                self.paste_clipboard(ctx);
+            }
+
+            // 选择工具下删除、复制一份选中的对象或调整其上下顺序
+            if self.state.current_tool == CanvasTool::Select {
+                let (delete, duplicate, front, back) = ctx.input(|i| {
+                    (
+                        i.key_pressed(egui::Key::Delete) || i.key_pressed(egui::Key::Backspace),
+                        i.modifiers.command && i.key_pressed(egui::Key::D),
+                        i.modifiers.command && i.key_pressed(egui::Key::CloseBracket),
+                        i.modifiers.command && i.key_pressed(egui::Key::OpenBracket),
+                    )
+                });
+                if delete {
+                    self.delete_selected();
+                } else if duplicate {
+                    self.duplicate_selected();
+                } else if front || back {
+                    self.reorder_selected(front);
+                }
+            }
+
+            // 折线工具：Enter 完成，Backspace 删除上一个顶点，Esc 取消
+            if self.state.current_tool == CanvasTool::Polyline && !self.state.canvas_locked {
+                let (finish, remove_last, cancel) = ctx.input(|i| {
+                    (
+                        i.key_pressed(egui::Key::Enter),
+                        i.key_pressed(egui::Key::Backspace),
+                        i.key_pressed(egui::Key::Escape),
+                    )
+                });
+                if finish {
+                    self.finish_pen_path();
+                } else if remove_last {
+                    self.state.pen_points.pop();
+                } else if cancel {
+                    self.state.pen_points.clear();
+                }
+            }
+        }
+        self.render_text_search(ctx);
+        self.render_page_overview(ctx);
+        self.render_shape_size_entry(ctx);
+        self.render_text_editor(ctx);
+
+        // Track whether the latest press came from a pen or a finger: touch
+        // events carrying force come from pens, touches without force from
+        // fingers, and presses without any touch event from a mouse/pen
+        ctx.input(|i| {
+            let touch_force = i.events.iter().find_map(|event| match event {
+                egui::Event::Touch {
+                    phase: egui::TouchPhase::Start,
+                    force,
+                    ..
+                } => Some(*force),
+                _ => None,
             });
+            let pressed = i
+                .events
+                .iter()
+                .any(|event| matches!(event, egui::Event::PointerButton { pressed: true, .. }));
+            match touch_force {
+                Some(Some(_)) => self.state.last_pointer_input = PointerInputType::Pen,
+                Some(None) => self.state.last_pointer_input = PointerInputType::Finger,
+                None if pressed => self.state.last_pointer_input = PointerInputType::Pen,
+                None => {}
+            }
+        });
+
+        // Track live touch points (shown by the touch point debug overlay)
+        ctx.input(|i| {
+            for event in &i.events {
+                if let egui::Event::Touch { id, phase, pos, .. } = event {
+                    match phase {
+                        egui::TouchPhase::Start | egui::TouchPhase::Move => {
+                            self.state.touch_points.insert(id.0, *pos);
+                        }
+                        egui::TouchPhase::End | egui::TouchPhase::Cancel => {
+                            self.state.touch_points.remove(&id.0);
+                        }
+                    }
+                }
+            }
+        });
+
+        self.handle_pointer_leave(ctx);
 
         // Main canvas area
         egui::CentralPanel::default().show(ctx, |ui| {
             self.render_canvas(ui);
         });
 
-        // Handle present mode changes
-        // if self.state.present_mode_changed {
-        //     self.apply_present_mode();
-        //     self.state.present_mode_changed = false;
-        // }
+        self.render_toast(ctx);
+
+        // 垂直同步模式只能在创建窗口时设置，更改后提示重启
+        if self.state.present_mode_changed {
+            self.state.present_mode_changed = false;
+            if self.state.present_mode != self.state.active_present_mode {
+                self.show_toast("垂直同步模式将在重启后生效");
+            }
+        }
 
         // Update FPS if enabled
         if self.state.show_fps {
@@ -196,41 +438,228 @@ impl eframe::App for App {
 }
 
 impl App {
+    // 页面总览：以缩略图网格显示所有页面，点击跳转，拖动调整顺序
+    fn render_page_overview(&mut self, ctx: &egui::Context) {
+        const THUMBNAIL_WIDTH: f32 = 160.0;
+        const THUMBNAILS_PER_ROW: usize = 4;
+
+        if !self.state.show_page_overview {
+            self.state.page_thumbnails.clear();
+            return;
+        }
+
+        let canvas_rect = self
+            .state
+            .last_canvas_rect
+            .unwrap_or_else(|| ctx.available_rect());
+        let thumbnail_size = egui::vec2(
+            THUMBNAIL_WIDTH,
+            THUMBNAIL_WIDTH * canvas_rect.height() / canvas_rect.width().max(1.0),
+        );
+
+        let mut open = true;
+        let mut jump_to = None;
+        let mut reorder = None;
+        let mut shown_thumbnails = std::collections::HashSet::new();
+        egui::Window::new("页面总览")
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    egui::Grid::new("page_overview_grid").show(ui, |ui| {
+                        for index in 0..self.state.pages.len() {
+                            let objects = if index == self.state.current_page {
+                                self.state.board.objects()
+                            } else {
+                                match self.state.pages.get(index) {
+                                    Some(objects) => objects,
+                                    None => continue,
+                                }
+                            };
+
+                            let drag_id = egui::Id::new(("page_thumbnail", index));
+                            let drop_response = ui
+                                .vertical(|ui| {
+                                    ui.dnd_drag_source(drag_id, index, |ui| {
+                                        let (thumb_rect, thumb_response) = ui.allocate_exact_size(
+                                            thumbnail_size,
+                                            egui::Sense::click(),
+                                        );
+                                        let key = Self::page_thumbnail_key(
+                                            objects,
+                                            canvas_rect,
+                                            thumbnail_size,
+                                            self.state.background_color,
+                                        );
+                                        let texture =
+                                            self.state.page_thumbnails.entry(key).or_insert_with(
+                                                || {
+                                                    Self::render_page_thumbnail(
+                                                        ui.ctx(),
+                                                        objects,
+                                                        canvas_rect,
+                                                        thumbnail_size,
+                                                        self.state.background_color,
+                                                    )
+                                                },
+                                            );
+                                        ui.painter().image(
+                                            texture.id(),
+                                            thumb_rect,
+                                            egui::Rect::from_min_max(
+                                                Pos2::ZERO,
+                                                Pos2::new(1.0, 1.0),
+                                            ),
+                                            Color32::WHITE,
+                                        );
+                                        shown_thumbnails.insert(key);
+                                        let border = if index == self.state.current_page {
+                                            Stroke::new(3.0, Color32::from_rgb(0, 120, 215))
+                                        } else {
+                                            Stroke::new(1.0, Color32::GRAY)
+                                        };
+                                        ui.painter().rect_stroke(
+                                            thumb_rect,
+                                            0.0,
+                                            border,
+                                            egui::StrokeKind::Outside,
+                                        );
+                                        if thumb_response.clicked() {
+                                            jump_to = Some(index);
+                                        }
+                                    });
+                                    ui.label(format!("第 {} 页", index + 1));
+                                })
+                                .response;
+
+                            if let Some(from) = drop_response.dnd_release_payload::<usize>() {
+                                reorder = Some((*from, index));
+                            }
+
+                            if (index + 1) % THUMBNAILS_PER_ROW == 0 {
+                                ui.end_row();
+                            }
+                        }
+                    });
+                });
+            });
+        self.state.show_page_overview = open;
+        // 内容已改变或页面已删除的缩略图不再显示，释放其纹理
+        self.state
+            .page_thumbnails
+            .retain(|key, _| shown_thumbnails.contains(key));
+
+        if let Some((from, to)) = reorder {
+            self.move_page(from, to);
+        } else if let Some(index) = jump_to {
+            self.switch_page(index);
+        }
+    }
+
+    // 缩略图缓存的键：页面内容、画布区域、缩略图尺寸与背景颜色的摘要，任意一项改变时重新渲染
+    fn page_thumbnail_key(
+        objects: &[CanvasObject],
+        canvas_rect: egui::Rect,
+        thumbnail_size: egui::Vec2,
+        background: Color32,
+    ) -> u64 {
+        use std::hash::{Hash as _, Hasher as _};
+
+        let mut hasher = std::hash::DefaultHasher::new();
+        for value in [canvas_rect.min, canvas_rect.max, thumbnail_size.to_pos2()] {
+            (value.x.to_bits(), value.y.to_bits()).hash(&mut hasher);
+        }
+        background.hash(&mut hasher);
+        for object in objects {
+            object.hash_appearance(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    // 离屏渲染页面内容的缩小版本并上传为纹理（分辨率与屏幕显示一致）
+    fn render_page_thumbnail(
+        ctx: &egui::Context,
+        objects: &[CanvasObject],
+        canvas_rect: egui::Rect,
+        thumbnail_size: egui::Vec2,
+        background: Color32,
+    ) -> egui::TextureHandle {
+        let fonts = ctx.fonts(|fonts| fonts.definitions().clone());
+        let scale = thumbnail_size.x / canvas_rect.width().max(1.0) * ctx.pixels_per_point();
+        let image = Board::render_objects(objects, canvas_rect, scale, background, &fonts);
+        ctx.load_texture("page_thumbnail", image, egui::TextureOptions::LINEAR)
+    }
+
+    fn render_text_search(&mut self, ctx: &egui::Context) {
+        if !self.state.show_text_search {
+            return;
+        }
+
+        let matches =
+            AppUtils::find_text_matches(self.state.board.objects(), &self.state.text_search_query);
+
+        let mut open = true;
+        egui::Window::new("查找文本")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("关键词:");
+                    if ui
+                        .text_edit_singleline(&mut self.state.text_search_query)
+                        .changed()
+                    {
+                        self.state.text_search_cursor = 0;
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label(format!("找到 {} 个结果", matches.len()));
+                    if ui
+                        .add_enabled(!matches.is_empty(), egui::Button::new("下一个"))
+                        .clicked()
+                    {
+                        let cursor = self.state.text_search_cursor % matches.len().max(1);
+                        if let Some(&index) = matches.get(cursor) {
+                            self.switch_tool(CanvasTool::Select);
+                            self.state.selected_object = Some(index);
+                            self.center_view_on_object(ctx, index);
+                        }
+                        self.state.text_search_cursor = cursor + 1;
+                    }
+                });
+            });
+        self.state.show_text_search = open;
+    }
+
+    // 平移视图，使对象的包围盒位于画布可见区域的中心（缩放比例不变）
+    fn center_view_on_object(&mut self, ctx: &egui::Context, index: usize) {
+        let (Some(object), Some(canvas_rect)) =
+            (self.state.board.get(index), self.state.last_canvas_rect)
+        else {
+            return;
+        };
+        let painter = ctx.layer_painter(egui::LayerId::background());
+        let center = AppUtils::calculate_object_bounding_box(&painter, object).center();
+        self.state.pan_offset = canvas_rect.center().to_vec2() - center.to_vec2() * self.state.zoom;
+    }
+
     fn render_toolbar(&mut self, ui: &mut egui::Ui) {
         // Tool selection
         ui.horizontal(|ui| {
             ui.label("工具:");
-            let old_tool = self.state.current_tool;
+            let mut new_tool = self.state.current_tool;
+            for tool in CanvasTool::ALL {
+                ui.selectable_value(&mut new_tool, tool, tool.label());
+            }
+            self.switch_tool(new_tool);
             if ui
-                .selectable_value(&mut self.state.current_tool, CanvasTool::Select, "选择")
-                .changed()
-                || ui
-                    .selectable_value(&mut self.state.current_tool, CanvasTool::Brush, "画笔")
-                    .changed()
-                || ui
-                    .selectable_value(
-                        &mut self.state.current_tool,
-                        CanvasTool::ObjectEraser,
-                        "对象橡皮擦",
-                    )
-                    .changed()
-                || ui
-                    .selectable_value(
-                        &mut self.state.current_tool,
-                        CanvasTool::PixelEraser,
-                        "像素橡皮擦",
-                    )
-                    .changed()
-                || ui
-                    .selectable_value(&mut self.state.current_tool, CanvasTool::Insert, "插入")
-                    .changed()
-                || ui
-                    .selectable_value(&mut self.state.current_tool, CanvasTool::Settings, "设置")
-                    .changed()
+                .button("快速切换")
+                .on_hover_text("在两个常用工具之间切换（快捷键 Q）")
+                .clicked()
             {
-                if self.state.current_tool != old_tool {
-                    self.state.selected_object = None;
-                }
+                self.quick_toggle_tool();
             }
         });
 
@@ -246,42 +675,42 @@ impl App {
                     .changed()
                 {
                     if self.state.is_drawing {
-                        for (_touch_id, active_stroke) in self.state.active_strokes.drain() {
+                        let active_strokes: Vec<ActiveStroke> = self
+                            .state
+                            .active_strokes
+                            .drain()
+                            .map(|(_touch_id, active_stroke)| active_stroke)
+                            .collect();
+                        for active_stroke in active_strokes {
                             if active_stroke.points.len() > 1 {
-                                self.state.canvas_objects.push(CanvasObject::Stroke(
-                                    crate::state::CanvasStroke {
-                                        points: active_stroke.points,
-                                        widths: active_stroke.widths,
-                                        color: old_color,
-                                        base_width: self.state.brush_width,
-                                    },
-                                ));
+                                self.record_brush_color(old_color);
+                                self.insert_stroke(crate::state::CanvasStroke {
+                                    points: active_stroke.points,
+                                    widths: active_stroke.widths,
+                                    forces: active_stroke.forces,
+                                    color: old_color,
+                                    base_width: self.state.brush_width,
+                                    follow_background: false,
+                                    input_type: active_stroke.input_type,
+                                    highlighter: self.state.highlighter
+                                        && active_stroke.input_type == PointerInputType::Pen,
+                                    fade: None,
+                                    cache: Default::default(),
+                                });
                             }
                         }
                         self.state.is_drawing = false;
                     }
                 }
+                ui.toggle_value(&mut self.state.eyedropper_active, "吸管")
+                    .on_hover_text("单击画布上的对象吸取其颜色");
             });
 
             // Quick color buttons
             ui.horizontal(|ui| {
                 ui.label("快捷颜色:");
                 for color in &self.state.quick_colors {
-                    let color_name = if color.r() == 255 && color.g() == 0 && color.b() == 0 {
-                        "红"
-                    } else if color.r() == 255 && color.g() == 255 && color.b() == 0 {
-                        "黄"
-                    } else if color.r() == 0 && color.g() == 255 && color.b() == 0 {
-                        "绿"
-                    } else if color.r() == 0 && color.g() == 0 && color.b() == 255 {
-                        "蓝"
-                    } else if color.r() == 0 && color.g() == 0 && color.b() == 0 {
-                        "黑"
-                    } else if color.r() == 255 && color.g() == 255 && color.b() == 255 {
-                        "白"
-                    } else {
-                        "自定义"
-                    };
+                    let color_name = AppUtils::nearest_color_name(*color);
                     if ui
                         .add(egui::Button::new(
                             egui::RichText::new(color_name).color(*color),
@@ -293,139 +722,765 @@ impl App {
                 }
             });
 
+            // 最近使用的颜色
+            if !self.state.recent_colors.is_empty() {
+                ui.horizontal(|ui| {
+                    ui.label("最近颜色:");
+                    for color in &self.state.recent_colors {
+                        let (rect, response) =
+                            ui.allocate_exact_size(egui::vec2(20.0, 20.0), egui::Sense::click());
+                        ui.painter().rect_filled(rect, 3.0, *color);
+                        ui.painter().rect_stroke(
+                            rect,
+                            3.0,
+                            ui.visuals().widgets.noninteractive.bg_stroke,
+                            egui::StrokeKind::Inside,
+                        );
+                        if response
+                            .on_hover_text(AppUtils::nearest_color_name(*color))
+                            .clicked()
+                        {
+                            self.state.brush_color = *color;
+                        }
+                    }
+                });
+            }
+
             ui.horizontal(|ui| {
                 ui.label("宽度:");
-                let slider_response =
-                    ui.add(egui::Slider::new(&mut self.state.brush_width, 1.0..=20.0));
+                let mut slider = egui::Slider::new(
+                    &mut self.state.brush_width,
+                    self.state.min_brush_width..=self.state.max_brush_width,
+                );
+                if self.state.integer_widths {
+                    slider = slider.step_by(1.0);
+                }
+                let slider_response = ui.add(slider);
 
                 if slider_response.dragged() || slider_response.hovered() {
                     self.state.show_size_preview = true;
                 } else if !slider_response.dragged() && !slider_response.hovered() {
                     self.state.show_size_preview = false;
                 }
+
+                if ui
+                    .checkbox(&mut self.state.integer_widths, "整数宽度")
+                    .changed()
+                    && self.state.integer_widths
+                {
+                    self.state.brush_width = self.state.brush_width.round();
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("不透明度:");
+                ui.add(
+                    egui::Slider::new(&mut self.state.brush_opacity, 0.05..=1.0)
+                        .custom_formatter(|value, _| format!("{:.0}%", value * 100.0))
+                        .custom_parser(|text| {
+                            text.trim_end_matches('%')
+                                .trim()
+                                .parse::<f64>()
+                                .ok()
+                                .map(|percent| percent / 100.0)
+                        }),
+                )
+                .on_hover_text("笔迹颜色的不透明度，重叠的半透明笔迹会叠加");
+                ui.checkbox(&mut self.state.highlighter, "荧光笔")
+                    .on_hover_text("笔头加宽为固定宽度，笔迹半透明，可叠加在文字上");
+            });
+
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.state.fading_ink, "渐隐笔迹")
+                    .on_hover_text("新笔迹显示一段时间后逐渐消失，适合临时标注");
+                ui.add_enabled(
+                    self.state.fading_ink,
+                    egui::Slider::new(&mut self.state.fading_ink_hold, 1.0..=30.0)
+                        .text("停留")
+                        .suffix(" 秒"),
+                );
             });
 
             // Brush width quick buttons
             ui.horizontal(|ui| {
                 ui.label("快捷宽度:");
-                if ui.button("小").clicked() {
-                    self.state.brush_width = 1.0;
-                }
-                if ui.button("中").clicked() {
-                    self.state.brush_width = 3.0;
-                }
-                if ui.button("大").clicked() {
-                    self.state.brush_width = 5.0;
+                const WIDTH_NAMES: [&str; 3] = ["小", "中", "大"];
+                let scale = self.state.max_brush_width / DEFAULT_MAX_BRUSH_WIDTH;
+                for (index, width) in self.state.quick_widths.iter().enumerate() {
+                    let width = (width * scale)
+                        .clamp(self.state.min_brush_width, self.state.max_brush_width);
+                    let label = if self.state.quick_widths.len() == WIDTH_NAMES.len() {
+                        WIDTH_NAMES.get(index).map(|name| (*name).to_owned())
+                    } else {
+                        None
+                    }
+                    .unwrap_or_else(|| format!("{width:.1}"));
+                    if ui
+                        .button(label)
+                        .on_hover_text(format!("宽度 {width:.1}"))
+                        .clicked()
+                    {
+                        self.state.brush_width = if self.state.integer_widths {
+                            width.round()
+                        } else {
+                            width
+                        };
+                    }
                 }
             });
 
             ui.separator();
 
+            ui.add_enabled_ui(!self.state.highlighter, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("动态画笔宽度微调:");
+                    ui.selectable_value(
+                        &mut self.state.dynamic_brush_width_mode,
+                        DynamicBrushWidthMode::Disabled,
+                        "禁用",
+                    );
+                    ui.selectable_value(
+                        &mut self.state.dynamic_brush_width_mode,
+                        DynamicBrushWidthMode::BrushTip,
+                        "模拟笔锋",
+                    );
+                    ui.selectable_value(
+                        &mut self.state.dynamic_brush_width_mode,
+                        DynamicBrushWidthMode::SpeedBased,
+                        "基于速度",
+                    );
+                    ui.selectable_value(
+                        &mut self.state.dynamic_brush_width_mode,
+                        DynamicBrushWidthMode::PressureBased,
+                        "基于压感",
+                    )
+                    .on_hover_text("按触控笔的压力调整宽度，输入设备未报告压力时使用固定宽度");
+                });
+            });
+
+            if self.state.show_dynamic_width_legend
+                && !self.state.highlighter
+                && self.state.dynamic_brush_width_mode != DynamicBrushWidthMode::Disabled
+            {
+                self.render_dynamic_width_legend(ui);
+            }
+
             ui.horizontal(|ui| {
-                ui.label("动态画笔宽度微调:");
-                ui.selectable_value(
-                    &mut self.state.dynamic_brush_width_mode,
-                    DynamicBrushWidthMode::Disabled,
-                    "禁用",
-                );
-                ui.selectable_value(
-                    &mut self.state.dynamic_brush_width_mode,
-                    DynamicBrushWidthMode::BrushTip,
-                    "模拟笔锋",
-                );
-                ui.selectable_value(
-                    &mut self.state.dynamic_brush_width_mode,
-                    DynamicBrushWidthMode::SpeedBased,
-                    "基于速度",
-                );
+                ui.label("手指输入:");
+                ui.checkbox(&mut self.state.separate_finger_style, "使用荧光笔样式")
+                    .on_hover_text("触控笔按画笔设置绘制，手指绘制固定宽度的半透明笔迹");
+                if self.state.separate_finger_style {
+                    ui.add(
+                        egui::Slider::new(&mut self.state.finger_brush_width, 4.0..=60.0)
+                            .text("宽度"),
+                    );
+                    ui.add(
+                        egui::Slider::new(&mut self.state.finger_brush_opacity, 0.1..=1.0)
+                            .text("不透明度"),
+                    );
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("修正笔:");
+                ui.checkbox(&mut self.state.correction_pen, "使用背景颜色绘制")
+                    .on_hover_text("笔迹颜色会随背景颜色变化，可用于涂改");
             });
 
             ui.horizontal(|ui| {
                 ui.label("笔迹平滑:");
                 ui.checkbox(&mut self.state.stroke_smoothing, "启用");
+                ui.add_enabled(
+                    self.state.stroke_smoothing,
+                    egui::Slider::new(&mut self.state.smoothing_iterations, 1..=5).text("次数"),
+                );
             });
-        }
 
-        // Eraser related settings
-        if self.state.current_tool == CanvasTool::ObjectEraser
-            || self.state.current_tool == CanvasTool::PixelEraser
-        {
             ui.horizontal(|ui| {
-                ui.label("橡皮擦大小:");
-                let slider_response =
-                    ui.add(egui::Slider::new(&mut self.state.eraser_size, 5.0..=50.0));
+                ui.label("实时墨迹平滑:");
+                ui.checkbox(&mut self.state.live_stroke_smoothing, "启用")
+                    .on_hover_text("仅平滑绘制中笔画的末端显示，不改变记录的笔迹");
+                ui.add_enabled(
+                    self.state.live_stroke_smoothing,
+                    egui::Slider::new(&mut self.state.live_smoothing_window, 2..=10).text("点数"),
+                );
+            });
 
-                if slider_response.dragged() || slider_response.hovered() {
-                    self.state.show_size_preview = true;
-                } else if !slider_response.dragged() && !slider_response.hovered() {
-                    self.state.show_size_preview = false;
+            ui.horizontal(|ui| {
+                ui.label("绘图辅助:");
+                ui.toggle_value(&mut self.state.auto_straighten, "自动拉直");
+                ui.add_enabled(
+                    self.state.auto_straighten,
+                    egui::Slider::new(&mut self.state.straighten_threshold, 1.0..=40.0)
+                        .text("阈值")
+                        .suffix(" px"),
+                );
+                ui.toggle_value(&mut self.state.recognize_shapes, "识别形状")
+                    .on_hover_text(
+                        "笔画接近直线、圆形、矩形或三角形时替换为对应形状，撤销可恢复手绘笔迹",
+                    );
+                let mut show_ruler = self.state.ruler.is_some();
+                if ui
+                    .toggle_value(&mut show_ruler, "直尺")
+                    .on_hover_text("拖动尺身移动直尺，拖动手柄旋转（按住 Shift 吸附角度）")
+                    .changed()
+                {
+                    self.state.ruler = show_ruler.then(|| {
+                        let canvas_rect = self
+                            .state
+                            .last_canvas_rect
+                            .unwrap_or_else(|| ui.ctx().available_rect());
+                        (
+                            self.state.view_transform().inverse() * canvas_rect.center(),
+                            0.0,
+                        )
+                    });
+                    self.state.ruler_drag = None;
                 }
+            });
 
-                if ui.button("清空画布").clicked() {
-                    self.state.canvas_objects.clear();
-                    self.state.active_strokes.clear();
-                    self.state.is_drawing = false;
-                    self.state.selected_object = None;
-                    self.state.current_tool = CanvasTool::Brush;
+            ui.horizontal(|ui| {
+                ui.label("描摹吸附:");
+                if ui.button("加载底图").clicked() {
+                    self.load_trace_reference(ui.ctx());
+                }
+                if self.state.trace_reference.is_some() {
+                    if ui.button("移除底图").clicked() {
+                        self.state.trace_reference = None;
+                    }
+                    ui.checkbox(&mut self.state.trace_snap, "启用");
+                    ui.add_enabled(
+                        self.state.trace_snap,
+                        egui::Slider::new(&mut self.state.trace_snap_strength, 0.0..=1.0)
+                            .text("强度"),
+                    );
+                    ui.add_enabled(
+                        self.state.trace_snap,
+                        egui::Slider::new(&mut self.state.trace_snap_radius, 2.0..=40.0)
+                            .text("半径")
+                            .suffix(" px"),
+                    );
                 }
             });
         }
 
-        // Insert tool related settings
-        if self.state.current_tool == CanvasTool::Insert {
+        // 锁定画布时禁用所有会修改内容的工具选项
+        let canvas_locked = self.state.canvas_locked;
+        ui.add_enabled_ui(!canvas_locked, |ui| self.render_editing_tool_settings(ui));
+
+        self.render_settings_tool(ui);
+    }
+
+    // 选择、插入等会修改画布内容的工具选项
+    fn render_editing_tool_settings(&mut self, ui: &mut egui::Ui) {
+        // Select tool related settings
+        if self.state.current_tool == CanvasTool::Select {
             ui.horizontal(|ui| {
-                if ui.button("图片").clicked() {
-                    let future = async {
-                        rfd::AsyncFileDialog::new()
-                            .add_filter(
-                                "图片",
-                                &[
-                                    "png", "jpg", "jpeg", "bmp", "gif", "tiff", "pnm", "webp",
-                                    "tga", "dds", "ico", "hdr", "avif", "qoi",
-                                ],
-                            )
-                            .pick_file()
-                            .await
-                    };
-                    // if let Some(path) = rfd::FileDialog::new()
-                    //     .add_filter(
-                    //         "图片",
-                    //         &[
+                ui.label("旋转中心:");
+                for (pivot, label) in [
+                    (RotationPivot::Center, "中心"),
+                    (RotationPivot::Origin, "起点"),
+                    (RotationPivot::TopLeft, "左上"),
+                    (RotationPivot::TopRight, "右上"),
+                    (RotationPivot::BottomLeft, "左下"),
+                    (RotationPivot::BottomRight, "右下"),
+                    (RotationPivot::Custom, "自定义"),
+                ] {
+                    ui.selectable_value(&mut self.state.rotation_pivot, pivot, label);
+                }
+            });
+
+            ui.checkbox(&mut self.state.keep_image_aspect_ratio, "图片保持宽高比")
+                .on_hover_text(
+                    "调整图片大小时按住 Shift 可临时切换；调整形状时按住 Shift 保持宽高比",
+                );
+
+            if self.state.rotation_pivot == RotationPivot::Custom {
+                ui.horizontal(|ui| {
+                    ui.label("水平位置:");
+                    ui.add(egui::Slider::new(
+                        &mut self.state.custom_rotation_pivot.x,
+                        0.0..=1.0,
+                    ));
+                    ui.label("垂直位置:");
+                    ui.add(egui::Slider::new(
+                        &mut self.state.custom_rotation_pivot.y,
+                        0.0..=1.0,
+                    ));
+                });
+            }
+
+            // Image properties
+            let mut image_action = None;
+            if let Some(CanvasObject::Image(img)) = self
+                .state
+                .selected_object
+                .and_then(|index| self.state.board.get_mut(index))
+            {
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("图片圆角:");
+                    let max_radius = img.size.min_elem() / 2.0;
+                    ui.add(egui::Slider::new(&mut img.corner_radius, 0.0..=max_radius));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("图片边框:");
+                    ui.add(egui::Slider::new(&mut img.border_width, 0.0..=20.0));
+                    ui.color_edit_button_srgba(&mut img.border_color);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("裁剪:");
+                    if self.state.crop_operation.is_some() {
+                        ui.label("在图片上拖动选择保留的区域");
+                        let has_rect = self
+                            .state
+                            .crop_operation
+                            .is_some_and(|crop| crop.rect.is_some());
+                        if ui
+                            .add_enabled(has_rect, egui::Button::new("应用"))
+                            .clicked()
+                        {
+                            image_action = Some(ImageAction::ApplyCrop);
+                        }
+                        if ui.button("取消").clicked() {
+                            image_action = Some(ImageAction::CancelCrop);
+                        }
+                    } else {
+                        if ui.button("裁剪").clicked() {
+                            image_action = Some(ImageAction::StartCrop);
+                        }
+                        if ui
+                            .add_enabled(img.crop.is_some(), egui::Button::new("还原原图"))
+                            .clicked()
+                        {
+                            image_action = Some(ImageAction::ResetCrop);
+                        }
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("变换:");
+                    if ui.button("↔ 水平翻转").clicked() {
+                        image_action = Some(ImageAction::FlipHorizontal);
+                    }
+                    if ui.button("↕ 垂直翻转").clicked() {
+                        image_action = Some(ImageAction::FlipVertical);
+                    }
+                    if ui.button("⟳ 旋转 90°").clicked() {
+                        image_action = Some(ImageAction::RotateClockwise);
+                    }
+                });
+            }
+            if let Some(action) = image_action {
+                self.handle_image_action(ui.ctx(), action);
+            }
+
+            // Rectangle and ellipse properties
+            if let Some(CanvasObject::Shape(shape)) = self
+                .state
+                .selected_object
+                .and_then(|index| self.state.board.get_mut(index))
+                && shape.frame().is_some()
+            {
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let mut size = shape.rect_size();
+                    ui.label("宽:");
+                    let width_changed = ui
+                        .add(egui::DragValue::new(&mut size.x).range(10.0..=4000.0))
+                        .changed();
+                    ui.label("高:");
+                    let height_changed = ui
+                        .add(egui::DragValue::new(&mut size.y).range(10.0..=4000.0))
+                        .changed();
+                    if width_changed || height_changed {
+                        shape.size = size.x;
+                        shape.height = Some(size.y);
+                    }
+                });
+                if matches!(shape.shape_type, CanvasShapeType::Rectangle) {
+                    ui.horizontal(|ui| {
+                        ui.label("圆角:");
+                        let max_radius = shape.rect_size().min_elem() / 2.0;
+                        ui.add(egui::Slider::new(
+                            &mut shape.corner_radius,
+                            0.0..=max_radius,
+                        ));
+                    });
+                }
+            }
+
+            // Arrow properties
+            if let Some(CanvasObject::Shape(shape)) = self
+                .state
+                .selected_object
+                .and_then(|index| self.state.board.get_mut(index))
+                && matches!(shape.shape_type, CanvasShapeType::Arrow)
+            {
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("箭头:");
+                    ui.selectable_value(&mut shape.arrow_heads, ArrowHeads::End, "单向");
+                    ui.selectable_value(&mut shape.arrow_heads, ArrowHeads::Both, "双向");
+                });
+            }
+
+            // Handwriting recognition
+            #[cfg(feature = "ocr")]
+            if let Some(CanvasObject::Stroke(_)) = self
+                .state
+                .selected_object
+                .and_then(|index| self.state.board.get(index))
+            {
+                ui.separator();
+                if ui
+                    .button("识别手写")
+                    .on_hover_text("识别选中笔迹及其附近的笔迹，并替换为文本")
+                    .clicked()
+                {
+                    self.recognize_handwriting();
+                }
+            }
+
+            // Re-smooth selected stroke
+            let mut resmoothed = None;
+            if let Some(CanvasObject::Stroke(stroke)) = self
+                .state
+                .selected_object
+                .and_then(|index| self.state.board.get(index))
+            {
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("平滑次数:");
+                    ui.add(egui::Slider::new(
+                        &mut self.state.smoothing_iterations,
+                        1..=5,
+                    ));
+                    ui.checkbox(&mut self.state.show_smoothing_preview, "预览");
+                    if ui.button("重新平滑选中").clicked() {
+                        let points = AppUtils::apply_stroke_smoothing(
+                            &stroke.points,
+                            self.state.smoothing_iterations,
+                        );
+                        let widths = AppUtils::resample_widths(&stroke.widths, points.len());
+                        let forces = AppUtils::resample_widths(&stroke.forces, points.len());
+                        resmoothed = Some((points, widths, forces));
+                    }
+                });
+            }
+            if let Some((points, widths, forces)) = resmoothed {
+                self.state.push_undo_snapshot();
+                if let Some(CanvasObject::Stroke(stroke)) = self
+                    .state
+                    .selected_object
+                    .and_then(|index| self.state.board.get_mut(index))
+                {
+                    stroke.points = points;
+                    stroke.widths = widths;
+                    stroke.forces = forces;
+                    stroke.invalidate_cache();
+                }
+            }
+
+            // Save selected object as stamp
+            if let Some(object) = self
+                .state
+                .selected_object
+                .and_then(|index| self.state.board.get(index))
+            {
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("保存为图章:");
+                    ui.text_edit_singleline(&mut self.state.new_stamp_name);
+                    if ui.button("保存").clicked() {
+                        let name = if self.state.new_stamp_name.trim().is_empty() {
+                            format!("图章 {}", self.state.stamps.len() + 1)
+                        } else {
+                            self.state.new_stamp_name.trim().to_owned()
+                        };
+                        self.state.stamps.push(crate::state::Stamp {
+                            name,
+                            object: object.clone(),
+                        });
+                        self.state.new_stamp_name.clear();
+                    }
+                });
+                if ui.button("导出选中为 SVG").clicked() {
+                    Self::export_svg(std::slice::from_ref(object));
+                }
+            }
+        }
+
+        // Eraser related settings
+        if self.state.current_tool == CanvasTool::ObjectEraser
+            || self.state.current_tool == CanvasTool::PixelEraser
+        {
+            ui.horizontal(|ui| {
+                ui.label("橡皮擦大小:");
+                let slider_response =
+                    ui.add(egui::Slider::new(&mut self.state.eraser_size, 5.0..=50.0));
+
+                if slider_response.dragged() || slider_response.hovered() {
+                    self.state.show_size_preview = true;
+                } else if !slider_response.dragged() && !slider_response.hovered() {
+                    self.state.show_size_preview = false;
+                }
+
+                if ui.button("清空画布").clicked() {
+                    self.state.push_undo_snapshot();
+                    self.state.board.clear();
+                    self.state.active_strokes.clear();
+                    self.state.is_drawing = false;
+                    self.state.selected_object = None;
+                    self.switch_tool(CanvasTool::Brush);
+                }
+            });
+
+            if self.state.current_tool == CanvasTool::ObjectEraser {
+                ui.horizontal(|ui| {
+                    ui.label("笔画擦除:");
+                    ui.checkbox(
+                        &mut self.state.object_eraser_split_strokes,
+                        "只擦除经过的部分",
+                    )
+                    .on_hover_text("笔画被擦到时只删去橡皮擦经过的线段，其他对象仍整体删除");
+                });
+            }
+
+            if self.state.current_tool == CanvasTool::PixelEraser {
+                ui.horizontal(|ui| {
+                    ui.label("柔和擦除:");
+                    ui.checkbox(&mut self.state.soft_eraser, "逐次淡化笔迹")
+                        .on_hover_text("每次擦过笔迹时降低其不透明度，而不是直接分割笔迹");
+                    ui.add_enabled(
+                        self.state.soft_eraser,
+                        egui::Slider::new(&mut self.state.soft_eraser_strength, 0.05..=1.0)
+                            .text("强度"),
+                    );
+                });
+            }
+
+            ui.horizontal(|ui| {
+                ui.label("动态橡皮擦大小:");
+                ui.checkbox(&mut self.state.dynamic_eraser_size, "快速移动时变大");
+            });
+
+            if self.state.dynamic_eraser_size {
+                ui.horizontal(|ui| {
+                    ui.label("缩放范围:");
+                    ui.add(
+                        egui::Slider::new(&mut self.state.dynamic_eraser_min_scale, 0.2..=1.0)
+                            .text("最小"),
+                    );
+                    ui.add(
+                        egui::Slider::new(&mut self.state.dynamic_eraser_max_scale, 1.0..=5.0)
+                            .text("最大"),
+                    );
+                });
+            }
+        }
+
+        // Stroke width tool related settings
+        if self.state.current_tool == CanvasTool::StrokeWidth {
+            ui.horizontal(|ui| {
+                ui.label("工具大小:");
+                let slider_response = ui.add(egui::Slider::new(
+                    &mut self.state.stroke_width_tool_size,
+                    5.0..=100.0,
+                ));
+
+                if slider_response.dragged() || slider_response.hovered() {
+                    self.state.show_size_preview = true;
+                } else if !slider_response.dragged() && !slider_response.hovered() {
+                    self.state.show_size_preview = false;
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("强度:");
+                ui.add(egui::Slider::new(
+                    &mut self.state.stroke_width_tool_strength,
+                    0.01..=0.3,
+                ))
+                .on_hover_text("每拖动一个工具直径的缩放比例");
+            });
+
+            ui.label("拖动经过笔迹使其变细，按住 Alt 拖动使其变粗");
+        }
+
+        // Polyline tool related settings
+        if self.state.current_tool == CanvasTool::Polyline {
+            ui.label("单击放置顶点，双击或按 Enter 完成，Backspace 删除上一个顶点，Esc 取消");
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.state.pen_smooth, "平滑为曲线")
+                    .on_hover_text("完成时按笔迹平滑次数将折线平滑为曲线");
+                if !self.state.pen_points.is_empty() {
+                    ui.label(format!("已放置 {} 个顶点", self.state.pen_points.len()));
+                }
+            });
+        }
+
+        // Fill tool related settings
+        if self.state.current_tool == CanvasTool::Fill {
+            ui.horizontal(|ui| {
+                ui.label("填充:");
+                ui.label("点击笔迹或形状围成的区域，以画笔颜色填充（填充位于所有对象下方）");
+            });
+        }
+
+        // Area measure tool related settings
+        if self.state.current_tool == CanvasTool::AreaMeasure {
+            ui.horizontal(|ui| {
+                ui.label("测量结果:");
+                match self.state.area_measurement {
+                    Some(crate::state::AreaMeasurement {
+                        area: Some(area), ..
+                    }) => {
+                        ui.label(format!("{area:.1} px²"));
+                    }
+                    Some(crate::state::AreaMeasurement { area: None, .. }) => {
+                        ui.label("所选对象未闭合");
+                    }
+                    None => {
+                        ui.label("点击封闭的笔迹或形状进行测量");
+                    }
+                }
+            });
+
+            ui.checkbox(
+                &mut self.state.area_measure_place_label,
+                "在画布上放置面积标签",
+            );
+        }
+
+        // Distance tool related settings
+        if self.state.current_tool == CanvasTool::Distance {
+            ui.horizontal(|ui| {
+                ui.label("测量结果:");
+                if let Some((start, end)) = self.state.distance_measurement {
+                    ui.label(self.distance_readout(start, end));
+                } else {
+                    ui.label("在画布上拖动进行测量（按住 Shift 吸附角度）");
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("长度单位:");
+                let mut use_cm = self.state.pixels_per_cm.is_some();
+                if ui.checkbox(&mut use_cm, "厘米").changed() {
+                    self.state.pixels_per_cm = use_cm.then_some(DEFAULT_PIXELS_PER_CM);
+                }
+                if let Some(pixels_per_cm) = &mut self.state.pixels_per_cm {
+                    ui.add(
+                        egui::DragValue::new(pixels_per_cm)
+                            .range(1.0..=500.0)
+                            .speed(0.1)
+                            .suffix(" px/cm"),
+                    )
+                    .on_hover_text("在屏幕上量出 1 厘米对应的像素数进行校准");
+                }
+            });
+
+            ui.checkbox(
+                &mut self.state.distance_place_annotation,
+                "松开后在画布上放置尺寸标注",
+            );
+        }
+
+        // Protractor tool related settings
+        if self.state.current_tool == CanvasTool::Protractor {
+            ui.horizontal(|ui| {
+                ui.label("测量结果:");
+                match self.state.protractor {
+                    Some(ProtractorMeasurement {
+                        center,
+                        first: Some(first),
+                        second: Some(second),
+                    }) => {
+                        let (_, sweep) = AppUtils::ray_sweep(center, first, second);
+                        ui.label(format!("{:.1}°", sweep.abs().to_degrees()));
+                    }
+                    Some(ProtractorMeasurement { first: None, .. }) => {
+                        ui.label("点击放置角的第一条边");
+                    }
+                    Some(_) => {
+                        ui.label("点击放置角的第二条边");
+                    }
+                    None => {
+                        ui.label("点击放置量角器（角的顶点）");
+                    }
+                }
+                if self.state.protractor.is_some() && ui.button("移除量角器").clicked() {
+                    self.state.protractor = None;
+                }
+            });
+
+            ui.checkbox(
+                &mut self.state.protractor_place_shapes,
+                "在画布上放置角的两条边与度数标签",
+            );
+        }
+
+        // Insert tool related settings
+        if self.state.current_tool == CanvasTool::Insert {
+            ui.horizontal(|ui| {
+                if ui.button("图片").clicked() {
+                    let future = async {
+                        rfd::AsyncFileDialog::new()
+                            .add_filter(
+                                "图片",
+                                &[
+                                    "png", "jpg", "jpeg", "bmp", "gif", "tiff", "pnm", "webp",
+                                    "tga", "dds", "ico", "hdr", "avif", "qoi",
+                                ],
+                            )
+                            .pick_file()
+                            .await
+                    };
+                    // if let Some(path) = rfd::FileDialog::new()
+                    //     .add_filter(
+                    //         "图片",
+                    //         &[
                     //             "png", "jpg", "jpeg", "bmp", "gif", "tiff", "pnm", "webp", "tga",
                     //             "dds", "ico", "hdr", "avif", "qoi",
                     //         ],
                     //     )
                     //     .pick_file()
                     // {
-                    if let Some(path) = futures::executor::block_on(future) {
-                        if let Ok(img) = image::open(path.path()) {
-                            let img = img.to_rgba8();
-                            let (width, height) = img.dimensions();
-                            let aspect_ratio = width as f32 / height as f32;
-
-                            let target_width = 300.0f32;
-                            let target_height = target_width / aspect_ratio;
-
-                            let ctx = ui.ctx();
-                            let texture = ctx.load_texture(
-                                "inserted_image",
-                                egui::ColorImage::from_rgba_unmultiplied(
-                                    [width as usize, height as usize],
-                                    &img,
-                                ),
-                                egui::TextureOptions::LINEAR,
-                            );
+                    if let Some(path) = futures::executor::block_on(future)
+                        && let Ok(img) = image::open(path.path())
+                    {
+                        let img = img.to_rgba8();
+                        let (width, height) = img.dimensions();
+                        let aspect_ratio = width as f32 / height as f32;
+
+                        let target_width = 300.0f32;
+                        let target_height = target_width / aspect_ratio;
+
+                        let ctx = ui.ctx();
+                        let texture = ctx.load_texture(
+                            "inserted_image",
+                            egui::ColorImage::from_rgba_unmultiplied(
+                                [width as usize, height as usize],
+                                &img,
+                            ),
+                            egui::TextureOptions::LINEAR,
+                        );
 
-                            self.state
-                                .canvas_objects
-                                .push(CanvasObject::Image(CanvasImage {
-                                    texture,
-                                    pos: Pos2::new(100.0, 100.0),
-                                    size: egui::vec2(target_width, target_height),
-                                    aspect_ratio,
-                                    marked_for_deletion: false,
-                                }));
-                        }
+                        self.state.push_undo_snapshot();
+                        self.add_object_on_grid(CanvasObject::Image(CanvasImage {
+                            texture,
+                            pos: Pos2::new(100.0, 100.0),
+                            size: egui::vec2(target_width, target_height),
+                            aspect_ratio,
+                            marked_for_deletion: false,
+                            corner_radius: 0.0,
+                            border_width: 0.0,
+                            border_color: Color32::WHITE,
+                            source: std::sync::Arc::new(img),
+                            crop: None,
+                            flip_x: false,
+                            flip_y: false,
+                        }));
                     }
                     // }
                 }
@@ -437,6 +1492,39 @@ impl App {
                 }
             });
 
+            ui.collapsing("图章", |ui| {
+                if self.state.stamps.is_empty() {
+                    ui.label("使用选择工具选中对象后可保存为图章");
+                }
+
+                let mut to_delete = None;
+                for (index, stamp) in self.state.stamps.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        let is_active = self.state.active_stamp == Some(index);
+                        if ui
+                            .selectable_label(is_active, "盖章")
+                            .on_hover_text("选中后点击画布放置")
+                            .clicked()
+                        {
+                            self.state.active_stamp = if is_active { None } else { Some(index) };
+                        }
+                        ui.text_edit_singleline(&mut stamp.name);
+                        if ui.button("删除").clicked() {
+                            to_delete = Some(index);
+                        }
+                    });
+                }
+
+                if let Some(index) = to_delete {
+                    self.state.stamps.remove(index);
+                    self.state.active_stamp = match self.state.active_stamp {
+                        Some(active) if active == index => None,
+                        Some(active) if active > index => Some(active - 1),
+                        other => other,
+                    };
+                }
+            });
+
             if self.state.show_text_dialog {
                 let content_rect = ui.ctx().available_rect();
                 let center_pos = content_rect.center();
@@ -449,19 +1537,32 @@ impl App {
                     .show(ui.ctx(), |ui| {
                         ui.horizontal(|ui| {
                             ui.label("文本内容:");
-                            ui.text_edit_singleline(&mut self.state.new_text_content);
+                            ui.add(
+                                egui::TextEdit::multiline(&mut self.state.new_text_content)
+                                    .desired_rows(3),
+                            );
                         });
+                        Self::text_style_editor(
+                            ui,
+                            &mut self.state.new_text_font,
+                            &mut self.state.new_text_bold,
+                            &mut self.state.new_text_italic,
+                        );
 
                         ui.horizontal(|ui| {
                             if ui.button("确认").clicked() {
-                                self.state
-                                    .canvas_objects
-                                    .push(CanvasObject::Text(CanvasText {
-                                        text: self.state.new_text_content.clone(),
-                                        pos: Pos2::new(100.0, 100.0),
-                                        color: Color32::WHITE,
-                                        font_size: 16.0,
-                                    }));
+                                self.state.push_undo_snapshot();
+                                self.add_object_on_grid(CanvasObject::Text(CanvasText {
+                                    text: self.state.new_text_content.clone(),
+                                    pos: Pos2::new(100.0, 100.0),
+                                    color: Color32::WHITE,
+                                    font_size: 16.0,
+                                    max_width: None,
+                                    align: TextAlign::Left,
+                                    font: self.state.new_text_font,
+                                    bold: self.state.new_text_bold,
+                                    italic: self.state.new_text_italic,
+                                }));
                                 self.state.show_text_dialog = false;
                                 self.state.new_text_content.clear();
                             }
@@ -479,94 +1580,96 @@ impl App {
                 let center_pos = content_rect.center();
 
                 egui::Window::new("插入形状")
-                    .collapsible(false)
-                    .resizable(false)
-                    .pivot(egui::Align2::CENTER_CENTER)
-                    .default_pos([center_pos.x, center_pos.y])
-                    .show(ui.ctx(), |ui| {
-                        ui.label("选择要插入的形状:");
+                        .collapsible(false)
+                        .resizable(false)
+                        .pivot(egui::Align2::CENTER_CENTER)
+                        .default_pos([center_pos.x, center_pos.y])
+                        .show(ui.ctx(), |ui| {
+                            ui.label("选择要插入的形状:");
 
-                        ui.horizontal(|ui| {
-                            if ui.button("线").clicked() {
-                                self.state
-                                    .canvas_objects
-                                    .push(CanvasObject::Shape(CanvasShape {
-                                        shape_type: CanvasShapeType::Line,
-                                        pos: Pos2::new(100.0, 100.0),
-                                        size: 100.0,
-                                        color: Color32::WHITE,
-                                        rotation: 0.0,
-                                    }));
-                                self.state.show_shape_dialog =
-                                    self.state.keep_insertion_window_open;
-                            }
+                            ui.horizontal(|ui| {
+                                for (shape_type, label) in [
+                                    (CanvasShapeType::Line, "线"),
+                                    (CanvasShapeType::Arrow, "箭头"),
+                                    (CanvasShapeType::Rectangle, "矩形"),
+                                    (CanvasShapeType::Triangle, "三角形"),
+                                    (CanvasShapeType::Circle, "圆形"),
+                                ] {
+                                    if ui.button(label).clicked() {
+                                        self.insert_shape(shape_type);
+                                    }
+                                }
+                            });
 
-                            if ui.button("箭头").clicked() {
-                                self.state
-                                    .canvas_objects
-                                    .push(CanvasObject::Shape(CanvasShape {
-                                        shape_type: CanvasShapeType::Arrow,
-                                        pos: Pos2::new(100.0, 100.0),
-                                        size: 100.0,
-                                        color: Color32::WHITE,
-                                        rotation: 0.0,
-                                    }));
-                                self.state.show_shape_dialog =
-                                    self.state.keep_insertion_window_open;
-                            }
+                            ui.horizontal(|ui| {
+                                ui.add(
+                                    egui::DragValue::new(&mut self.state.shape_polygon_sides)
+                                        .range(3..=12)
+                                        .suffix(" 边"),
+                                );
+                                if ui.button("正多边形").clicked() {
+                                    self.insert_shape(CanvasShapeType::Polygon {
+                                        sides: self.state.shape_polygon_sides,
+                                    });
+                                }
+                                ui.add(
+                                    egui::DragValue::new(&mut self.state.shape_star_points)
+                                        .range(3..=12)
+                                        .suffix(" 角"),
+                                );
+                                if ui.button("星形").clicked() {
+                                    self.insert_shape(CanvasShapeType::Star {
+                                        points: self.state.shape_star_points,
+                                    });
+                                }
+                            });
 
-                            if ui.button("矩形").clicked() {
-                                self.state
-                                    .canvas_objects
-                                    .push(CanvasObject::Shape(CanvasShape {
-                                        shape_type: CanvasShapeType::Rectangle,
-                                        pos: Pos2::new(100.0, 100.0),
-                                        size: 100.0,
-                                        color: Color32::WHITE,
-                                        rotation: 0.0,
-                                    }));
-                                self.state.show_shape_dialog =
-                                    self.state.keep_insertion_window_open;
-                            }
-                            if ui.button("三角形").clicked() {
-                                self.state
-                                    .canvas_objects
-                                    .push(CanvasObject::Shape(CanvasShape {
-                                        shape_type: CanvasShapeType::Triangle,
-                                        pos: Pos2::new(100.0, 100.0),
-                                        size: 100.0,
-                                        color: Color32::WHITE,
-                                        rotation: 0.0,
-                                    }));
-                                self.state.show_shape_dialog =
-                                    self.state.keep_insertion_window_open;
-                            }
+                            ui.add(
+                                egui::Slider::new(&mut self.state.shape_stroke_width, 1.0..=20.0)
+                                    .text("轮廓宽度"),
+                            );
 
-                            if ui.button("圆形").clicked() {
-                                self.state
-                                    .canvas_objects
-                                    .push(CanvasObject::Shape(CanvasShape {
-                                        shape_type: CanvasShapeType::Circle,
-                                        pos: Pos2::new(100.0, 100.0),
-                                        size: 100.0,
-                                        color: Color32::WHITE,
-                                        rotation: 0.0,
-                                    }));
-                                self.state.show_shape_dialog =
-                                    self.state.keep_insertion_window_open;
-                            }
-                        });
+                            ui.horizontal(|ui| {
+                                ui.label("箭头:");
+                                ui.selectable_value(
+                                    &mut self.state.arrow_heads,
+                                    ArrowHeads::End,
+                                    "单向",
+                                );
+                                ui.selectable_value(
+                                    &mut self.state.arrow_heads,
+                                    ArrowHeads::Both,
+                                    "双向",
+                                );
+                            });
 
-                        ui.horizontal(|ui| {
-                            if ui.button("取消").clicked() {
-                                self.state.show_shape_dialog = false;
-                            }
-                            ui.checkbox(&mut self.state.keep_insertion_window_open, "保持窗口开启");
+                            ui.horizontal(|ui| {
+                                ui.checkbox(&mut self.state.shape_filled, "填充")
+                                    .on_hover_text("填充封闭的形状，直线与箭头不受影响");
+                                ui.add_enabled_ui(self.state.shape_filled, |ui| {
+                                    ui.color_edit_button_srgba(&mut self.state.shape_fill_color);
+                                });
+                            });
+
+                            ui.checkbox(&mut self.state.tap_place_shapes, "点击画布放置并输入尺寸")
+                                .on_hover_text("选择形状后点击画布放置，再输入精确尺寸；Enter 确认，Esc 恢复默认尺寸");
+
+                            ui.horizontal(|ui| {
+                                if ui.button("取消").clicked() {
+                                    self.state.show_shape_dialog = false;
+                                }
+                                ui.checkbox(
+                                    &mut self.state.keep_insertion_window_open,
+                                    "保持窗口开启",
+                                );
+                            });
                         });
-                    });
             }
         }
+    }
 
+    // 设置工具的选项与状态栏
+    fn render_settings_tool(&mut self, ui: &mut egui::Ui) {
         // Settings tool related settings
         if self.state.current_tool == CanvasTool::Settings {
             ui.collapsing("外观", |ui| {
@@ -582,10 +1685,135 @@ impl App {
                     ui.selectable_value(&mut self.state.theme_mode, ThemeMode::System, "跟随系统");
                     ui.selectable_value(&mut self.state.theme_mode, ThemeMode::Light, "浅色模式");
                     ui.selectable_value(&mut self.state.theme_mode, ThemeMode::Dark, "深色模式");
+                    ui.selectable_value(&mut self.state.theme_mode, ThemeMode::Custom, "自定义");
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("坐标轴:");
+                    ui.checkbox(&mut self.state.show_axes, "显示");
+                    ui.add(
+                        egui::Slider::new(&mut self.state.axes_unit_spacing, 10.0..=200.0)
+                            .text("单位长度")
+                            .suffix(" px"),
+                    );
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("网格:");
+                    ui.checkbox(&mut self.state.grid_enabled, "显示并吸附")
+                        .on_hover_text("新插入与移动的图片、形状和文本的位置吸附到网格交点");
+                    ui.add(
+                        egui::Slider::new(&mut self.state.grid_size, 10.0..=200.0)
+                            .text("间距")
+                            .suffix(" px"),
+                    );
+                    ui.checkbox(&mut self.state.grid_snap_strokes, "笔画起点也吸附");
+                });
+
+                if self.state.show_axes {
+                    ui.horizontal(|ui| {
+                        ui.label("原点水平位置:");
+                        ui.add(egui::Slider::new(&mut self.state.axes_origin.x, 0.0..=1.0));
+                        ui.label("原点垂直位置:");
+                        ui.add(egui::Slider::new(&mut self.state.axes_origin.y, 0.0..=1.0));
+                    });
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("窗口尺寸变化时:");
+                    for (behavior, label) in [
+                        (CanvasResizeBehavior::Keep, "不调整内容"),
+                        (CanvasResizeBehavior::Center, "保持居中"),
+                        (CanvasResizeBehavior::TopLeft, "保持左上角"),
+                    ] {
+                        ui.selectable_value(
+                            &mut self.state.canvas_resize_behavior,
+                            behavior,
+                            label,
+                        );
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("工具栏位置:");
+                    for (position, label) in [
+                        (ToolbarPosition::Floating, "浮动"),
+                        (ToolbarPosition::Top, "顶部"),
+                        (ToolbarPosition::Bottom, "底部"),
+                        (ToolbarPosition::Left, "左侧"),
+                        (ToolbarPosition::Right, "右侧"),
+                    ] {
+                        ui.selectable_value(&mut self.state.toolbar_position, position, label);
+                    }
                 });
+
+                if self.state.theme_mode == ThemeMode::Custom {
+                    ui.horizontal(|ui| {
+                        ui.label("强调色:");
+                        ui.color_edit_button_srgba(&mut self.state.custom_theme.accent_color);
+                        ui.label("面板颜色:");
+                        ui.color_edit_button_srgba(&mut self.state.custom_theme.panel_color);
+                        ui.label("文字颜色:");
+                        ui.color_edit_button_srgba(&mut self.state.custom_theme.text_color);
+                        if ui.button("重置").clicked() {
+                            self.state.custom_theme = crate::state::CustomTheme::default();
+                        }
+                    });
+                }
+            });
+
+            ui.collapsing("无障碍", |ui| {
+                ui.add(
+                    egui::Slider::new(&mut self.state.accessibility.ui_scale, 0.75..=2.5)
+                        .text("界面缩放"),
+                );
+                ui.checkbox(&mut self.state.accessibility.high_contrast, "高对比度");
+                ui.checkbox(
+                    &mut self.state.accessibility.large_hit_targets,
+                    "增大点击区域",
+                );
+                if ui.button("恢复默认").clicked() {
+                    self.state.accessibility = AccessibilitySettings::default();
+                }
             });
 
             ui.collapsing("绘制", |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("最大撤销步数:");
+                    if ui
+                        .add(egui::Slider::new(&mut self.state.max_undo_steps, 1..=200))
+                        .changed()
+                    {
+                        self.state.trim_undo_stack();
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("画笔宽度范围:");
+                    let min_changed = ui
+                        .add(
+                            egui::Slider::new(&mut self.state.min_brush_width, 0.5..=10.0)
+                                .text("最小"),
+                        )
+                        .changed();
+                    let max_changed = ui
+                        .add(
+                            egui::Slider::new(
+                                &mut self.state.max_brush_width,
+                                DEFAULT_MAX_BRUSH_WIDTH..=100.0,
+                            )
+                            .text("最大"),
+                        )
+                        .on_hover_text("大尺寸触控屏上可调大，快捷宽度按上限等比例缩放")
+                        .changed();
+                    if min_changed || max_changed {
+                        self.state.brush_width = self
+                            .state
+                            .brush_width
+                            .clamp(self.state.min_brush_width, self.state.max_brush_width);
+                    }
+                });
+
                 ui.horizontal(|ui| {
                     ui.label("插值频率:");
                     ui.add(egui::Slider::new(
@@ -594,6 +1822,175 @@ impl App {
                     ));
                 });
 
+                ui.horizontal(|ui| {
+                    ui.label("笔画简化:");
+                    ui.add(
+                        egui::Slider::new(&mut self.state.simplify_epsilon, 0.0..=5.0)
+                            .text("容差")
+                            .suffix(" px"),
+                    )
+                    .on_hover_text("提交笔画时删除偏离不超过容差的点以节省内存，为 0 时不简化");
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("手掌防误触:");
+                    ui.checkbox(&mut self.state.palm_rejection, "启用").on_hover_text(
+                        "触控笔按下期间忽略手指与手掌的触点，并丢弃它们未完成的笔画（需要设备报告触控笔压力）",
+                    );
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("动态画笔宽度指示:");
+                    ui.checkbox(&mut self.state.show_dynamic_width_legend, "在工具栏中显示");
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("丢弃过短的笔画:");
+                    ui.add(
+                        egui::Slider::new(&mut self.state.min_stroke_length, 0.0..=50.0)
+                            .text("长度")
+                            .suffix(" px"),
+                    )
+                    .on_hover_text("设为 0 则保留所有笔画");
+                    ui.add(
+                        egui::Slider::new(&mut self.state.min_stroke_duration, 0.0..=0.5)
+                            .text("时长")
+                            .suffix(" 秒"),
+                    );
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("按住笔按钮画圈切换颜色:");
+                    ui.checkbox(&mut self.state.pen_color_gesture_enabled, "启用")
+                        .on_hover_text(
+                            "按住触控笔按钮（或鼠标右键）并画圈，顺时针切换到下一种快捷颜色",
+                        );
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("长按画布打开径向菜单:");
+                    ui.checkbox(&mut self.state.radial_menu_enabled, "启用");
+                    ui.add(
+                        egui::Slider::new(&mut self.state.radial_menu_hold_duration, 0.3..=2.0)
+                            .suffix(" 秒"),
+                    );
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("点击画布时显示波纹:");
+                    ui.checkbox(&mut self.state.tap_ripple_enabled, "启用");
+                    ui.color_edit_button_srgba(&mut self.state.tap_ripple_color);
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("选择锚点:");
+                    ui.add(
+                        egui::Slider::new(&mut self.state.anchor_size, 4.0..=30.0).text("显示大小"),
+                    );
+                    ui.add(
+                        egui::Slider::new(&mut self.state.anchor_hit_radius, 4.0..=50.0)
+                            .text("触控范围"),
+                    );
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("移动对象时吸附画布中心与边缘:");
+                    ui.checkbox(&mut self.state.snap_to_canvas, "启用");
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("移动对象时与其他对象对齐:");
+                    ui.checkbox(&mut self.state.snap_to_objects, "启用")
+                        .on_hover_text("边缘或中心与其他对象对齐时显示参考线并吸附");
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("移动对象时限制在画布内:");
+                    ui.checkbox(&mut self.state.keep_objects_on_canvas, "启用")
+                        .on_hover_text("至少保留对象的一部分在画布内，避免拖出画布后找不到");
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("新笔迹置于:");
+                    let old_position = self.state.stroke_insert_position;
+                    ui.selectable_value(
+                        &mut self.state.stroke_insert_position,
+                        StrokeInsertPosition::Top,
+                        "顶层",
+                    );
+                    ui.selectable_value(
+                        &mut self.state.stroke_insert_position,
+                        StrokeInsertPosition::CurrentLayer,
+                        "当前层",
+                    )
+                    .on_hover_text("置于最近选中的对象之上");
+                    ui.selectable_value(
+                        &mut self.state.stroke_insert_position,
+                        StrokeInsertPosition::Bottom,
+                        "底层",
+                    );
+                    if self.state.stroke_insert_position != old_position {
+                        self.state.board.stroke_insert_cursor = None;
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("切换工具时未完成的笔画:");
+                    ui.selectable_value(
+                        &mut self.state.tool_switch_stroke_behavior,
+                        ToolSwitchStrokeBehavior::Commit,
+                        "保留",
+                    );
+                    ui.selectable_value(
+                        &mut self.state.tool_switch_stroke_behavior,
+                        ToolSwitchStrokeBehavior::Discard,
+                        "丢弃",
+                    );
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("快速切换 (Q):");
+                    ui.selectable_value(
+                        &mut self.state.quick_toggle_mode,
+                        QuickToggleMode::Recent,
+                        "最近两个工具",
+                    );
+                    ui.selectable_value(
+                        &mut self.state.quick_toggle_mode,
+                        QuickToggleMode::Custom,
+                        "自定义",
+                    );
+                });
+
+                if self.state.quick_toggle_mode == QuickToggleMode::Custom {
+                    ui.horizontal(|ui| {
+                        for (index, tool) in self.state.quick_toggle_pair.iter_mut().enumerate() {
+                            egui::ComboBox::from_id_salt(("quick_toggle_tool", index))
+                                .selected_text(tool.label())
+                                .show_ui(ui, |ui| {
+                                    for option in CanvasTool::ALL {
+                                        ui.selectable_value(tool, option, option.label());
+                                    }
+                                });
+                        }
+                    });
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("指针离开窗口时:");
+                    ui.checkbox(&mut self.state.commit_on_pointer_leave, "立即提交笔画")
+                        .on_hover_text(
+                            "部分平台在指针移出窗口后不会结束拖动，启用后可避免笔画悬空",
+                        );
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("查找文本:");
+                    if ui.button("查找 (Ctrl+F)").clicked() {
+                        self.state.show_text_search = true;
+                    }
+                });
+
                 ui.horizontal(|ui| {
                     ui.label("快捷颜色管理:");
                     if ui.button("编辑快捷颜色").clicked() {
@@ -660,6 +2057,145 @@ impl App {
                 }
             });
 
+            ui.collapsing("替换颜色", |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("将颜色:");
+                    ui.color_edit_button_srgba(&mut self.state.replace_color_from);
+                    ui.label("替换为:");
+                    ui.color_edit_button_srgba(&mut self.state.replace_color_to);
+                });
+                ui.add(
+                    egui::Slider::new(&mut self.state.replace_color_tolerance, 0.0..=150.0)
+                        .text("容差"),
+                );
+
+                let match_count = self.state.board.count_color_matches(
+                    self.state.replace_color_from,
+                    self.state.replace_color_tolerance,
+                );
+                ui.horizontal(|ui| {
+                    ui.label(format!("匹配 {match_count} 个对象"));
+                    if ui
+                        .add_enabled(
+                            match_count > 0 && !self.state.canvas_locked,
+                            egui::Button::new("替换"),
+                        )
+                        .clicked()
+                    {
+                        self.state.push_undo_snapshot();
+                        let replaced = self.state.board.replace_color(
+                            self.state.replace_color_from,
+                            self.state.replace_color_to,
+                            self.state.replace_color_tolerance,
+                        );
+                        self.show_toast(format!("已替换 {replaced} 个对象的颜色"));
+                    }
+                });
+            });
+
+            ui.collapsing("文件", |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("画板:");
+                    if ui
+                        .button("保存")
+                        .on_hover_text("将当前页的内容与样式保存为 .smartboard 文件")
+                        .clicked()
+                    {
+                        self.save_board();
+                    }
+                    if ui
+                        .add_enabled(!self.state.canvas_locked, egui::Button::new("打开"))
+                        .on_hover_text("打开 .smartboard 文件，替换当前页的内容")
+                        .clicked()
+                    {
+                        self.open_board(ui.ctx());
+                    }
+                });
+            });
+
+            ui.collapsing("配置", |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("调色板与默认样式:");
+                    if ui.button("导出配置").clicked() {
+                        self.export_shared_config();
+                    }
+                    if ui.button("导入配置").clicked() {
+                        self.import_shared_config();
+                    }
+                });
+
+                if self.state.pending_config_import.is_some() {
+                    let content_rect = ui.ctx().available_rect();
+                    let center_pos = content_rect.center();
+
+                    egui::Window::new("导入配置")
+                        .collapsible(false)
+                        .resizable(false)
+                        .pivot(egui::Align2::CENTER_CENTER)
+                        .default_pos([center_pos.x, center_pos.y])
+                        .show(ui.ctx(), |ui| {
+                            ui.label("如何应用导入的配置？");
+                            ui.label("合并: 保留当前设置，仅追加新的快捷颜色与宽度");
+                            ui.label("替换: 使用导入的配置覆盖当前设置");
+                            ui.separator();
+
+                            ui.horizontal(|ui| {
+                                for (label, mode) in [
+                                    ("合并", ConfigImportMode::Merge),
+                                    ("替换", ConfigImportMode::Replace),
+                                ] {
+                                    if ui.button(label).clicked()
+                                        && let Some(config) =
+                                            self.state.pending_config_import.take()
+                                    {
+                                        config.apply(&mut self.state, mode);
+                                    }
+                                }
+                                if ui.button("取消").clicked() {
+                                    self.state.pending_config_import = None;
+                                }
+                            });
+                        });
+                }
+            });
+
+            ui.collapsing("导出", |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("范围:");
+                    ui.selectable_value(
+                        &mut self.state.clipboard_export_area,
+                        CanvasExportArea::FullCanvas,
+                        "整个画布",
+                    );
+                    ui.selectable_value(
+                        &mut self.state.clipboard_export_area,
+                        CanvasExportArea::ContentBounds,
+                        "仅内容区域",
+                    );
+                });
+
+                #[cfg(not(target_arch = "wasm32"))]
+                if ui.button("复制画布到剪贴板").clicked() {
+                    self.copy_canvas_to_clipboard(ui.ctx());
+                }
+
+                if ui.button("导出 PNG").clicked() {
+                    self.export_png(ui.ctx());
+                }
+
+                if ui.button("导出对象信息 (JSON)").clicked() {
+                    self.export_object_metadata();
+                }
+
+                if ui
+                    .button("导出 SVG")
+                    .on_hover_text("将当前页导出为矢量图（不包含背景）")
+                    .clicked()
+                {
+                    Self::export_svg(self.state.board.objects());
+                }
+            });
+
             ui.collapsing("性能", |ui| {
                 ui.horizontal(|ui| {
                     ui.label("窗口模式:");
@@ -749,56 +2285,36 @@ impl App {
                 // });
 
                 // Vertical sync mode selection
+                const PRESENT_MODES: [(PresentMode, &str); 6] = [
+                    (PresentMode::AutoVsync, "开 (自动) | AutoVsync"),
+                    (PresentMode::AutoNoVsync, "关 (自动) | AutoNoVsync"),
+                    (PresentMode::Fifo, "开 | Fifo"),
+                    (PresentMode::FifoRelaxed, "自适应 | FifoRelaxed"),
+                    (PresentMode::Immediate, "关 | Immediate"),
+                    (PresentMode::Mailbox, "开 (快速) | Mailbox"),
+                ];
                 ui.horizontal(|ui| {
                     ui.label("垂直同步:");
-                    let old_present_mode = self.state.present_mode;
-                    let present_mode_changed = ui
-                        .selectable_value(
-                            &mut self.state.present_mode,
-                            PresentMode::AAutoVsync,
-                            "开 (自动) | AutoVsync",
-                        )
-                        .changed()
-                        || ui
-                            .selectable_value(
-                                &mut self.state.present_mode,
-                                PresentMode::AutoNoVsync,
-                                "关 (自动) | AutoNoVsync",
-                            )
-                            .changed()
-                        || ui
-                            .selectable_value(
-                                &mut self.state.present_mode,
-                                PresentMode::Fifo,
-                                "开 | Fifo",
-                            )
-                            .changed()
-                        || ui
-                            .selectable_value(
-                                &mut self.state.present_mode,
-                                PresentMode::FifoRelaxed,
-                                "自适应 | FifoRelaxed",
-                            )
-                            .changed()
-                        || ui
-                            .selectable_value(
-                                &mut self.state.present_mode,
-                                PresentMode::Immediate,
-                                "关 | Immediate",
-                            )
+                    for (mode, label) in PRESENT_MODES {
+                        if ui
+                            .selectable_value(&mut self.state.present_mode, mode, label)
                             .changed()
-                        || ui
-                            .selectable_value(
-                                &mut self.state.present_mode,
-                                PresentMode::Mailbox,
-                                "开 (快速) | Mailbox",
-                            )
-                            .changed();
-
-                    if present_mode_changed && self.state.present_mode != old_present_mode {
-                        self.state.present_mode_changed = true;
+                        {
+                            self.state.present_mode_changed = true;
+                        }
                     }
                 });
+                if self.state.present_mode != self.state.active_present_mode {
+                    let active = PRESENT_MODES
+                        .iter()
+                        .find(|(mode, _)| *mode == self.state.active_present_mode)
+                        .map_or("", |(_, label)| *label);
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        format!("当前使用: {active}，重启后生效"),
+                    )
+                    .on_hover_text("驱动不支持所选模式时，下次启动会改用默认模式");
+                }
 
                 ui.horizontal(|ui| {
                     ui.label("渲染更新模式:");
@@ -826,36 +2342,21 @@ impl App {
                 });
 
                 ui.horizontal(|ui| {
-                    ui.label("压力测试:");
-                    if ui.button("OK").clicked() {
-                        let stress_color = Color32::from_rgb(255, 0, 0);
-                        let stress_width = 3.0;
-
-                        for i in 0..1000 {
-                            let mut points = Vec::new();
-                            let mut widths = Vec::new();
-
-                            let num_points = 100;
-
-                            let start_x = (i as f32 % 20.0) * 50.0;
-                            let start_y = ((i as f32 / 20.0).floor() % 15.0) * 50.0;
-
-                            for j in 0..num_points {
-                                let x = start_x + (j as f32 * 10.0);
-                                let y = start_y + (j as f32 * 5.0);
-
-                                points.push(Pos2::new(x, y));
-                                widths.push(stress_width);
-                            }
+                    ui.label("显示状态栏:");
+                    ui.checkbox(&mut self.state.show_status_bar, "启用");
+                });
 
-                            let stroke = crate::state::CanvasStroke {
-                                points,
-                                widths,
-                                color: stress_color,
-                                base_width: stress_width,
-                            };
+                ui.horizontal(|ui| {
+                    ui.label("显示笔画宽度热力图:");
+                    ui.checkbox(&mut self.state.show_stroke_width_heatmap, "启用")
+                        .on_hover_text("蓝色表示比基础宽度细，红色表示比基础宽度粗");
+                });
 
-                            self.state.canvas_objects.push(CanvasObject::Stroke(stroke));
+                ui.horizontal(|ui| {
+                    ui.label("压力测试:");
+                    if ui.button("OK").clicked() {
+                        for stroke in AppUtils::stress_test_strokes() {
+                            self.state.board.add_object(CanvasObject::Stroke(stroke));
                         }
                     }
                 });
@@ -865,92 +2366,264 @@ impl App {
         ui.separator();
 
         ui.horizontal(|ui| {
-            if ui.button("退出").clicked() {
-                ui.ctx().send_viewport_cmd(ViewportCommand::Close);
+            ui.label("页面:");
+            let page_count = self.state.pages.len();
+            if ui
+                .add_enabled(self.state.current_page > 0, egui::Button::new("上一页"))
+                .clicked()
+            {
+                self.switch_page(self.state.current_page - 1);
             }
-            if self.state.show_fps {
-                ui.label(format!(
-                    "FPS: {}",
-                    self.state.fps_counter.current_fps.to_string()
-                ));
+            ui.label(format!("{} / {}", self.state.current_page + 1, page_count));
+            if ui
+                .add_enabled(
+                    self.state.current_page + 1 < page_count,
+                    egui::Button::new("下一页"),
+                )
+                .clicked()
+            {
+                self.switch_page(self.state.current_page + 1);
+            }
+            if ui.button("新建页").clicked() {
+                self.add_page();
             }
+            ui.toggle_value(&mut self.state.show_page_overview, "页面总览");
         });
-    }
-
-    fn render_canvas(&mut self, ui: &mut egui::Ui) {
-        let (rect, response) =
-            ui.allocate_exact_size(ui.available_size(), egui::Sense::click_and_drag());
 
-        let painter = ui.painter();
-
-        // Draw background
-        painter.rect_filled(rect, 0.0, self.state.background_color);
+        ui.separator();
 
-        // Draw all objects
-        for (i, object) in self.state.canvas_objects.iter().enumerate() {
-            let selected = self.state.selected_object == Some(i);
+        ui.horizontal(|ui| {
+            if ui
+                .toggle_value(&mut self.state.canvas_locked, "锁定画布")
+                .on_hover_text("锁定后画布只读，无法绘制、擦除、移动或插入对象")
+                .changed()
+                && self.state.canvas_locked
+            {
+                self.finish_active_strokes(self.state.tool_switch_stroke_behavior);
+                self.state.selected_object = None;
+                self.state.selected_objects.clear();
+                self.state.pen_points.clear();
+            }
+            if ui
+                .button("重置视图")
+                .on_hover_text(format!("当前缩放: {:.0}%", self.state.zoom * 100.0))
+                .clicked()
+            {
+                self.state.pan_offset = egui::Vec2::ZERO;
+                self.state.zoom = 1.0;
+            }
+            let editable = !self.state.canvas_locked && !self.state.is_drawing;
+            if ui
+                .add_enabled(
+                    editable && !self.state.undo_stack.is_empty(),
+                    egui::Button::new("撤销"),
+                )
+                .on_hover_text("Ctrl+Z")
+                .clicked()
+            {
+                self.undo();
+            }
+            if ui
+                .add_enabled(
+                    editable && !self.state.redo_stack.is_empty(),
+                    egui::Button::new("重做"),
+                )
+                .on_hover_text("Ctrl+Y")
+                .clicked()
+            {
+                self.redo();
+            }
+            if ui.button("退出").clicked() {
+                ui.ctx().send_viewport_cmd(ViewportCommand::Close);
+            }
+            if self.state.show_fps {
+                ui.label(format!(
+                    "FPS: {:.0} ({:.1}ms) | 绘制: {} / {}",
+                    self.state.fps_counter.current_fps,
+                    self.state.fps_counter.frame_time_ms,
+                    self.state.drawn_object_count,
+                    self.state.board.len()
+                ));
+            }
+        });
+
+        if self.state.show_status_bar {
+            let stats = CanvasStats::from_objects(self.state.board.objects());
+            ui.label(format!(
+                "笔画: {} | 形状: {} | 文本: {} | 图片: {} | 点: {}",
+                stats.strokes, stats.shapes, stats.texts, stats.images, stats.points
+            ));
+        }
+    }
+
+    fn render_canvas(&mut self, ui: &mut egui::Ui) {
+        let (screen_rect, response) =
+            ui.allocate_exact_size(ui.available_size(), egui::Sense::click_and_drag());
+
+        self.handle_canvas_resize(screen_rect);
+
+        let panning = self.handle_view_navigation(ui, &response);
+        let view = self.state.view_transform();
+        // 画布坐标系下的可见区域
+        let rect = view.inverse() * screen_rect;
+
+        // 背景与界面提示直接绘制在屏幕上，画布内容绘制在应用视图变换的图层上
+        let screen_painter = ui.painter();
+        let content_layer = egui::LayerId::new(ui.layer_id().order, ui.id().with("canvas_content"));
+        ui.ctx().set_sublayer(ui.layer_id(), content_layer);
+        ui.ctx().set_transform_layer(content_layer, view);
+        let painter = &ui
+            .painter()
+            .clone()
+            .with_layer_id(content_layer)
+            .with_clip_rect(rect);
+
+        // Draw background
+        screen_painter.rect_filled(screen_rect, 0.0, self.state.background_color);
+
+        // Trace reference image (behind objects)
+        if let Some(reference) = &self.state.trace_reference {
+            painter.image(
+                reference.texture.id(),
+                reference.rect,
+                egui::Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0)),
+                Color32::from_white_alpha(160),
+            );
+        }
+
+        // 背景网格（位于对象之下）
+        if self.state.grid_enabled {
+            AppUtils::draw_grid(
+                painter,
+                rect,
+                self.state.grid_size,
+                view.scaling,
+                AppUtils::contrast_color(self.state.background_color).gamma_multiply(0.15),
+            );
+        }
+
+        // Coordinate axes overlay (behind objects)
+        // 原点按未平移缩放时的画布区域确定，平移或缩放视图时坐标轴与笔迹保持对齐
+        if self.state.show_axes {
+            AppUtils::draw_axes(
+                painter,
+                rect,
+                screen_rect.min + screen_rect.size() * self.state.axes_origin,
+                self.state.axes_unit_spacing,
+                AppUtils::contrast_color(self.state.background_color),
+            );
+        }
+
+        // Draw all objects（跳过包围盒与可见区域不相交的对象）
+        let mut drawn_object_count = 0;
+        for (i, object) in self.state.board.objects().iter().enumerate() {
+            if !AppUtils::calculate_object_bounding_box(painter, object).intersects(rect) {
+                continue;
+            }
+            let selected = self.state.selected_object == Some(i);
             object.draw(painter, selected);
+            drawn_object_count += 1;
         }
+        self.state.drawn_object_count = drawn_object_count;
 
         // Draw currently drawing strokes
         for (_touch_id, active_stroke) in &self.state.active_strokes {
+            let active_stroke_color = if self.state.correction_pen {
+                self.state.background_color
+            } else {
+                self.brush_style(active_stroke.input_type).0
+            };
             if active_stroke.points.len() >= 2
                 && active_stroke.widths.len() == active_stroke.points.len()
             {
+                let points = if self.state.live_stroke_smoothing {
+                    AppUtils::smooth_trailing_points(
+                        &active_stroke.points,
+                        self.state.live_smoothing_window,
+                    )
+                } else {
+                    active_stroke.points.clone()
+                };
                 let all_same_width = active_stroke
                     .widths
                     .windows(2)
                     .all(|w| (w[0] - w[1]).abs() < 0.01);
+                // 较宽的笔画与已提交的笔画一样使用网格绘制
+                let uniform_width = active_stroke
+                    .widths
+                    .first()
+                    .copied()
+                    .filter(|width| all_same_width && *width <= AppUtils::WIDE_STROKE_WIDTH);
 
-                if all_same_width && active_stroke.points.len() == 2 {
-                    painter.line_segment(
-                        [active_stroke.points[0], active_stroke.points[1]],
-                        Stroke::new(active_stroke.widths[0], self.state.brush_color),
-                    );
-                } else if all_same_width {
+                if let (Some(width), &[a, b]) = (uniform_width, points.as_slice()) {
+                    painter.line_segment([a, b], Stroke::new(width, active_stroke_color));
+                } else if let Some(width) = uniform_width {
                     let path = egui::epaint::PathShape::line(
-                        active_stroke.points.clone(),
-                        Stroke::new(active_stroke.widths[0], self.state.brush_color),
+                        points,
+                        Stroke::new(width, active_stroke_color),
                     );
                     painter.add(Shape::Path(path));
                 } else {
-                    for i in 0..active_stroke.points.len() - 1 {
-                        let avg_width =
-                            (active_stroke.widths[i] + active_stroke.widths[i + 1]) / 2.0;
-                        painter.line_segment(
-                            [active_stroke.points[i], active_stroke.points[i + 1]],
-                            Stroke::new(avg_width, self.state.brush_color),
-                        );
-                    }
+                    painter.add(AppUtils::build_stroke_mesh(
+                        &points,
+                        &active_stroke.widths,
+                        active_stroke_color,
+                    ));
                 }
             }
         }
 
+        // 直尺位于所有内容之上
+        if let Some((anchor, angle)) = self.state.ruler {
+            let handle_hovered = self.state.ruler_drag == Some(RulerDrag::Rotate)
+                || response.hover_pos().is_some_and(|pos| {
+                    (view.inverse() * pos).distance(AppUtils::ruler_handle_position(anchor, angle))
+                        <= AppUtils::RULER_HANDLE_RADIUS
+                });
+            AppUtils::draw_ruler(painter, anchor, angle, view.scaling, handle_hovered);
+        }
+
         // Draw size preview circle
         if self.state.show_size_preview {
             let content_rect = ui.ctx().available_rect();
             let pos = content_rect.center();
+            let (size, style) = match self.state.current_tool {
+                CanvasTool::Brush => (
+                    self.state.brush_width,
+                    SizePreviewStyle::Brush(if self.state.correction_pen {
+                        self.state.background_color
+                    } else {
+                        self.state.brush_color
+                    }),
+                ),
+                CanvasTool::ObjectEraser | CanvasTool::PixelEraser => {
+                    (self.state.eraser_size, SizePreviewStyle::Eraser)
+                }
+                CanvasTool::StrokeWidth => {
+                    (self.state.stroke_width_tool_size, SizePreviewStyle::Neutral)
+                }
+                _ => (10.0, SizePreviewStyle::Neutral), // fallback
+            };
+            // 宽笔画放大后可能超出画面，预览圆最大占可见区域短边的 80%
+            let max_size = content_rect.size().min_elem() * 0.8;
             AppUtils::draw_size_preview(
-                painter,
+                screen_painter,
                 pos,
-                match self.state.current_tool {
-                    CanvasTool::Brush => self.state.brush_width,
-                    CanvasTool::ObjectEraser | CanvasTool::PixelEraser => self.state.eraser_size,
-                    _ => 10.0, // fallback
-                },
+                (size * self.state.zoom).min(max_size),
+                style,
             );
         }
 
         if self.state.show_touch_points {
             for (id, pos) in &self.state.touch_points {
-                painter.circle_filled(
+                screen_painter.circle_filled(
                     *pos,
                     15.0,
                     Color32::from_rgba_unmultiplied(255, 255, 255, 180),
                 );
-                painter.circle_stroke(*pos, 15.0, Stroke::new(2.0, Color32::BLUE));
+                screen_painter.circle_stroke(*pos, 15.0, Stroke::new(2.0, Color32::BLUE));
 
-                let text_galley = painter.layout_no_wrap(
+                let text_galley = screen_painter.layout_no_wrap(
                     format!("{}", id),
                     egui::FontId::proportional(14.0),
                     Color32::BLACK,
@@ -968,108 +2641,400 @@ impl App {
                     fallback_color: Color32::BLACK,
                     opacity_factor: 1.0,
                 };
-                painter.add(text_shape);
+                screen_painter.add(text_shape);
+            }
+        }
+
+        // Preview re-smoothing of the selected stroke
+        if self.state.show_smoothing_preview
+            && self.state.current_tool == CanvasTool::Select
+            && let Some(CanvasObject::Stroke(stroke)) = self
+                .state
+                .selected_object
+                .and_then(|index| self.state.board.get(index))
+        {
+            let preview =
+                AppUtils::apply_stroke_smoothing(&stroke.points, self.state.smoothing_iterations);
+            painter.add(Shape::line(
+                preview,
+                Stroke::new(
+                    stroke.base_width,
+                    Color32::from_rgba_unmultiplied(255, 140, 0, 160),
+                ),
+            ));
+        }
+
+        // Debug: stroke width heatmap
+        if self.state.show_stroke_width_heatmap {
+            for object in self.state.board.objects() {
+                if let CanvasObject::Stroke(stroke) = object {
+                    AppUtils::draw_stroke_width_heatmap(painter, stroke);
+                }
+            }
+        }
+
+        // Highlight text search matches
+        if self.state.show_text_search {
+            for index in AppUtils::find_text_matches(
+                self.state.board.objects(),
+                &self.state.text_search_query,
+            ) {
+                if let Some(object) = self.state.board.get(index) {
+                    let match_rect = AppUtils::calculate_object_bounding_box(painter, object);
+                    painter.rect_filled(
+                        match_rect.expand(2.0),
+                        2.0,
+                        Color32::from_rgba_unmultiplied(255, 255, 0, 60),
+                    );
+                }
+            }
+        }
+
+        // Highlight measured area
+        if self.state.current_tool == CanvasTool::AreaMeasure
+            && let Some(measurement) = self.state.area_measurement
+            && let Some(object) = self.state.board.get(measurement.object_index)
+        {
+            let highlight = Stroke::new(2.0, Color32::from_rgb(0, 160, 255));
+            if let Some(polygon) = AppUtils::closed_object_polygon(object) {
+                painter.add(Shape::closed_line(polygon, highlight));
+            } else {
+                let object_rect = AppUtils::calculate_object_bounding_box(painter, object);
+                painter.rect_stroke(object_rect, 0.0, highlight, egui::StrokeKind::Outside);
+            }
+        }
+
+        // 距离测量：两端画出短竖线，并在中点旁显示长度与角度
+        if self.state.current_tool == CanvasTool::Distance
+            && let Some((start, end)) = self.state.distance_measurement
+        {
+            let color = Color32::from_rgb(255, 120, 0);
+            let stroke = Stroke::new(2.0 / view.scaling, color);
+            let tick = (end - start).normalized().rot90() * (8.0 / view.scaling);
+            painter.line_segment([start, end], stroke);
+            for point in [start, end] {
+                painter.line_segment([point - tick, point + tick], stroke);
             }
+            Self::draw_readout(
+                screen_painter,
+                view * start.lerp(end, 0.5) + egui::vec2(12.0, 0.0),
+                self.distance_readout(start, end),
+            );
+        }
+
+        // 量角器与正在测量的角（尚未放置的射线跟随指针）
+        if self.state.current_tool == CanvasTool::Protractor
+            && let Some(measurement) = self.state.protractor
+        {
+            let hover = response.hover_pos().map(|pos| view.inverse() * pos);
+            let rays = match (measurement.first, measurement.second) {
+                (None, _) => [hover, None],
+                (Some(first), None) => [Some(first), hover],
+                (Some(first), Some(second)) => [Some(first), Some(second)],
+            };
+            AppUtils::draw_protractor(painter, measurement.center, view.scaling);
+            AppUtils::draw_angle_measurement(painter, measurement.center, rays, view.scaling);
+        }
+
+        // Tap ripples (presentation aid, not stored on the canvas)
+        if self.state.tap_ripple_enabled
+            && response.hovered()
+            && ui.input(|i| i.pointer.any_pressed())
+            && let Some(pos) = ui.input(|i| i.pointer.interact_pos())
+        {
+            self.state.tap_ripples.push((pos, Instant::now()));
+        }
+        self.draw_tap_ripples(ui, screen_painter);
+
+        // Draw alignment guides
+        for guide in &self.state.snap_guides {
+            painter.line_segment(*guide, Stroke::new(1.0, Color32::from_rgb(255, 0, 255)));
+        }
+        for guide in &self.state.object_guides {
+            painter.line_segment(*guide, Stroke::new(1.0, Color32::from_rgb(255, 140, 0)));
+        }
+
+        // 多选对象的整体边界
+        if let Some(bounds) = self.selection_bounds(painter) {
+            painter.rect_stroke(
+                bounds.expand(4.0),
+                0.0,
+                Stroke::new(2.0, Color32::from_rgb(0, 160, 255)),
+                egui::StrokeKind::Outside,
+            );
         }
 
         // Draw resize and rotation anchors
-        if let Some(selected_idx) = self.state.selected_object {
-            if let Some(object) = self.state.canvas_objects.get(selected_idx) {
-                let object_rect = match object {
-                    CanvasObject::Image(img) => egui::Rect::from_min_size(img.pos, img.size),
-                    CanvasObject::Text(text) => {
-                        let text_galley = painter.layout_no_wrap(
-                            text.text.clone(),
-                            egui::FontId::proportional(text.font_size),
-                            text.color,
-                        );
-                        let text_size = text_galley.size();
-                        egui::Rect::from_min_size(text.pos, text_size)
-                    }
-                    CanvasObject::Shape(shape) => AppUtils::calculate_shape_bounding_box(shape),
-                    CanvasObject::Stroke(_) => {
-                        return;
-                    }
+        if let Some((start, end)) = self.selected_line_endpoints() {
+            for (endpoint, pos) in [(ShapeEndpoint::Start, start), (ShapeEndpoint::End, end)] {
+                let color = if self.state.endpoint_hovered == Some(endpoint) {
+                    Color32::YELLOW
+                } else {
+                    Color32::WHITE
+                };
+                painter.circle_filled(pos, self.state.anchor_size, color);
+                painter.circle_stroke(
+                    pos,
+                    self.state.anchor_size,
+                    Stroke::new(2.0, Color32::BLACK),
+                );
+            }
+
+            // 拖动端点时在该端点旁显示直线的角度
+            if let Some(endpoint) = self.state.endpoint_operation {
+                let pos = match endpoint {
+                    ShapeEndpoint::Start => start,
+                    ShapeEndpoint::End => end,
                 };
+                Self::draw_angle_readout(
+                    screen_painter,
+                    view * pos + egui::vec2(self.state.anchor_size * view.scaling + 8.0, 0.0),
+                    (end - start).angle(),
+                );
+            }
+        } else if let Some(selected_idx) = self.state.selected_object
+            && let Some(object) = self.state.board.get(selected_idx)
+        {
+            let object_rect = match object {
+                CanvasObject::Image(img) => egui::Rect::from_min_size(img.pos, img.size),
+                CanvasObject::Text(text) => {
+                    let text_size = painter.text_size(text);
+                    egui::Rect::from_min_size(text.pos, text_size)
+                }
+                CanvasObject::Shape(shape) => AppUtils::calculate_shape_bounding_box(shape),
+                CanvasObject::Stroke(_) => {
+                    return;
+                }
+            };
+
+            AppUtils::draw_resize_and_rotation_anchors(
+                painter,
+                object_rect,
+                self.state.anchor_size,
+                self.state.resize_anchor_hovered,
+                self.state.rotation_anchor_hovered,
+            );
+
+            // 旋转时标出旋转中心，并在旋转锚点旁显示当前角度
+            if let Some(rotate_op) = self.state.rotation_operation {
+                painter.circle_filled(rotate_op.pivot, 4.0, Color32::YELLOW);
+                painter.circle_stroke(rotate_op.pivot, 4.0, Stroke::new(1.0, Color32::BLACK));
+                if let CanvasObject::Shape(shape) = object {
+                    let anchor = AppUtils::rotation_anchor_position(object_rect);
+                    Self::draw_angle_readout(
+                        screen_painter,
+                        view * anchor
+                            + egui::vec2(self.state.anchor_size * view.scaling + 8.0, 0.0),
+                        shape.rotation,
+                    );
+                }
+            }
+        }
+
+        // 裁剪框：压暗图片上将被裁掉的部分
+        if let Some(crop) = self.state.crop_operation
+            && let Some(CanvasObject::Image(img)) = self.state.board.get(crop.index)
+        {
+            let image_rect = egui::Rect::from_min_size(img.pos, img.size);
+            let keep = crop
+                .rect
+                .map_or(image_rect, |rect| rect.intersect(image_rect));
+            let shade = Color32::from_black_alpha(140);
+            for part in [
+                egui::Rect::from_min_max(image_rect.min, Pos2::new(image_rect.max.x, keep.min.y)),
+                egui::Rect::from_min_max(Pos2::new(image_rect.min.x, keep.max.y), image_rect.max),
+                egui::Rect::from_min_max(
+                    Pos2::new(image_rect.min.x, keep.min.y),
+                    Pos2::new(keep.min.x, keep.max.y),
+                ),
+                egui::Rect::from_min_max(
+                    Pos2::new(keep.max.x, keep.min.y),
+                    Pos2::new(image_rect.max.x, keep.max.y),
+                ),
+            ] {
+                if part.is_positive() {
+                    painter.rect_filled(part, 0.0, shade);
+                }
+            }
+            if keep.is_positive() {
+                painter.rect_stroke(
+                    keep,
+                    0.0,
+                    Stroke::new(2.0, Color32::WHITE),
+                    egui::StrokeKind::Inside,
+                );
+            }
+        }
+
+        // 锁定画布时显示提示边框
+        if self.state.canvas_locked {
+            let lock_color = Color32::from_rgb(255, 140, 0);
+            screen_painter.rect_stroke(
+                screen_rect,
+                0.0,
+                Stroke::new(4.0, lock_color),
+                egui::StrokeKind::Inside,
+            );
+            screen_painter.text(
+                screen_rect.right_top() + egui::vec2(-12.0, 12.0),
+                egui::Align2::RIGHT_TOP,
+                "画布已锁定",
+                egui::FontId::proportional(18.0),
+                lock_color,
+            );
+        }
+
+        if panning {
+            return;
+        }
 
-                AppUtils::draw_resize_and_rotation_anchors(
-                    &painter,
-                    object_rect,
-                    self.state.resize_anchor_hovered,
-                    self.state.rotation_anchor_hovered,
+        // Handle mouse input（转换到画布坐标）
+        let pointer_pos = response
+            .interact_pointer_pos()
+            .map(|pos| view.inverse() * pos);
+
+        // Pen button circular gesture to cycle quick colors
+        if self.handle_color_gesture(ui, screen_painter, &response) {
+            return;
+        }
+
+        // Press-and-hold radial menu
+        if self.handle_radial_menu(ui, screen_painter, &response) {
+            return;
+        }
+
+        if self.handle_ruler_drag(ui, &response, pointer_pos, view.scaling) {
+            return;
+        }
+
+        // 吸管：悬停时在指针旁预览颜色，单击吸取颜色（不透明度由画笔设置决定）
+        if self.state.eyedropper_active {
+            if let (Some(pos), Some(screen_pos)) = (pointer_pos, response.hover_pos()) {
+                let color = self
+                    .state
+                    .board
+                    .color_at(pos, painter)
+                    .unwrap_or(self.state.background_color);
+                let swatch = egui::Rect::from_min_size(
+                    screen_pos + egui::vec2(16.0, 16.0),
+                    egui::vec2(24.0, 24.0),
+                );
+                screen_painter.rect(
+                    swatch,
+                    4.0,
+                    color,
+                    Stroke::new(1.0, AppUtils::contrast_color(self.state.background_color)),
+                    egui::StrokeKind::Outside,
                 );
+
+                if response.clicked() {
+                    let [r, g, b, _] = color.to_srgba_unmultiplied();
+                    self.state.brush_color = Color32::from_rgb(r, g, b);
+                    self.state.eyedropper_active = false;
+                }
             }
+            return;
+        }
+
+        // 锁定画布时只响应不修改内容的工具
+        if self.state.canvas_locked
+            && !matches!(
+                self.state.current_tool,
+                CanvasTool::Settings
+                    | CanvasTool::AreaMeasure
+                    | CanvasTool::Distance
+                    | CanvasTool::Protractor
+            )
+        {
+            return;
         }
 
-        // Handle mouse input
-        let pointer_pos = response.interact_pointer_pos();
+        // 擦除与粗细调整在每次按下时记录一次撤销快照
+        if (response.drag_started() || response.clicked())
+            && matches!(
+                self.state.current_tool,
+                CanvasTool::ObjectEraser | CanvasTool::PixelEraser | CanvasTool::StrokeWidth
+            )
+        {
+            self.state.push_undo_snapshot();
+        }
 
         match self.state.current_tool {
-            CanvasTool::Insert | CanvasTool::Settings => {}
+            CanvasTool::Settings => {}
+
+            CanvasTool::Insert => {
+                // 点击放置形状，并打开尺寸输入
+                if response.clicked()
+                    && let Some(pos) = pointer_pos
+                    && let Some(shape_type) = self.state.pending_shape_placement.take()
+                {
+                    self.place_shape_at(shape_type, pos);
+                    return;
+                }
+
+                // 盖章：以点击位置为中心放置图章对象的副本
+                if response.clicked()
+                    && let Some(pos) = pointer_pos
+                    && let Some(stamp) = self
+                        .state
+                        .active_stamp
+                        .and_then(|index| self.state.stamps.get(index))
+                {
+                    let mut object = stamp.object.clone();
+                    let center = AppUtils::calculate_object_bounding_box(painter, &object).center();
+                    object.translate(pos - center);
+                    self.state.push_undo_snapshot();
+                    self.add_object_on_grid(object);
+                }
+            }
 
             CanvasTool::Select => {
-                if let Some(pos) = pointer_pos {
-                    self.state.drag_start_pos = Some(pos);
-
-                    let mut hit = false;
-                    for object in &self.state.canvas_objects {
-                        if let CanvasObject::Image(img) = object {
-                            if egui::Rect::from_min_size(img.pos, img.size).contains(pos) {
-                                hit = true;
-                                break;
-                            }
-                        }
-                    }
-                    if !hit {
-                        for object in &self.state.canvas_objects {
-                            if let CanvasObject::Stroke(stroke) = object {
-                                if AppUtils::point_intersects_stroke(pos, stroke, 10.0) {
-                                    hit = true;
-                                    break;
-                                }
-                            }
+                // 裁剪图片：拖动选择保留的区域（应用与取消在选择工具面板中）
+                if let Some(crop) = &mut self.state.crop_operation {
+                    if let Some(pos) = pointer_pos {
+                        if response.drag_started() {
+                            crop.start = Some(pos);
+                            crop.rect = None;
+                        } else if response.dragged()
+                            && let Some(start) = crop.start
+                        {
+                            crop.rect = Some(egui::Rect::from_two_pos(start, pos));
                         }
                     }
-                    if !hit {
+                    return;
+                }
+
+                // 双击文本对象进入编辑
+                if response.double_clicked()
+                    && let Some(pos) = pointer_pos
+                    && let Some(index) = self.state.board.hit_test(pos, painter)
+                    && let Some(CanvasObject::Text(text)) = self.state.board.get(index)
+                {
+                    self.state.selected_object = Some(index);
+                    self.state.editing_text = Some(index);
+                    self.state.editing_text_draft = Some(text.clone());
+                    return;
+                }
+
+                if let Some(pos) = pointer_pos {
+                    if !self.state.board.over_image_or_stroke(pos) {
                         self.state.selected_object = None;
                     }
 
                     if let Some(selected_idx) = self.state.selected_object {
-                        if let Some(object) = self.state.canvas_objects.get(selected_idx) {
-                            let object_rect = match object {
-                                CanvasObject::Image(img) => {
-                                    Some(egui::Rect::from_min_size(img.pos, img.size))
-                                }
-                                CanvasObject::Text(text) => {
-                                    let text_galley = painter.layout_no_wrap(
-                                        text.text.clone(),
-                                        egui::FontId::proportional(text.font_size),
-                                        text.color,
-                                    );
-                                    let text_size = text_galley.size();
-                                    Some(egui::Rect::from_min_size(text.pos, text_size))
-                                }
-                                CanvasObject::Shape(shape) => {
-                                    Some(AppUtils::calculate_shape_bounding_box(shape))
-                                }
-                                CanvasObject::Stroke(_) => None,
-                            };
+                        if let Some(object) = self.state.board.get(selected_idx) {
+                            let object_rect = Board::handle_rect(object, painter);
 
                             if let Some(rect) = object_rect {
-                                let resize_anchors = [
-                                    (ResizeAnchor::TopLeft, rect.left_top()),
-                                    (ResizeAnchor::TopRight, rect.right_top()),
-                                    (ResizeAnchor::BottomLeft, rect.left_bottom()),
-                                    (ResizeAnchor::BottomRight, rect.right_bottom()),
-                                    (ResizeAnchor::Top, Pos2::new(rect.center().x, rect.min.y)),
-                                    (ResizeAnchor::Bottom, Pos2::new(rect.center().x, rect.max.y)),
-                                    (ResizeAnchor::Left, Pos2::new(rect.min.x, rect.center().y)),
-                                    (ResizeAnchor::Right, Pos2::new(rect.max.x, rect.center().y)),
-                                ];
+                                // 触控范围不小于锚点的显示大小
+                                let hit_radius =
+                                    self.state.anchor_hit_radius.max(self.state.anchor_size);
 
                                 let mut found_resize_anchor = None;
-                                for (anchor_type, anchor_pos) in resize_anchors {
-                                    if pos.distance(anchor_pos) <= 15.0 {
+                                for (anchor_type, anchor_pos) in
+                                    AppUtils::resize_anchor_positions(rect)
+                                {
+                                    if pos.distance(anchor_pos) <= hit_radius {
                                         found_resize_anchor = Some(anchor_type);
                                         break;
                                     }
@@ -1077,10 +3042,9 @@ impl App {
 
                                 self.state.resize_anchor_hovered = found_resize_anchor;
 
-                                let rotation_anchor_pos =
-                                    Pos2::new(rect.center().x, rect.min.y - 30.0);
+                                let rotation_anchor_pos = AppUtils::rotation_anchor_position(rect);
                                 self.state.rotation_anchor_hovered =
-                                    pos.distance(rotation_anchor_pos) <= 15.0;
+                                    pos.distance(rotation_anchor_pos) <= hit_radius;
                             } else {
                                 self.state.resize_anchor_hovered = None;
                                 self.state.rotation_anchor_hovered = false;
@@ -1094,79 +3058,79 @@ impl App {
                         self.state.rotation_anchor_hovered = false;
                     }
 
+                    // 直线与箭头只显示端点手柄，拖动端点代替调整大小与旋转
+                    self.state.endpoint_hovered = None;
+                    if let Some((start, end)) = self.selected_line_endpoints() {
+                        let hit_radius = self.state.anchor_hit_radius.max(self.state.anchor_size);
+                        self.state.resize_anchor_hovered = None;
+                        self.state.rotation_anchor_hovered = false;
+                        if pos.distance(start) <= hit_radius {
+                            self.state.endpoint_hovered = Some(ShapeEndpoint::Start);
+                        } else if pos.distance(end) <= hit_radius {
+                            self.state.endpoint_hovered = Some(ShapeEndpoint::End);
+                        }
+                    }
+
                     if response.drag_started() {
                         if let Some(pos) = pointer_pos {
                             self.state.drag_start_pos = Some(pos);
 
-                            let mut hit = false;
-                            for object in &self.state.canvas_objects {
-                                if let CanvasObject::Image(img) = object {
-                                    if egui::Rect::from_min_size(img.pos, img.size).contains(pos) {
-                                        hit = true;
-                                        break;
-                                    }
-                                }
-                            }
-                            if !hit {
-                                for object in &self.state.canvas_objects {
-                                    if let CanvasObject::Stroke(stroke) = object {
-                                        if AppUtils::point_intersects_stroke(pos, stroke, 10.0) {
-                                            hit = true;
-                                            break;
-                                        }
-                                    }
+                            // 在多选对象的整体边界内拖动时整体移动，否则取消多选
+                            if !self.state.selected_objects.is_empty() {
+                                if self
+                                    .selection_bounds(painter)
+                                    .is_some_and(|bounds| bounds.contains(pos))
+                                {
+                                    self.state.push_undo_snapshot();
+                                    return;
                                 }
+                                self.state.selected_objects.clear();
                             }
-                            if !hit {
+
+                            if !self.state.board.over_image_or_stroke(pos) {
                                 self.state.selected_object = None;
                             }
 
                             if let Some(selected_idx) = self.state.selected_object {
-                                if let Some(object) = self.state.canvas_objects.get(selected_idx) {
-                                    let object_rect = match object {
-                                        CanvasObject::Image(img) => {
-                                            Some(egui::Rect::from_min_size(img.pos, img.size))
-                                        }
-                                        CanvasObject::Text(text) => {
-                                            let text_galley = painter.layout_no_wrap(
-                                                text.text.clone(),
-                                                egui::FontId::proportional(text.font_size),
-                                                text.color,
-                                            );
-                                            let text_size = text_galley.size();
-                                            Some(egui::Rect::from_min_size(text.pos, text_size))
-                                        }
-                                        CanvasObject::Shape(shape) => {
-                                            Some(AppUtils::calculate_shape_bounding_box(shape))
-                                        }
-                                        CanvasObject::Stroke(_) => None,
-                                    };
+                                if let Some(object) = self.state.board.get(selected_idx) {
+                                    let object_rect = Board::handle_rect(object, painter);
 
                                     if let Some(rect) = object_rect {
-                                        if let Some(anchor) = self.state.resize_anchor_hovered {
+                                        if let Some(endpoint) = self.state.endpoint_hovered {
+                                            self.state.endpoint_operation = Some(endpoint);
+                                        } else if let Some(anchor) =
+                                            self.state.resize_anchor_hovered
+                                        {
                                             self.state.resize_operation = Some(ResizeOperation {
                                                 anchor,
                                                 start_pos: pos,
                                                 start_size: rect.size(),
                                                 start_object_pos: rect.min,
+                                                start_shape_rect: match object {
+                                                    CanvasObject::Shape(shape) => shape.frame(),
+                                                    _ => None,
+                                                },
                                             });
                                         } else if self.state.rotation_anchor_hovered {
+                                            let pivot = AppUtils::rotation_pivot_position(
+                                                self.state.rotation_pivot,
+                                                self.state.custom_rotation_pivot,
+                                                rect,
+                                                object,
+                                            );
+                                            let (start_angle, start_object_pos) = match object {
+                                                CanvasObject::Shape(shape) => {
+                                                    (shape.rotation, shape.pos)
+                                                }
+                                                _ => (0.0, rect.min),
+                                            };
                                             self.state.rotation_operation =
                                                 Some(RotationOperation {
                                                     start_pos: pos,
-                                                    start_angle: 0.0,
-                                                    center: rect.center(),
+                                                    start_angle,
+                                                    pivot,
+                                                    start_object_pos,
                                                 });
-
-                                            if let Some(CanvasObject::Shape(shape)) =
-                                                self.state.canvas_objects.get(selected_idx)
-                                            {
-                                                if let Some(op) =
-                                                    self.state.rotation_operation.as_mut()
-                                                {
-                                                    op.start_angle = shape.rotation;
-                                                }
-                                            }
                                         } else if rect.contains(pos) {
                                         } else {
                                             self.state.selected_object = None;
@@ -1174,262 +3138,200 @@ impl App {
                                     }
                                 }
                             } else {
-                                self.state.selected_object = None;
+                                self.state.selected_object =
+                                    self.state.board.hit_test(pos, painter);
+                            }
 
-                                for (i, object) in
-                                    self.state.canvas_objects.iter().enumerate().rev()
-                                {
-                                    match object {
-                                        CanvasObject::Image(img) => {
-                                            let img_rect =
-                                                egui::Rect::from_min_size(img.pos, img.size);
-                                            if img_rect.contains(pos) {
-                                                self.state.selected_object = Some(i);
-                                                break;
-                                            }
-                                        }
-                                        CanvasObject::Text(text) => {
-                                            let text_galley = painter.layout_no_wrap(
-                                                text.text.clone(),
-                                                egui::FontId::proportional(text.font_size),
-                                                text.color,
-                                            );
-                                            let text_size = text_galley.size();
-                                            let text_rect =
-                                                egui::Rect::from_min_size(text.pos, text_size);
-                                            if text_rect.contains(pos) {
-                                                self.state.selected_object = Some(i);
-                                                break;
-                                            }
-                                        }
-                                        CanvasObject::Shape(shape) => {
-                                            let shape_rect =
-                                                AppUtils::calculate_shape_bounding_box(shape);
-                                            if shape_rect.contains(pos) {
-                                                self.state.selected_object = Some(i);
-                                                break;
-                                            }
-                                        }
-                                        CanvasObject::Stroke(stroke) => {
-                                            if AppUtils::point_intersects_stroke(pos, stroke, 10.0)
-                                            {
-                                                self.state.selected_object = Some(i);
-                                                break;
-                                            }
-                                        }
-                                    }
-                                }
+                            // 移动、调整大小或旋转之前记录撤销快照
+                            if self.state.selected_object.is_some() {
+                                self.state.push_undo_snapshot();
+                            } else if self.state.board.hit_test(pos, painter).is_none() {
+                                // 从空白处开始拖动时框选
+                                self.state.marquee_start = Some(pos);
                             }
                         }
                     } else if response.clicked() {
                         if let Some(pos) = pointer_pos {
-                            let mut hit = false;
-                            for object in &self.state.canvas_objects {
-                                if let CanvasObject::Image(img) = object {
-                                    if egui::Rect::from_min_size(img.pos, img.size).contains(pos) {
-                                        hit = true;
-                                        break;
-                                    }
-                                }
-                            }
-                            if !hit {
-                                for object in &self.state.canvas_objects {
-                                    if let CanvasObject::Stroke(stroke) = object {
-                                        if AppUtils::point_intersects_stroke(pos, stroke, 10.0) {
-                                            hit = true;
-                                            break;
-                                        }
-                                    }
-                                }
+                            if !self
+                                .selection_bounds(painter)
+                                .is_some_and(|bounds| bounds.contains(pos))
+                            {
+                                self.state.selected_objects.clear();
                             }
-                            if !hit {
+
+                            if !self.state.board.over_image_or_stroke(pos) {
                                 self.state.selected_object = None;
                             }
                         }
                     } else if response.dragged() {
                         if let Some(pos) = pointer_pos {
-                            if let Some(resize_op) = self.state.resize_operation {
-                                if let Some(selected_idx) = self.state.selected_object {
-                                    if let Some(object) =
-                                        self.state.canvas_objects.get_mut(selected_idx)
-                                    {
-                                        let delta = pos - resize_op.start_pos;
-
-                                        match object {
-                                            CanvasObject::Image(img) => {
-                                                let mut new_size = resize_op.start_size;
-                                                let mut new_pos = resize_op.start_object_pos;
-
-                                                match resize_op.anchor {
-                                                    ResizeAnchor::TopLeft => {
-                                                        new_size.x = (resize_op.start_size.x
-                                                            - delta.x)
-                                                            .max(20.0);
-                                                        new_size.y = (resize_op.start_size.y
-                                                            - delta.y)
-                                                            .max(20.0);
-                                                        new_pos.x =
-                                                            resize_op.start_object_pos.x + delta.x;
-                                                        new_pos.y =
-                                                            resize_op.start_object_pos.y + delta.y;
-                                                    }
-                                                    ResizeAnchor::TopRight => {
-                                                        new_size.x = (resize_op.start_size.x
-                                                            + delta.x)
-                                                            .max(20.0);
-                                                        new_size.y = (resize_op.start_size.y
-                                                            - delta.y)
-                                                            .max(20.0);
-                                                        new_pos.y =
-                                                            resize_op.start_object_pos.y + delta.y;
-                                                    }
-                                                    ResizeAnchor::BottomLeft => {
-                                                        new_size.x = (resize_op.start_size.x
-                                                            - delta.x)
-                                                            .max(20.0);
-                                                        new_size.y = (resize_op.start_size.y
-                                                            + delta.y)
-                                                            .max(20.0);
-                                                        new_pos.x =
-                                                            resize_op.start_object_pos.x + delta.x;
-                                                    }
-                                                    ResizeAnchor::BottomRight => {
-                                                        new_size.x = (resize_op.start_size.x
-                                                            + delta.x)
-                                                            .max(20.0);
-                                                        new_size.y = (resize_op.start_size.y
-                                                            + delta.y)
-                                                            .max(20.0);
-                                                    }
-                                                    ResizeAnchor::Top => {
-                                                        new_size.y = (resize_op.start_size.y
-                                                            - delta.y)
-                                                            .max(20.0);
-                                                        new_pos.y =
-                                                            resize_op.start_object_pos.y + delta.y;
-                                                    }
-                                                    ResizeAnchor::Bottom => {
-                                                        new_size.y = (resize_op.start_size.y
-                                                            + delta.y)
-                                                            .max(20.0);
-                                                    }
-                                                    ResizeAnchor::Left => {
-                                                        new_size.x = (resize_op.start_size.x
-                                                            - delta.x)
-                                                            .max(20.0);
-                                                        new_pos.x =
-                                                            resize_op.start_object_pos.x + delta.x;
-                                                    }
-                                                    ResizeAnchor::Right => {
-                                                        new_size.x = (resize_op.start_size.x
-                                                            + delta.x)
-                                                            .max(20.0);
-                                                    }
-                                                }
-
-                                                if img.aspect_ratio > 0.0 {
-                                                    let target_aspect = img.aspect_ratio;
-                                                    let current_aspect = new_size.x / new_size.y;
-
-                                                    if current_aspect.abs() > 0.01 {
-                                                        if current_aspect > target_aspect {
-                                                            new_size.x = new_size.y * target_aspect;
-                                                        } else {
-                                                            new_size.y = new_size.x / target_aspect;
-                                                        }
-                                                    }
-                                                }
-
-                                                img.pos = new_pos;
-                                                img.size = new_size;
-                                            }
-                                            CanvasObject::Text(text) => match resize_op.anchor {
-                                                ResizeAnchor::TopLeft
-                                                | ResizeAnchor::BottomRight => {
-                                                    text.font_size =
-                                                        (resize_op.start_size.x + delta.x).max(8.0);
-                                                }
-                                                _ => {}
-                                            },
-                                            CanvasObject::Shape(shape) => {
-                                                let delta = pos - resize_op.start_pos;
-
-                                                match resize_op.anchor {
-                                                    ResizeAnchor::TopLeft
-                                                    | ResizeAnchor::BottomRight => {
-                                                        shape.size = (resize_op.start_size.x
-                                                            + delta.x)
-                                                            .max(10.0);
-                                                    }
-                                                    ResizeAnchor::TopRight
-                                                    | ResizeAnchor::BottomLeft => {
-                                                        shape.size = (resize_op.start_size.x
-                                                            - delta.x)
-                                                            .max(10.0);
-                                                    }
-                                                    ResizeAnchor::Top | ResizeAnchor::Bottom => {
-                                                        shape.size = (resize_op.start_size.y
-                                                            + delta.y)
-                                                            .max(10.0);
-                                                    }
-                                                    ResizeAnchor::Left | ResizeAnchor::Right => {
-                                                        shape.size = (resize_op.start_size.x
-                                                            + delta.x)
-                                                            .max(10.0);
-                                                    }
-                                                }
-                                            }
-                                            CanvasObject::Stroke(_) => {}
+                            if !self.state.selected_objects.is_empty() {
+                                if let Some(start_pos) = self.state.drag_start_pos {
+                                    let delta = pos - start_pos;
+                                    self.state.drag_start_pos = Some(pos);
+                                    self.state
+                                        .board
+                                        .move_objects(&self.state.selected_objects, delta);
+                                }
+                            } else if let Some(endpoint) = self.state.endpoint_operation {
+                                if let Some(CanvasObject::Shape(shape)) = self
+                                    .state
+                                    .selected_object
+                                    .and_then(|index| self.state.board.get_mut(index))
+                                {
+                                    let (start, end) = AppUtils::line_endpoints(shape);
+                                    let snap = ui.input(|i| i.modifiers.shift);
+                                    match endpoint {
+                                        ShapeEndpoint::Start => {
+                                            let pos = if snap {
+                                                AppUtils::snap_line_angle(
+                                                    end,
+                                                    pos,
+                                                    ROTATION_SNAP_STEP,
+                                                )
+                                            } else {
+                                                pos
+                                            };
+                                            AppUtils::set_line_endpoints(shape, pos, end);
+                                        }
+                                        ShapeEndpoint::End => {
+                                            let pos = if snap {
+                                                AppUtils::snap_line_angle(
+                                                    start,
+                                                    pos,
+                                                    ROTATION_SNAP_STEP,
+                                                )
+                                            } else {
+                                                pos
+                                            };
+                                            AppUtils::set_line_endpoints(shape, start, pos);
                                         }
                                     }
                                 }
+                            } else if let Some(resize_op) = self.state.resize_operation {
+                                if let Some(selected_idx) = self.state.selected_object {
+                                    // 按住 Shift 时形状保持原有宽高比；图片默认保持宽高比，按住 Shift 时可以自由拉伸
+                                    let shift = ui.input(|i| i.modifiers.shift);
+                                    let keep_image_aspect =
+                                        self.state.keep_image_aspect_ratio != shift;
+                                    self.state.board.resize_object(
+                                        selected_idx,
+                                        &resize_op,
+                                        pos,
+                                        keep_image_aspect,
+                                        shift,
+                                    );
+                                }
                             } else if let Some(rotate_op) = self.state.rotation_operation {
                                 if let Some(selected_idx) = self.state.selected_object {
-                                    if let Some(object) =
-                                        self.state.canvas_objects.get_mut(selected_idx)
-                                    {
-                                        let center = rotate_op.center;
-                                        let current_dir = pos - center;
-                                        let start_dir = rotate_op.start_pos - center;
+                                    if let Some(object) = self.state.board.get_mut(selected_idx) {
+                                        let pivot = rotate_op.pivot;
+                                        let current_dir = pos - pivot;
+                                        let start_dir = rotate_op.start_pos - pivot;
 
                                         let current_angle = current_dir.y.atan2(current_dir.x);
                                         let start_angle = start_dir.y.atan2(start_dir.x);
 
-                                        let angle_delta = current_angle - start_angle;
+                                        let mut angle_delta = current_angle - start_angle;
+                                        // 按住 Shift 时吸附到 15° 的整数倍
+                                        if ui.input(|i| i.modifiers.shift) {
+                                            let snapped = ((rotate_op.start_angle + angle_delta)
+                                                / ROTATION_SNAP_STEP)
+                                                .round()
+                                                * ROTATION_SNAP_STEP;
+                                            angle_delta = snapped - rotate_op.start_angle;
+                                        }
 
-                                        match object {
-                                            CanvasObject::Shape(shape) => {
-                                                shape.rotation =
-                                                    rotate_op.start_angle + angle_delta;
-                                            }
-                                            _ => {}
+                                        if let CanvasObject::Shape(shape) = object {
+                                            shape.rotation = rotate_op.start_angle + angle_delta;
+
+                                            // 形状绕自身中心旋转，因此将其中心绕旋转中心转动相同角度
+                                            let center_offset =
+                                                AppUtils::shape_center(shape) - shape.pos;
+                                            let start_center =
+                                                rotate_op.start_object_pos + center_offset;
+                                            shape.pos = AppUtils::rotate_point(
+                                                start_center,
+                                                pivot,
+                                                angle_delta,
+                                            ) - center_offset;
                                         }
                                     }
                                 }
                             } else if let (Some(start_pos), Some(selected_idx)) =
                                 (self.state.drag_start_pos, self.state.selected_object)
                             {
-                                let delta = pos - start_pos;
-                                self.state.drag_start_pos = Some(pos);
+                                const SNAP_THRESHOLD: f32 = 8.0;
+                                // 限制在画布内时对象至少保留的可见尺寸
+                                const MIN_VISIBLE_SIZE: f32 = 40.0;
 
-                                if let Some(object) =
-                                    self.state.canvas_objects.get_mut(selected_idx)
+                                // 先撤销上一帧的吸附偏移，再基于未吸附的位置重新计算
+                                let delta = pos - start_pos - self.state.move_snap_offset;
+                                self.state.drag_start_pos = Some(pos);
+                                self.state.move_snap_offset = egui::Vec2::ZERO;
+                                self.state.snap_guides.clear();
+                                self.state.object_guides.clear();
+
+                                let grid = self.grid_snap();
+                                // 可见的其他对象的包围盒（用于对象间对齐）
+                                let others: Vec<egui::Rect> = if grid.is_none()
+                                    && self.state.snap_to_objects
                                 {
-                                    match object {
-                                        CanvasObject::Image(img) => {
-                                            img.pos += delta;
-                                        }
-                                        CanvasObject::Stroke(stroke) => {
-                                            for p in &mut stroke.points {
-                                                *p += delta;
-                                            }
-                                        }
-                                        CanvasObject::Text(text) => {
-                                            text.pos += delta;
-                                        }
-                                        CanvasObject::Shape(shape) => {
-                                            shape.pos += delta;
-                                        }
+                                    self.state
+                                        .board
+                                        .objects()
+                                        .iter()
+                                        .enumerate()
+                                        .filter(|(i, _)| *i != selected_idx)
+                                        .map(|(_, other)| {
+                                            AppUtils::calculate_object_bounding_box(painter, other)
+                                        })
+                                        .filter(|other| other.intersects(rect))
+                                        .collect()
+                                } else {
+                                    Vec::new()
+                                };
+                                if let Some(object) = self.state.board.get_mut(selected_idx) {
+                                    object.translate(delta);
+
+                                    let (offset, object_guides) = AppUtils::snap_rect_to_objects(
+                                        AppUtils::calculate_object_bounding_box(painter, object),
+                                        &others,
+                                        SNAP_THRESHOLD,
+                                    );
+                                    if let Some(grid) = grid {
+                                        let offset = Self::snap_object_to_grid(object, grid);
+                                        self.state.move_snap_offset = offset;
+                                    } else if !object_guides.is_empty() {
+                                        // 与其他对象对齐优先于吸附画布
+                                        object.translate(offset);
+                                        self.state.move_snap_offset = offset;
+                                        self.state.object_guides = object_guides;
+                                    } else if self.state.snap_to_canvas {
+                                        let object_rect = AppUtils::calculate_object_bounding_box(
+                                            painter, object,
+                                        );
+                                        let (offset, guides) = AppUtils::snap_rect_to_canvas(
+                                            object_rect,
+                                            rect,
+                                            SNAP_THRESHOLD,
+                                        );
+                                        object.translate(offset);
+                                        self.state.move_snap_offset = offset;
+                                        self.state.snap_guides = guides;
+                                    }
+
+                                    if self.state.keep_objects_on_canvas {
+                                        let object_rect = AppUtils::calculate_object_bounding_box(
+                                            painter, object,
+                                        );
+                                        let offset = AppUtils::keep_rect_visible(
+                                            object_rect,
+                                            rect,
+                                            MIN_VISIBLE_SIZE,
+                                        );
+                                        object.translate(offset);
+                                        self.state.move_snap_offset += offset;
                                     }
                                 }
                             }
@@ -1437,58 +3339,76 @@ impl App {
                     } else if response.drag_stopped() {
                         self.state.resize_operation = None;
                         self.state.rotation_operation = None;
+                        self.state.endpoint_operation = None;
                         self.state.drag_start_pos = None;
+                        self.state.move_snap_offset = egui::Vec2::ZERO;
+                        self.state.snap_guides.clear();
+                        self.state.object_guides.clear();
+
+                        // 框选结束：选中包围盒与框选范围相交的对象，只有一个时按单选处理
+                        if let Some(start) = self.state.marquee_start.take() {
+                            let indices = self
+                                .state
+                                .board
+                                .objects_in_rect(egui::Rect::from_two_pos(start, pos), painter);
+                            if let [index] = indices.as_slice() {
+                                self.state.selected_object = Some(*index);
+                            } else {
+                                self.state.selected_objects = indices;
+                            }
+                        }
                     }
                 }
+
+                // 绘制框选范围
+                if let (Some(start), Some(pos)) = (self.state.marquee_start, pointer_pos) {
+                    painter.rect(
+                        egui::Rect::from_two_pos(start, pos),
+                        0.0,
+                        Color32::from_rgba_unmultiplied(0, 160, 255, 30),
+                        Stroke::new(1.0, Color32::from_rgb(0, 160, 255)),
+                        egui::StrokeKind::Inside,
+                    );
+                }
+            }
+
+            CanvasTool::ObjectEraser | CanvasTool::PixelEraser
+                if !response.dragged() && !response.clicked() =>
+            {
+                self.state.eraser_last_sample = None;
+                self.finish_soft_erase_pass();
+            }
+
+            CanvasTool::PixelEraser if self.state.soft_eraser => {
+                if let Some(pos) = pointer_pos {
+                    let eraser_size = self.update_dynamic_eraser_size(pos);
+                    AppUtils::draw_size_preview(
+                        painter,
+                        pos,
+                        eraser_size,
+                        SizePreviewStyle::Eraser,
+                    );
+                    self.soft_erase_at(pos, eraser_size);
+                }
             }
 
             CanvasTool::ObjectEraser => {
                 if response.drag_started() || response.clicked() || response.dragged() {
                     if let Some(pos) = pointer_pos {
-                        AppUtils::draw_size_preview(painter, pos, self.state.eraser_size);
-
-                        let mut to_remove = Vec::new();
+                        let eraser_size = self.update_dynamic_eraser_size(pos);
+                        AppUtils::draw_size_preview(
+                            painter,
+                            pos,
+                            eraser_size,
+                            SizePreviewStyle::Eraser,
+                        );
 
-                        for (i, object) in self.state.canvas_objects.iter().enumerate().rev() {
-                            match object {
-                                CanvasObject::Image(img) => {
-                                    let img_rect = egui::Rect::from_min_size(img.pos, img.size);
-                                    if img_rect.contains(pos) {
-                                        to_remove.push(i);
-                                    }
-                                }
-                                CanvasObject::Text(text) => {
-                                    let text_galley = painter.layout_no_wrap(
-                                        text.text.clone(),
-                                        egui::FontId::proportional(text.font_size),
-                                        text.color,
-                                    );
-                                    let text_size = text_galley.size();
-                                    let text_rect = egui::Rect::from_min_size(text.pos, text_size);
-                                    if text_rect.contains(pos) {
-                                        to_remove.push(i);
-                                    }
-                                }
-                                CanvasObject::Shape(shape) => {
-                                    let shape_rect = AppUtils::calculate_shape_bounding_box(shape);
-                                    if shape_rect.contains(pos) {
-                                        to_remove.push(i);
-                                    }
-                                }
-                                CanvasObject::Stroke(stroke) => {
-                                    if AppUtils::point_intersects_stroke(
-                                        pos,
-                                        stroke,
-                                        self.state.eraser_size,
-                                    ) {
-                                        to_remove.push(i);
-                                    }
-                                }
-                            }
-                        }
-
-                        for i in to_remove {
-                            self.state.canvas_objects.remove(i);
+                        if self.state.object_eraser_split_strokes {
+                            self.state
+                                .board
+                                .erase_stroke_segments_at(pos, eraser_size, painter);
+                        } else {
+                            self.state.board.erase_objects_at(pos, eraser_size, painter);
                         }
                     }
                 }
@@ -1497,236 +3417,2605 @@ impl App {
             CanvasTool::PixelEraser => {
                 if response.dragged() || response.clicked() {
                     if let Some(pos) = pointer_pos {
-                        AppUtils::draw_size_preview(painter, pos, self.state.eraser_size);
+                        let eraser_size = self.update_dynamic_eraser_size(pos);
+                        AppUtils::draw_size_preview(
+                            painter,
+                            pos,
+                            eraser_size,
+                            SizePreviewStyle::Eraser,
+                        );
 
-                        let eraser_radius = self.state.eraser_size / 2.0;
-                        let mut new_strokes = Vec::new();
+                        self.state.board.pixel_erase_at(pos, eraser_size, painter);
+                    }
+                }
+            }
 
-                        for object in &self.state.canvas_objects {
-                            if let CanvasObject::Stroke(stroke) = object {
-                                if stroke.points.len() < 2 {
-                                    continue;
-                                }
+            CanvasTool::StrokeWidth => {
+                if (response.dragged() || response.clicked())
+                    && let Some(pos) = pointer_pos
+                {
+                    AppUtils::draw_size_preview(
+                        painter,
+                        pos,
+                        self.state.stroke_width_tool_size,
+                        SizePreviewStyle::Neutral,
+                    );
 
-                                let mut current_points = Vec::new();
-                                let mut current_widths = Vec::new();
+                    // 强度按拖动距离累计（每移动一个工具直径缩放一次），与帧率无关；单击时缩放一次
+                    let tool_size = self.state.stroke_width_tool_size;
+                    let passes = if response.clicked() {
+                        1.0
+                    } else {
+                        response.drag_delta().length() / self.state.zoom / tool_size
+                    };
+                    let strength = self.state.stroke_width_tool_strength;
+                    let base = if ui.input(|i| i.modifiers.alt) {
+                        1.0 + strength
+                    } else {
+                        1.0 - strength
+                    };
+                    let factor = base.powf(passes);
+
+                    for object in self.state.board.objects_mut() {
+                        if let CanvasObject::Stroke(stroke) = object {
+                            AppUtils::adjust_stroke_widths(
+                                stroke,
+                                pos,
+                                self.state.stroke_width_tool_size,
+                                factor,
+                                self.state.min_brush_width,
+                                self.state.max_brush_width,
+                            );
+                        }
+                    }
+                }
+            }
 
-                                current_points.push(stroke.points[0]);
-                                if !stroke.widths.is_empty() {
-                                    current_widths.push(stroke.widths[0]);
-                                }
+            CanvasTool::Fill => {
+                if response.clicked()
+                    && let Some(pos) = pointer_pos
+                {
+                    self.fill_region_at(pos, rect);
+                }
+            }
 
-                                for i in 0..stroke.points.len() - 1 {
-                                    let p1 = stroke.points[i];
-                                    let p2 = stroke.points[i + 1];
-                                    let segment_width = if i < stroke.widths.len() {
-                                        stroke.widths[i]
-                                    } else {
-                                        stroke.widths[0]
-                                    };
-
-                                    let dist =
-                                        AppUtils::point_to_line_segment_distance(pos, p1, p2);
-
-                                    if dist > eraser_radius + segment_width / 2.0 {
-                                        current_points.push(p2);
-                                        if i + 1 < stroke.widths.len() {
-                                            current_widths.push(stroke.widths[i + 1]);
-                                        } else if !stroke.widths.is_empty() {
-                                            current_widths
-                                                .push(stroke.widths[stroke.widths.len() - 1]);
-                                        }
-                                    } else {
-                                        if current_points.len() >= 2 {
-                                            new_strokes.push(crate::state::CanvasStroke {
-                                                points: current_points.clone(),
-                                                widths: current_widths.clone(),
-                                                color: stroke.color,
-                                                base_width: stroke.base_width,
-                                            });
-                                        }
-                                        current_points = Vec::new();
-                                        current_widths = Vec::new();
-                                    }
-                                }
+            CanvasTool::AreaMeasure => {
+                if response.clicked()
+                    && let Some(pos) = pointer_pos
+                {
+                    self.measure_area_at(painter, pos);
+                }
+            }
 
-                                if current_points.len() >= 2 {
-                                    new_strokes.push(crate::state::CanvasStroke {
-                                        points: current_points,
-                                        widths: current_widths,
-                                        color: stroke.color,
-                                        base_width: stroke.base_width,
-                                    });
-                                }
-                            } else {
-                                if let CanvasObject::Stroke(stroke) = object {
-                                    new_strokes.push(stroke.clone());
-                                }
-                            }
-                        }
+            CanvasTool::Distance => {
+                if response.drag_started()
+                    && let Some(pos) = pointer_pos
+                {
+                    self.state.distance_measurement = Some((pos, pos));
+                } else if response.dragged()
+                    && let (Some(pos), Some((start, _))) =
+                        (pointer_pos, self.state.distance_measurement)
+                {
+                    let end = if ui.input(|i| i.modifiers.shift) {
+                        AppUtils::snap_line_angle(start, pos, ROTATION_SNAP_STEP)
+                    } else {
+                        pos
+                    };
+                    self.state.distance_measurement = Some((start, end));
+                } else if response.drag_stopped() {
+                    self.place_distance_annotation();
+                }
+            }
 
-                        self.state.canvas_objects = self
-                            .state
-                            .canvas_objects
-                            .iter()
-                            .filter_map(|obj| {
-                                if let CanvasObject::Stroke(_) = obj {
-                                    None
-                                } else {
-                                    Some(obj.clone())
-                                }
-                            })
-                            .collect();
+            CanvasTool::Protractor => {
+                if response.clicked()
+                    && let Some(pos) = pointer_pos
+                {
+                    self.place_protractor_point(pos);
+                }
+            }
 
-                        for stroke in new_strokes {
-                            self.state.canvas_objects.push(CanvasObject::Stroke(stroke));
-                        }
-                    }
+            CanvasTool::Polyline => {
+                // 双击的第二次点击不再添加顶点，直接完成
+                if response.double_clicked() {
+                    self.finish_pen_path();
+                } else if response.clicked()
+                    && let Some(pos) = pointer_pos
+                    && rect.contains(pos)
+                {
+                    self.state.pen_points.push(pos);
+                }
+
+                // 预览已放置的折线及其到指针的下一段
+                let (color, width) = self.brush_style(PointerInputType::Pen);
+                let stroke = Stroke::new(width, color);
+                let mut preview = self.state.pen_points.clone();
+                if !preview.is_empty()
+                    && let Some(pos) = pointer_pos
+                {
+                    preview.push(pos);
                 }
+                for vertex in &self.state.pen_points {
+                    painter.circle_stroke(*vertex, width / 2.0 + 3.0, Stroke::new(1.0, color));
+                }
+                painter.add(Shape::line(preview, stroke));
             }
 
             CanvasTool::Brush => {
-                if response.drag_started() {
-                    if let Some(pos) = pointer_pos {
-                        if pos.x >= rect.min.x
-                            && pos.x <= rect.max.x
-                            && pos.y >= rect.min.y
-                            && pos.y <= rect.max.y
+                // 多点触控：每个触控点独立绘制一条笔画
+                let touches: Vec<(u64, egui::TouchPhase, Pos2, Option<f32>)> = ui.input(|i| {
+                    i.events
+                        .iter()
+                        .filter_map(|event| match event {
+                            egui::Event::Touch {
+                                id,
+                                phase,
+                                pos,
+                                force,
+                                ..
+                            } => Some((id.0, *phase, *pos, *force)),
+                            _ => None,
+                        })
+                        .collect()
+                });
+                if !touches.is_empty() || ui.input(|i| i.any_touches()) {
+                    for (touch_id, phase, screen_pos, force) in touches {
+                        if self.state.palm_rejection
+                            && self.reject_palm_touch(touch_id, phase, force)
                         {
-                            self.state.is_drawing = true;
-                            let start_time = Instant::now();
-                            let width = AppUtils::calculate_dynamic_width(
-                                self.state.brush_width,
-                                self.state.dynamic_brush_width_mode,
-                                0,
-                                1,
-                                None,
-                            );
-
-                            let touch_id = 0;
-                            self.state.active_strokes.insert(
-                                touch_id,
-                                crate::state::ActiveStroke {
-                                    points: vec![pos],
-                                    widths: vec![width],
-                                    times: vec![0.0],
-                                    start_time,
-                                },
-                            );
+                            continue;
                         }
-                    }
-                } else if response.dragged() {
-                    if self.state.is_drawing {
-                        if let Some(pos) = pointer_pos {
-                            let touch_id = 0;
-                            if let Some(active_stroke) =
-                                self.state.active_strokes.get_mut(&touch_id)
-                            {
-                                let current_time = active_stroke.start_time.elapsed().as_secs_f64();
-
-                                if active_stroke.points.is_empty()
-                                    || active_stroke.points.last().unwrap().distance(pos) > 1.0
+                        let pos = self
+                            .snap_to_ruler(self.snap_to_trace_edge(view.inverse() * screen_pos));
+                        match phase {
+                            egui::TouchPhase::Start => {
+                                if rect.contains(pos) && Self::touch_on_canvas(ui, screen_pos) {
+                                    let input_type =
+                                        if self.state.separate_finger_style && force.is_none() {
+                                            PointerInputType::Finger
+                                        } else {
+                                            PointerInputType::Pen
+                                        };
+                                    self.begin_active_stroke(touch_id, pos, input_type, force);
+                                }
+                            }
+                            egui::TouchPhase::Move => {
+                                self.extend_active_stroke(touch_id, pos, force);
+                            }
+                            egui::TouchPhase::End => {
+                                self.extend_active_stroke(touch_id, pos, force);
+                                if let Some(active_stroke) =
+                                    self.state.active_strokes.remove(&touch_id)
                                 {
-                                    let speed = if active_stroke.points.len() > 0
-                                        && active_stroke.times.len() > 0
-                                    {
-                                        let last_time = active_stroke.times.last().unwrap();
-                                        let time_delta =
-                                            ((current_time - last_time) as f32).max(0.001);
-                                        let distance =
-                                            active_stroke.points.last().unwrap().distance(pos);
-                                        Some(distance / time_delta)
-                                    } else {
-                                        None
-                                    };
-
-                                    active_stroke.points.push(pos);
-                                    active_stroke.times.push(current_time);
-
-                                    let width = AppUtils::calculate_dynamic_width(
-                                        self.state.brush_width,
-                                        self.state.dynamic_brush_width_mode,
-                                        active_stroke.points.len() - 1,
-                                        active_stroke.points.len(),
-                                        speed,
-                                    );
-                                    active_stroke.widths.push(width);
+                                    self.commit_active_stroke(active_stroke);
                                 }
                             }
+                            egui::TouchPhase::Cancel => {
+                                self.state.active_strokes.remove(&touch_id);
+                            }
+                        }
+                    }
+                    self.state.is_drawing = !self.state.active_strokes.is_empty();
+                    return;
+                }
+
+                // 鼠标或单指针输入
+                let pointer_pos =
+                    pointer_pos.map(|pos| self.snap_to_ruler(self.snap_to_trace_edge(pos)));
+                if response.drag_started() {
+                    if let Some(pos) = pointer_pos
+                        && rect.contains(pos)
+                    {
+                        let input_type = self.current_input_type();
+                        self.begin_active_stroke(POINTER_STROKE_ID, pos, input_type, None);
+                    }
+                } else if response.dragged() {
+                    if self.state.is_drawing
+                        && let Some(pos) = pointer_pos
+                    {
+                        // 按住 Shift 绘制直线，同时按住 Ctrl 时吸附到 45° 的整数倍
+                        let modifiers = ui.input(|i| i.modifiers);
+                        if modifiers.shift {
+                            self.constrain_active_stroke_to_line(
+                                POINTER_STROKE_ID,
+                                pos,
+                                modifiers.command,
+                            );
+                        } else {
+                            self.extend_active_stroke(POINTER_STROKE_ID, pos, None);
                         }
                     }
                 } else if response.drag_stopped() {
                     if self.state.is_drawing {
-                        let touch_id = 0;
-                        if let Some(active_stroke) = self.state.active_strokes.remove(&touch_id) {
-                            if active_stroke.points.len() > 1
-                                && active_stroke.widths.len() == active_stroke.points.len()
-                            {
-                                let final_points = if self.state.stroke_smoothing {
-                                    AppUtils::apply_stroke_smoothing(&active_stroke.points)
-                                } else {
-                                    active_stroke.points
-                                };
-
-                                let (interpolated_points, interpolated_widths) =
-                                    AppUtils::apply_point_interpolation(
-                                        &final_points,
-                                        &active_stroke.widths,
-                                        self.state.interpolation_frequency,
-                                    );
-
-                                self.state.canvas_objects.push(CanvasObject::Stroke(
-                                    crate::state::CanvasStroke {
-                                        points: interpolated_points,
-                                        widths: interpolated_widths,
-                                        color: self.state.brush_color,
-                                        base_width: self.state.brush_width,
-                                    },
-                                ));
-                            }
+                        if let Some(active_stroke) =
+                            self.state.active_strokes.remove(&POINTER_STROKE_ID)
+                        {
+                            self.commit_active_stroke(active_stroke);
                         }
 
                         self.state.is_drawing = !self.state.active_strokes.is_empty();
                     }
+                } else if response.clicked()
+                    && !self.state.is_drawing
+                    && let Some(pos) = pointer_pos
+                    && rect.contains(pos)
+                {
+                    // 单击未触发拖动，直接画点
+                    if self.passes_min_stroke_filter(0.0, 0.0) {
+                        self.commit_dot(pos);
+                    }
                 }
 
-                if response.hovered() && self.state.is_drawing {
-                    if let Some(pos) = pointer_pos {
-                        let touch_id = 0;
-                        if let Some(active_stroke) = self.state.active_strokes.get_mut(&touch_id) {
-                            let current_time = active_stroke.start_time.elapsed().as_secs_f64();
+                if response.hovered()
+                    && self.state.is_drawing
+                    && !ui.input(|i| i.modifiers.shift)
+                    && let Some(pos) = pointer_pos
+                {
+                    self.extend_active_stroke(POINTER_STROKE_ID, pos, None);
+                }
+            }
+        }
+    }
 
-                            if active_stroke.points.is_empty()
-                                || active_stroke.points.last().unwrap().distance(pos) > 1.0
-                            {
-                                let speed = if active_stroke.points.len() > 0
-                                    && active_stroke.times.len() > 0
-                                {
-                                    let last_time = active_stroke.times.last().unwrap();
-                                    let time_delta = ((current_time - last_time) as f32).max(0.001);
-                                    let distance =
-                                        active_stroke.points.last().unwrap().distance(pos);
-                                    Some(distance / time_delta)
-                                } else {
-                                    None
-                                };
+    // 触控点是否直接落在画布上：原始触控事件不经过 egui 的命中测试，
+    // 需要排除工具栏、页面概览等浮动在画布之上的窗口
+    fn touch_on_canvas(ui: &egui::Ui, screen_pos: Pos2) -> bool {
+        ui.ctx()
+            .layer_id_at(screen_pos)
+            .is_none_or(|layer| layer == ui.layer_id())
+    }
 
-                                active_stroke.points.push(pos);
-                                active_stroke.times.push(current_time);
+    // 手掌防误触：触控笔（报告压力的触点）按下时丢弃其他触点未完成的笔画，
+    // 按下期间忽略其他触点，返回该触点事件是否应被忽略
+    fn reject_palm_touch(
+        &mut self,
+        touch_id: u64,
+        phase: egui::TouchPhase,
+        force: Option<f32>,
+    ) -> bool {
+        if phase == egui::TouchPhase::Start && force.is_some() {
+            self.state.pen_touch_ids.insert(touch_id);
+            self.state
+                .active_strokes
+                .retain(|id, _| *id == POINTER_STROKE_ID || self.state.pen_touch_ids.contains(id));
+        }
 
-                                let width = AppUtils::calculate_dynamic_width(
-                                    self.state.brush_width,
-                                    self.state.dynamic_brush_width_mode,
-                                    active_stroke.points.len() - 1,
-                                    active_stroke.points.len(),
-                                    speed,
-                                );
-                                active_stroke.widths.push(width);
-                            }
-                        }
+        let rejected =
+            !self.state.pen_touch_ids.is_empty() && !self.state.pen_touch_ids.contains(&touch_id);
+        if matches!(phase, egui::TouchPhase::End | egui::TouchPhase::Cancel) {
+            self.state.pen_touch_ids.remove(&touch_id);
+        }
+        if rejected {
+            self.state.active_strokes.remove(&touch_id);
+        }
+        rejected
+    }
+
+    // 开始绘制一条笔画（stroke_id 为触控 ID，鼠标输入使用 POINTER_STROKE_ID；force 为输入报告的压力）
+    fn begin_active_stroke(
+        &mut self,
+        stroke_id: u64,
+        pos: Pos2,
+        input_type: PointerInputType,
+        force: Option<f32>,
+    ) {
+        let (pen_width, pen_width_mode) = self.pen_width();
+        let width = match input_type {
+            PointerInputType::Pen => {
+                AppUtils::calculate_dynamic_width(pen_width, pen_width_mode, 0, 1, None, force)
+            }
+            PointerInputType::Finger => self.state.finger_brush_width,
+        };
+
+        self.state.is_drawing = true;
+        self.state.active_strokes.insert(
+            stroke_id,
+            crate::state::ActiveStroke {
+                points: vec![pos],
+                widths: vec![width],
+                times: vec![0.0],
+                forces: force.into_iter().collect(),
+                start_time: Instant::now(),
+                input_type,
+            },
+        );
+    }
+
+    // 向正在绘制的笔画追加一个点，并按速度或压力计算该点的动态宽度
+    fn extend_active_stroke(&mut self, stroke_id: u64, pos: Pos2, force: Option<f32>) {
+        let (pen_width, pen_width_mode) = self.pen_width();
+        let Some(active_stroke) = self.state.active_strokes.get_mut(&stroke_id) else {
+            return;
+        };
+        let current_time = active_stroke.start_time.elapsed().as_secs_f64();
+
+        let last = active_stroke
+            .points
+            .last()
+            .copied()
+            .zip(active_stroke.times.last().copied());
+        if let Some((last_pos, _)) = last
+            && last_pos.distance(pos) <= 1.0
+        {
+            return;
+        }
+        let speed = last.map(|(last_pos, last_time)| {
+            let time_delta = ((current_time - last_time) as f32).max(0.001);
+            last_pos.distance(pos) / time_delta
+        });
+
+        active_stroke.points.push(pos);
+        active_stroke.times.push(current_time);
+        // 只有每个点都报告了压力时才保留压力数据
+        match force {
+            Some(force) if active_stroke.forces.len() + 1 == active_stroke.points.len() => {
+                active_stroke.forces.push(force);
+            }
+            _ => active_stroke.forces.clear(),
+        }
+
+        let width = match active_stroke.input_type {
+            PointerInputType::Pen => AppUtils::calculate_dynamic_width(
+                pen_width,
+                pen_width_mode,
+                active_stroke.points.len() - 1,
+                active_stroke.points.len(),
+                speed,
+                force,
+            ),
+            PointerInputType::Finger => self.state.finger_brush_width,
+        };
+        active_stroke.widths.push(width);
+    }
+
+    // 在屏幕上的指定位置显示角度（度，0° 到 360°）
+    fn draw_angle_readout(painter: &egui::Painter, pos: Pos2, angle: f32) {
+        let degrees = angle.to_degrees().round().rem_euclid(360.0);
+        Self::draw_readout(painter, pos, format!("{degrees:.0}°"));
+    }
+
+    // 在屏幕上的指定位置（标签左侧中点）显示深色背景的数值标签
+    fn draw_readout(painter: &egui::Painter, pos: Pos2, text: String) {
+        let galley = painter.layout_no_wrap(text, egui::FontId::proportional(14.0), Color32::WHITE);
+        let rect = egui::Align2::LEFT_CENTER
+            .anchor_size(pos, galley.size())
+            .expand(4.0);
+        painter.rect_filled(rect, 4.0, Color32::from_black_alpha(180));
+        painter.galley(rect.shrink(4.0).min, galley, Color32::WHITE);
+    }
+
+    // 将正在绘制的笔画替换为从起点到指定位置的直线，宽度保持起点宽度
+    fn constrain_active_stroke_to_line(&mut self, stroke_id: u64, pos: Pos2, snap_angle: bool) {
+        let Some(active_stroke) = self.state.active_strokes.get_mut(&stroke_id) else {
+            return;
+        };
+        let (Some(&start), Some(&width)) =
+            (active_stroke.points.first(), active_stroke.widths.first())
+        else {
+            return;
+        };
+
+        let end = if snap_angle {
+            AppUtils::snap_line_angle(start, pos, std::f32::consts::FRAC_PI_4)
+        } else {
+            pos
+        };
+        active_stroke.points = vec![start, end];
+        active_stroke.widths = vec![width, width];
+        active_stroke.times = vec![0.0, active_stroke.start_time.elapsed().as_secs_f64()];
+        active_stroke.forces.clear();
+    }
+
+    // 视图平移与缩放：Ctrl+滚轮或双指捏合以指针为中心缩放，按住空格拖动或中键拖动平移
+    // 返回本帧是否在平移（平移时不处理工具输入）
+    fn handle_view_navigation(&mut self, ui: &egui::Ui, response: &egui::Response) -> bool {
+        const MIN_ZOOM: f32 = 0.1;
+        const MAX_ZOOM: f32 = 10.0;
+
+        if let Some(cursor) = response.hover_pos() {
+            let zoom_delta = ui.input(|i| i.zoom_delta());
+            if zoom_delta != 1.0 {
+                let zoom = (self.state.zoom * zoom_delta).clamp(MIN_ZOOM, MAX_ZOOM);
+                // 保持指针下的画布位置不变
+                let cursor = cursor.to_vec2();
+                self.state.pan_offset =
+                    cursor - (cursor - self.state.pan_offset) * (zoom / self.state.zoom);
+                self.state.zoom = zoom;
+            }
+        }
+
+        let space_down =
+            !ui.ctx().wants_keyboard_input() && ui.input(|i| i.key_down(egui::Key::Space));
+        let panning = response.dragged_by(egui::PointerButton::Middle)
+            || (space_down && response.dragged_by(egui::PointerButton::Primary));
+        if panning {
+            self.state.pan_offset += response.drag_delta();
+        }
+        panning
+    }
+
+    // 直尺吸附：靠近直尺边缘的画笔点移到边缘上
+    fn snap_to_ruler(&self, pos: Pos2) -> Pos2 {
+        // 吸附距离（屏幕像素）
+        const RULER_SNAP_DISTANCE: f32 = 24.0;
+
+        self.state
+            .ruler
+            .and_then(|(anchor, angle)| {
+                AppUtils::project_onto_ruler(
+                    pos,
+                    anchor,
+                    angle,
+                    RULER_SNAP_DISTANCE / self.state.zoom,
+                )
+            })
+            .unwrap_or(pos)
+    }
+
+    // 拖动尺身移动直尺，拖动手柄旋转直尺（按住 Shift 吸附到 15° 的整数倍）
+    // 返回本帧的指针输入是否被直尺占用
+    fn handle_ruler_drag(
+        &mut self,
+        ui: &egui::Ui,
+        response: &egui::Response,
+        pointer_pos: Option<Pos2>,
+        zoom: f32,
+    ) -> bool {
+        let Some((anchor, angle)) = self.state.ruler else {
+            self.state.ruler_drag = None;
+            return false;
+        };
+
+        if response.drag_started()
+            && let Some(pos) = pointer_pos
+        {
+            self.state.ruler_drag = if pos.distance(AppUtils::ruler_handle_position(anchor, angle))
+                <= AppUtils::RULER_HANDLE_RADIUS
+            {
+                Some(RulerDrag::Rotate)
+            } else if AppUtils::point_in_polygon(pos, &AppUtils::ruler_corners(anchor, angle)) {
+                Some(RulerDrag::Move)
+            } else {
+                None
+            };
+        }
+        let Some(drag) = self.state.ruler_drag else {
+            return false;
+        };
+
+        match drag {
+            RulerDrag::Move => {
+                self.state.ruler = Some((anchor + response.drag_delta() / zoom, angle));
+            }
+            RulerDrag::Rotate => {
+                if let Some(pos) = pointer_pos {
+                    let mut angle = (pos - anchor).angle();
+                    if ui.input(|i| i.modifiers.shift) {
+                        angle = (angle / ROTATION_SNAP_STEP).round() * ROTATION_SNAP_STEP;
                     }
+                    self.state.ruler = Some((anchor, angle));
                 }
             }
         }
+        if response.drag_stopped() {
+            self.state.ruler_drag = None;
+        }
+        true
+    }
+
+    // 描摹吸附：将画笔点向附近的底图边缘靠拢
+    fn snap_to_trace_edge(&self, pos: Pos2) -> Pos2 {
+        if !self.state.trace_snap {
+            return pos;
+        }
+        self.state
+            .trace_reference
+            .as_ref()
+            .and_then(|reference| reference.nearest_edge(pos, self.state.trace_snap_radius))
+            .map_or(pos, |edge| pos.lerp(edge, self.state.trace_snap_strength))
+    }
+
+    // 选择描摹底图，按比例居中铺满画布并预先计算边缘图
+    fn load_trace_reference(&mut self, ctx: &egui::Context) {
+        // 边缘图的最大边长，过大的图片先缩小以控制内存与计算量
+        const MAX_EDGE_MAP_SIZE: u32 = 1600;
+        // Sobel 梯度幅值阈值
+        const EDGE_THRESHOLD: f32 = 128.0;
+
+        let future = async {
+            rfd::AsyncFileDialog::new()
+                .add_filter("图片", &["png", "jpg", "jpeg", "bmp", "gif", "webp"])
+                .pick_file()
+                .await
+        };
+        let Some(path) = futures::executor::block_on(future) else {
+            return;
+        };
+        let img = match image::open(path.path()) {
+            Ok(img) => img,
+            Err(err) => {
+                log::error!("Failed to load trace reference: {err}");
+                return;
+            }
+        };
+
+        let rgba = img.to_rgba8();
+        let (width, height) = rgba.dimensions();
+        let texture = ctx.load_texture(
+            "trace_reference",
+            egui::ColorImage::from_rgba_unmultiplied([width as usize, height as usize], &rgba),
+            egui::TextureOptions::LINEAR,
+        );
+
+        let gray = img
+            .thumbnail(MAX_EDGE_MAP_SIZE, MAX_EDGE_MAP_SIZE)
+            .to_luma8();
+        let edges = AppUtils::compute_edge_map(&gray, EDGE_THRESHOLD);
+
+        let canvas_rect = self
+            .state
+            .last_canvas_rect
+            .unwrap_or_else(|| ctx.available_rect());
+        let image_size = egui::vec2(width as f32, height as f32);
+        let scale = (canvas_rect.width() / image_size.x).min(canvas_rect.height() / image_size.y);
+        let rect = egui::Rect::from_center_size(canvas_rect.center(), image_size * scale);
+
+        self.state.trace_reference = Some(TraceReference {
+            texture,
+            rect,
+            edge_width: gray.width() as usize,
+            edge_height: gray.height() as usize,
+            edges,
+        });
+    }
+
+    // 动态画笔宽度指示：以进度条显示正在绘制的笔画最近一次计算出的宽度
+    fn render_dynamic_width_legend(&self, ui: &mut egui::Ui) {
+        let current_width = self
+            .state
+            .active_strokes
+            .values()
+            .find_map(|active_stroke| active_stroke.widths.last().copied());
+
+        ui.horizontal(|ui| {
+            ui.label("当前宽度:");
+            let (min_scale, max_scale) = (
+                *AppUtils::DYNAMIC_WIDTH_RANGE.start(),
+                *AppUtils::DYNAMIC_WIDTH_RANGE.end(),
+            );
+            let (fraction, text) = match current_width {
+                Some(width) => {
+                    let scale = width / self.state.brush_width.max(f32::EPSILON);
+                    (
+                        ((scale - min_scale) / (max_scale - min_scale)).clamp(0.0, 1.0),
+                        format!("{width:.1} ({:.0}%)", scale * 100.0),
+                    )
+                }
+                None => (0.0, "未在绘制".to_owned()),
+            };
+            ui.add(
+                egui::ProgressBar::new(fraction)
+                    .desired_width(160.0)
+                    .text(text),
+            );
+            ui.label(format!(
+                "{:.0}% ~ {:.0}%",
+                min_scale * 100.0,
+                max_scale * 100.0
+            ));
+        });
+    }
+
+    // 将正在绘制的笔画提交到画布
+    fn commit_active_stroke(&mut self, active_stroke: ActiveStroke) {
+        if active_stroke.points.is_empty()
+            || active_stroke.widths.len() != active_stroke.points.len()
+        {
+            return;
+        }
+
+        // 丢弃过短的笔画（误触、手掌接触时的抖动）
+        let duration = active_stroke.times.last().copied().unwrap_or(0.0) as f32;
+        if !self.passes_min_stroke_filter(
+            AppUtils::calculate_path_length(&active_stroke.points),
+            duration,
+        ) {
+            return;
+        }
+
+        // 单击（或几乎未移动）时留下一个圆点
+        if active_stroke.points.len() == 1
+            && let Some(&pos) = active_stroke.points.first()
+        {
+            self.commit_dot(pos);
+            return;
+        }
+
+        let (points, widths, forces) = self.process_stroke_points(
+            active_stroke.points,
+            active_stroke.widths,
+            active_stroke.forces,
+        );
+
+        self.record_brush_color(self.state.brush_color);
+        let (color, base_width) = self.brush_style(active_stroke.input_type);
+        let index = self.insert_stroke(crate::state::CanvasStroke {
+            points,
+            widths,
+            forces,
+            color,
+            base_width,
+            follow_background: false,
+            input_type: active_stroke.input_type,
+            highlighter: self.state.highlighter
+                && active_stroke.input_type == PointerInputType::Pen,
+            fade: None,
+            cache: Default::default(),
+        });
+
+        if self.state.recognize_shapes {
+            self.replace_with_recognized_shape(index);
+        }
+    }
+
+    // 提交前处理笔画的点：自动拉直、平滑、简化与插值，宽度与压力随点重新取样
+    fn process_stroke_points(
+        &self,
+        mut points: Vec<Pos2>,
+        mut widths: Vec<f32>,
+        mut forces: Vec<f32>,
+    ) -> (Vec<Pos2>, Vec<f32>, Vec<f32>) {
+        // 自动拉直：笔画偏离首尾连线不超过阈值时，替换为直线
+        if self.state.auto_straighten
+            && let Some([first, last]) =
+                AppUtils::maybe_straighten(&points, self.state.straighten_threshold)
+            && let (Some(first_width), Some(last_width)) =
+                (widths.first().copied(), widths.last().copied())
+        {
+            points = vec![first, last];
+            widths = vec![first_width, last_width];
+            forces = [forces.first(), forces.last()]
+                .into_iter()
+                .flatten()
+                .copied()
+                .collect();
+        }
+
+        let final_points = if self.state.stroke_smoothing {
+            AppUtils::apply_stroke_smoothing(&points, self.state.smoothing_iterations)
+        } else {
+            points
+        };
+
+        // 简化笔画：删除几乎共线的点，宽度与压力在保留的点处取样
+        let widths = if widths.len() == final_points.len() {
+            widths
+        } else {
+            AppUtils::resample_widths(&widths, final_points.len())
+        };
+        let forces = AppUtils::resample_widths(&forces, final_points.len());
+        let kept = AppUtils::simplify_stroke(&final_points, self.state.simplify_epsilon);
+        let final_points: Vec<Pos2> = kept
+            .iter()
+            .filter_map(|&i| final_points.get(i).copied())
+            .collect();
+        let widths: Vec<f32> = kept
+            .iter()
+            .filter_map(|&i| widths.get(i).copied())
+            .collect();
+        let forces: Vec<f32> = kept
+            .iter()
+            .filter_map(|&i| forces.get(i).copied())
+            .collect();
+
+        let (interpolated_points, interpolated_widths) = AppUtils::apply_point_interpolation(
+            &final_points,
+            &widths,
+            self.state.interpolation_frequency,
+        );
+        let interpolated_forces = if forces.is_empty() {
+            forces
+        } else {
+            AppUtils::apply_point_interpolation(
+                &final_points,
+                &forces,
+                self.state.interpolation_frequency,
+            )
+            .1
+        };
+
+        (
+            interpolated_points,
+            interpolated_widths,
+            interpolated_forces,
+        )
+    }
+
+    // 识别形状：先提交手绘笔迹，再单独记录一步替换为形状，撤销即可恢复手绘笔迹
+    fn replace_with_recognized_shape(&mut self, index: usize) {
+        if let Some(CanvasObject::Stroke(stroke)) = self.state.board.get(index)
+            && let Some(shape_type) = AppUtils::recognize_shape(&stroke.points)
+        {
+            let shape = AppUtils::fit_recognized_shape(
+                shape_type,
+                &stroke.points,
+                stroke.color,
+                stroke.base_width,
+            );
+            self.state.push_undo_snapshot();
+            if let Some(object) = self.state.board.get_mut(index) {
+                *object = CanvasObject::Shape(shape);
+            }
+
+            let name = match shape_type {
+                CanvasShapeType::Line | CanvasShapeType::Arrow => "直线",
+                CanvasShapeType::Rectangle => "矩形",
+                CanvasShapeType::Triangle => "三角形",
+                CanvasShapeType::Circle => "圆形",
+                CanvasShapeType::Polygon { .. } => "多边形",
+                CanvasShapeType::Star { .. } => "星形",
+                CanvasShapeType::Region => "填充区域",
+            };
+            self.show_toast(format!("已识别为{name}，撤销可恢复手绘笔迹"));
+        }
+    }
+
+    // 笔画长度与时长是否达到最短笔画设置
+    fn passes_min_stroke_filter(&self, length: f32, duration: f32) -> bool {
+        length >= self.state.min_stroke_length && duration >= self.state.min_stroke_duration
+    }
+
+    // 记录提交笔迹所用的画笔颜色（修正笔使用背景颜色，不记录）
+    fn record_brush_color(&mut self, color: Color32) {
+        if !self.state.correction_pen {
+            self.state.record_recent_color(color);
+        }
+    }
+
+    // 在指定位置提交一个直径为画笔宽度的圆点
+    fn commit_dot(&mut self, pos: Pos2) {
+        let input_type = self.current_input_type();
+        self.record_brush_color(self.state.brush_color);
+        let (color, base_width) = self.brush_style(input_type);
+        self.insert_stroke(crate::state::CanvasStroke {
+            points: vec![pos],
+            widths: vec![base_width],
+            forces: Vec::new(),
+            color,
+            base_width,
+            follow_background: false,
+            input_type,
+            highlighter: self.state.highlighter && input_type == PointerInputType::Pen,
+            fade: None,
+            cache: Default::default(),
+        });
+    }
+
+    // 新笔画使用的输入方式（未启用区分时一律按触控笔处理）
+    fn current_input_type(&self) -> PointerInputType {
+        if self.state.separate_finger_style {
+            self.state.last_pointer_input
+        } else {
+            PointerInputType::Pen
+        }
+    }
+
+    // 触控笔的基础宽度与动态宽度模式（荧光笔使用加宽的固定宽度）
+    fn pen_width(&self) -> (f32, DynamicBrushWidthMode) {
+        if self.state.highlighter {
+            (
+                self.state.brush_width * HIGHLIGHTER_WIDTH_SCALE,
+                DynamicBrushWidthMode::Disabled,
+            )
+        } else {
+            (self.state.brush_width, self.state.dynamic_brush_width_mode)
+        }
+    }
+
+    // 不同输入方式对应的笔画颜色与基础宽度
+    fn brush_style(&self, input_type: PointerInputType) -> (Color32, f32) {
+        match input_type {
+            PointerInputType::Pen => {
+                let opacity = if self.state.highlighter {
+                    self.state.brush_opacity * HIGHLIGHTER_OPACITY
+                } else {
+                    self.state.brush_opacity
+                };
+                (
+                    self.state.brush_color.gamma_multiply(opacity),
+                    self.pen_width().0,
+                )
+            }
+            PointerInputType::Finger => (
+                self.state
+                    .brush_color
+                    .gamma_multiply(self.state.finger_brush_opacity),
+                self.state.finger_brush_width,
+            ),
+        }
+    }
+
+    // 网格吸附设置（网格间距、笔画起点是否吸附），未启用网格时为 None
+    fn grid_snap(&self) -> Option<(f32, bool)> {
+        self.state
+            .grid_enabled
+            .then_some((self.state.grid_size, self.state.grid_snap_strokes))
+    }
+
+    // 网格吸附：将对象的定位点（图片、形状与文本的位置，启用时笔画的起点）移到最近的网格交点，返回移动的偏移
+    fn snap_object_to_grid(
+        object: &mut CanvasObject,
+        (grid_size, snap_strokes): (f32, bool),
+    ) -> egui::Vec2 {
+        let anchor = match object {
+            CanvasObject::Image(img) => img.pos,
+            CanvasObject::Shape(shape) => shape.pos,
+            CanvasObject::Text(text) => text.pos,
+            CanvasObject::Stroke(stroke) if snap_strokes => match stroke.points.first() {
+                Some(point) => *point,
+                None => return egui::Vec2::ZERO,
+            },
+            CanvasObject::Stroke(_) => return egui::Vec2::ZERO,
+        };
+        let offset = AppUtils::snap_to_grid(anchor, grid_size) - anchor;
+        object.translate(offset);
+        offset
+    }
+
+    // 添加对象到画布（启用网格时先吸附到网格）
+    fn add_object_on_grid(&mut self, mut object: CanvasObject) {
+        if let Some(grid) = self.grid_snap() {
+            Self::snap_object_to_grid(&mut object, grid);
+        }
+        self.state.board.add_object(object);
+    }
+
+    // 按"新笔迹置于"设置将笔画插入对象列表，返回插入位置
+    fn insert_stroke(&mut self, mut stroke: crate::state::CanvasStroke) -> usize {
+        self.state.push_undo_snapshot();
+
+        // 修正笔：笔迹使用并跟随背景颜色
+        if self.state.correction_pen {
+            stroke.color = self.state.background_color;
+            stroke.follow_background = true;
+        }
+
+        if self.state.fading_ink {
+            stroke.fade = Some(crate::state::StrokeFade {
+                created_at: Instant::now(),
+                hold: self.state.fading_ink_hold,
+            });
+        }
+
+        // 整数宽度：使笔迹边缘清晰
+        if self.state.integer_widths {
+            for width in &mut stroke.widths {
+                *width = width.round().max(1.0);
+            }
+            stroke.base_width = stroke.base_width.round().max(1.0);
+        }
+
+        let len = self.state.board.len();
+        let index = match self.state.stroke_insert_position {
+            StrokeInsertPosition::Top => len,
+            StrokeInsertPosition::CurrentLayer => self.state.board.insert_cursor().unwrap_or(len),
+            StrokeInsertPosition::Bottom => self.state.board.insert_cursor().unwrap_or(0),
+        };
+
+        let mut object = CanvasObject::Stroke(stroke);
+        if let Some(grid) = self.grid_snap() {
+            Self::snap_object_to_grid(&mut object, grid);
+        }
+        self.state.board.insert_object(index, object);
+
+        // 后续笔迹依次叠放在刚插入的笔迹之上
+        if self.state.stroke_insert_position != StrokeInsertPosition::Top {
+            self.state.board.stroke_insert_cursor = Some(index + 1);
+        }
+        index
+    }
+
+    // 指针在绘制中途离开窗口（PointerGone）时提交未完成的笔画，不依赖 drag_stopped 是否触发
+    fn handle_pointer_leave(&mut self, ctx: &egui::Context) {
+        if self.state.commit_on_pointer_leave
+            && self.state.is_drawing
+            && ctx.input(|i| {
+                i.events
+                    .iter()
+                    .any(|event| matches!(event, egui::Event::PointerGone))
+            })
+        {
+            self.finish_active_strokes(ToolSwitchStrokeBehavior::Commit);
+        }
+    }
+
+    // 结束所有正在绘制的笔画（例如切换工具时），按设置提交或丢弃
+    fn finish_active_strokes(&mut self, behavior: ToolSwitchStrokeBehavior) {
+        let active_strokes: Vec<ActiveStroke> = self
+            .state
+            .active_strokes
+            .drain()
+            .map(|(_touch_id, active_stroke)| active_stroke)
+            .collect();
+
+        // 折线工具未完成的路径按同样的方式处理
+        if behavior == ToolSwitchStrokeBehavior::Commit {
+            for active_stroke in active_strokes {
+                self.commit_active_stroke(active_stroke);
+            }
+            self.finish_pen_path();
+        } else {
+            self.state.pen_points.clear();
+        }
+
+        self.state.is_drawing = false;
+    }
+
+    // 完成折线：至少两个顶点时提交为笔画，启用平滑时先平滑为曲线
+    fn finish_pen_path(&mut self) {
+        if self.state.canvas_locked {
+            return;
+        }
+        let points = std::mem::take(&mut self.state.pen_points);
+        if points.len() < 2 {
+            return;
+        }
+
+        let points = if self.state.pen_smooth {
+            AppUtils::apply_stroke_smoothing(&points, self.state.smoothing_iterations)
+        } else {
+            points
+        };
+        self.record_brush_color(self.state.brush_color);
+        let (color, base_width) = self.brush_style(PointerInputType::Pen);
+        self.insert_stroke(crate::state::CanvasStroke {
+            widths: vec![base_width; points.len()],
+            forces: Vec::new(),
+            points,
+            color,
+            base_width,
+            follow_background: false,
+            input_type: PointerInputType::Pen,
+            highlighter: self.state.highlighter,
+            fade: None,
+            cache: Default::default(),
+        });
+    }
+
+    // 新插入形状的填充颜色
+    fn shape_fill(&self) -> Option<Color32> {
+        self.state
+            .shape_filled
+            .then_some(self.state.shape_fill_color)
+    }
+
+    // 插入形状：默认放在固定位置，启用点击放置时等待下一次点击画布
+    fn insert_shape(&mut self, shape_type: CanvasShapeType) {
+        if self.state.tap_place_shapes {
+            self.state.pending_shape_placement = Some(shape_type);
+            self.state.show_shape_dialog = false;
+            return;
+        }
+
+        self.state.push_undo_snapshot();
+        self.state
+            .board
+            .add_object(CanvasObject::Shape(CanvasShape {
+                shape_type,
+                pos: Pos2::new(100.0, 100.0),
+                size: DEFAULT_SHAPE_SIZE,
+                color: Color32::WHITE,
+                rotation: 0.0,
+                stroke_width: self.state.shape_stroke_width,
+                fill: self.shape_fill(),
+                arrow_heads: self.state.arrow_heads,
+                height: None,
+                corner_radius: 0.0,
+                outline: Vec::new(),
+            }));
+        self.state.show_shape_dialog = self.state.keep_insertion_window_open;
+    }
+
+    // 以点击位置为中心放置默认尺寸的形状，并打开尺寸输入
+    fn place_shape_at(&mut self, shape_type: CanvasShapeType, center: Pos2) {
+        let mut shape = CanvasShape {
+            shape_type,
+            pos: center,
+            size: DEFAULT_SHAPE_SIZE,
+            color: Color32::WHITE,
+            rotation: 0.0,
+            stroke_width: self.state.shape_stroke_width,
+            fill: self.shape_fill(),
+            arrow_heads: self.state.arrow_heads,
+            height: None,
+            corner_radius: 0.0,
+            outline: Vec::new(),
+        };
+        Self::center_shape(&mut shape, center);
+
+        self.state.push_undo_snapshot();
+        self.add_object_on_grid(CanvasObject::Shape(shape));
+        self.state.shape_size_entry = Some(ShapeSizeEntry {
+            object_index: self.state.board.len() - 1,
+            center,
+            text: format!("{DEFAULT_SHAPE_SIZE}"),
+        });
+    }
+
+    // 平移形状，使其包围盒中心位于指定位置
+    fn center_shape(shape: &mut CanvasShape, center: Pos2) {
+        let bounds = AppUtils::calculate_shape_bounding_box(shape);
+        shape.pos += center - bounds.center();
+    }
+
+    // 形状尺寸输入框：Enter 确认，Esc 恢复默认尺寸
+    fn render_shape_size_entry(&mut self, ctx: &egui::Context) {
+        let Some(entry) = self.state.shape_size_entry.as_mut() else {
+            return;
+        };
+
+        let mut confirmed = false;
+        let mut reverted = false;
+        egui::Window::new("形状尺寸")
+            .id(egui::Id::new("shape_size_entry"))
+            .collapsible(false)
+            .resizable(false)
+            .title_bar(false)
+            .fixed_pos(entry.center + egui::vec2(20.0, 20.0))
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("尺寸:");
+                    let response = ui.add(
+                        egui::TextEdit::singleline(&mut entry.text)
+                            .desired_width(80.0)
+                            .hint_text("像素"),
+                    );
+                    if !response.has_focus() && !response.lost_focus() {
+                        response.request_focus();
+                    }
+                    ui.label("px");
+
+                    confirmed = ui.button("确认").clicked()
+                        || (response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)));
+                    reverted = ui.button("默认").clicked()
+                        || ui.input(|i| i.key_pressed(egui::Key::Escape));
+                });
+            });
+
+        if !confirmed && !reverted {
+            return;
+        }
+
+        let size = if reverted {
+            Some(DEFAULT_SHAPE_SIZE)
+        } else {
+            entry
+                .text
+                .trim()
+                .parse::<f32>()
+                .ok()
+                .filter(|size| size.is_finite() && *size > 0.0)
+        };
+        let Some(size) = size else {
+            // 输入无效时保留输入框，等待重新输入
+            return;
+        };
+
+        let center = entry.center;
+        if let Some(CanvasObject::Shape(shape)) = self.state.board.get_mut(entry.object_index) {
+            shape.size = size;
+            Self::center_shape(shape, center);
+        }
+        self.state.shape_size_entry = None;
+    }
+
+    // 编辑文本对象：修改内容、颜色与字号，确认后写回（可撤销）
+    fn render_text_editor(&mut self, ctx: &egui::Context) {
+        let (Some(index), Some(draft)) = (
+            self.state.editing_text,
+            self.state.editing_text_draft.as_mut(),
+        ) else {
+            return;
+        };
+
+        let mut confirmed = false;
+        let mut cancelled = false;
+        egui::Window::new("编辑文本")
+            .id(egui::Id::new("text_editor"))
+            .collapsible(false)
+            .resizable(false)
+            .default_pos(draft.pos)
+            .show(ctx, |ui| {
+                ui.add(egui::TextEdit::multiline(&mut draft.text).desired_rows(3));
+                ui.horizontal(|ui| {
+                    ui.label("颜色:");
+                    ui.color_edit_button_srgba(&mut draft.color);
+                    ui.label("字号:");
+                    ui.add(egui::Slider::new(&mut draft.font_size, 8.0..=128.0));
+                });
+                Self::text_style_editor(ui, &mut draft.font, &mut draft.bold, &mut draft.italic);
+                ui.horizontal(|ui| {
+                    ui.label("对齐:");
+                    ui.selectable_value(&mut draft.align, TextAlign::Left, "左对齐");
+                    ui.selectable_value(&mut draft.align, TextAlign::Center, "居中");
+                    ui.selectable_value(&mut draft.align, TextAlign::Right, "右对齐");
+                    if draft.max_width.is_some() && ui.button("取消换行").clicked() {
+                        draft.max_width = None;
+                    }
+                });
+                ui.horizontal(|ui| {
+                    confirmed = ui.button("确认").clicked();
+                    cancelled = ui.button("取消").clicked()
+                        || ui.input(|i| i.key_pressed(egui::Key::Escape));
+                });
+            });
+
+        if !confirmed && !cancelled {
+            return;
+        }
+
+        let draft = self.state.editing_text_draft.take();
+        self.state.editing_text = None;
+        if confirmed
+            && let Some(draft) = draft
+            && matches!(self.state.board.get(index), Some(CanvasObject::Text(_)))
+        {
+            self.state.push_undo_snapshot();
+            if let Some(CanvasObject::Text(text)) = self.state.board.get_mut(index) {
+                text.text = draft.text;
+                text.color = draft.color;
+                text.font_size = draft.font_size;
+                text.align = draft.align;
+                text.max_width = draft.max_width;
+                text.font = draft.font;
+                text.bold = draft.bold;
+                text.italic = draft.italic;
+            }
+        }
+    }
+
+    // 文本字体与粗体、斜体选项（插入文本与编辑文本共用）
+    fn text_style_editor(
+        ui: &mut egui::Ui,
+        font: &mut TextFont,
+        bold: &mut bool,
+        italic: &mut bool,
+    ) {
+        ui.horizontal(|ui| {
+            ui.label("字体:");
+            ui.selectable_value(font, TextFont::Cjk, "中文");
+            ui.selectable_value(font, TextFont::Monospace, "等宽");
+            ui.selectable_value(font, TextFont::Default, "默认");
+            ui.toggle_value(bold, egui::RichText::new("B").strong());
+            ui.toggle_value(italic, egui::RichText::new("I").italics());
+        });
+    }
+
+    // 切换到指定页面
+    fn switch_page(&mut self, index: usize) {
+        if index == self.state.current_page || index >= self.state.pages.len() {
+            return;
+        }
+
+        self.finish_active_strokes(self.state.tool_switch_stroke_behavior);
+        self.clear_page_local_state();
+
+        let current_objects = self.state.board.take_objects();
+        if let Some(page) = self.state.pages.get_mut(self.state.current_page) {
+            *page = current_objects;
+        }
+        if let Some(page) = self.state.pages.get_mut(index) {
+            self.state.board.set_objects(std::mem::take(page));
+        }
+        self.state.current_page = index;
+        self.state.undo_stack.clear();
+        self.state.redo_stack.clear();
+    }
+
+    // 在当前页之后新建空白页并切换过去
+    fn add_page(&mut self) {
+        let index = self.state.current_page + 1;
+        self.state.pages.insert(index, Vec::new());
+        self.switch_page(index);
+    }
+
+    // 调整页面顺序，当前页随之移动
+    fn move_page(&mut self, from: usize, to: usize) {
+        let page_count = self.state.pages.len();
+        if from == to || from >= page_count || to >= page_count {
+            return;
+        }
+
+        let page = self.state.pages.remove(from);
+        self.state.pages.insert(to, page);
+
+        let current = self.state.current_page;
+        self.state.current_page = if current == from {
+            to
+        } else if from < current && current <= to {
+            current - 1
+        } else if to <= current && current < from {
+            current + 1
+        } else {
+            current
+        };
+    }
+
+    // 清除与当前页对象索引相关的临时状态
+    fn clear_page_local_state(&mut self) {
+        self.state.selected_object = None;
+        self.state.selected_objects.clear();
+        self.state.marquee_start = None;
+        self.state.board.stroke_insert_cursor = None;
+        self.state.area_measurement = None;
+        self.state.protractor = None;
+        self.state.distance_measurement = None;
+        self.state.soft_erased_strokes.clear();
+        self.state.resize_operation = None;
+        self.state.rotation_operation = None;
+        self.state.endpoint_operation = None;
+        self.state.shape_size_entry = None;
+        self.state.editing_text = None;
+        self.state.editing_text_draft = None;
+        self.state.crop_operation = None;
+    }
+
+    // 图片属性面板中的操作（作用于选中的图片）
+    fn handle_image_action(&mut self, ctx: &egui::Context, action: ImageAction) {
+        match action {
+            ImageAction::StartCrop => {
+                self.state.crop_operation = self.state.selected_object.map(|index| CropOperation {
+                    index,
+                    start: None,
+                    rect: None,
+                });
+            }
+            ImageAction::ApplyCrop => {
+                if let Some(crop) = self.state.crop_operation.take()
+                    && let Some(rect) = crop.rect
+                {
+                    self.state.push_undo_snapshot();
+                    if let Some(CanvasObject::Image(img)) = self.state.board.get_mut(crop.index) {
+                        img.crop_to(ctx, rect);
+                    }
+                }
+            }
+            ImageAction::CancelCrop => self.state.crop_operation = None,
+            ImageAction::ResetCrop => self.edit_selected_image(|img| img.reset_crop(ctx)),
+            ImageAction::FlipHorizontal => self.edit_selected_image(|img| img.flip_x = !img.flip_x),
+            ImageAction::FlipVertical => self.edit_selected_image(|img| img.flip_y = !img.flip_y),
+            ImageAction::RotateClockwise => {
+                self.edit_selected_image(|img| img.rotate_clockwise(ctx));
+            }
+        }
+    }
+
+    // 记录撤销快照后修改选中的图片
+    fn edit_selected_image(&mut self, edit: impl FnOnce(&mut CanvasImage)) {
+        let Some(index) = self.state.selected_object else {
+            return;
+        };
+        if !matches!(self.state.board.get(index), Some(CanvasObject::Image(_))) {
+            return;
+        }
+        self.state.push_undo_snapshot();
+        if let Some(CanvasObject::Image(img)) = self.state.board.get_mut(index) {
+            edit(img);
+        }
+    }
+
+    // 选中的直线或箭头的端点
+    fn selected_line_endpoints(&self) -> Option<(Pos2, Pos2)> {
+        match self
+            .state
+            .selected_object
+            .and_then(|index| self.state.board.get(index))
+        {
+            Some(CanvasObject::Shape(shape))
+                if matches!(
+                    shape.shape_type,
+                    CanvasShapeType::Line | CanvasShapeType::Arrow
+                ) =>
+            {
+                Some(AppUtils::line_endpoints(shape))
+            }
+            _ => None,
+        }
+    }
+
+    // 多选对象的整体边界
+    fn selection_bounds(&self, painter: &egui::Painter) -> Option<egui::Rect> {
+        self.state
+            .selected_objects
+            .iter()
+            .filter_map(|&index| self.state.board.get(index))
+            .map(|object| AppUtils::calculate_object_bounding_box(painter, object))
+            .reduce(egui::Rect::union)
+    }
+
+    // 复制选中的对象
+    fn copy_selected(&mut self) {
+        if let Some(object) = self
+            .state
+            .selected_object
+            .and_then(|index| self.state.board.get(index))
+        {
+            self.state.clipboard = Some(object.clone());
+        }
+    }
+
+    // 剪切选中的对象
+    fn cut_selected(&mut self) {
+        if self.state.canvas_locked {
+            return;
+        }
+        let Some(index) = self
+            .state
+            .selected_object
+            .filter(|index| *index < self.state.board.len())
+        else {
+            return;
+        };
+
+        self.state.push_undo_snapshot();
+        self.state.clipboard = self.state.board.remove(index);
+        self.clear_page_local_state();
+    }
+
+    // 删除选中的对象（多选时删除全部选中的对象）
+    fn delete_selected(&mut self) {
+        if self.state.canvas_locked {
+            return;
+        }
+        if !self.state.selected_objects.is_empty() {
+            self.state.push_undo_snapshot();
+            let indices = std::mem::take(&mut self.state.selected_objects);
+            self.state.board.remove_indices(&indices);
+            self.clear_page_local_state();
+            return;
+        }
+        if let Some(index) = self
+            .state
+            .selected_object
+            .filter(|index| *index < self.state.board.len())
+        {
+            self.state.push_undo_snapshot();
+            self.state.board.remove(index);
+            self.clear_page_local_state();
+        }
+    }
+
+    // 在原位置稍作偏移复制一份选中的对象，并选中副本
+    fn duplicate_selected(&mut self) {
+        const DUPLICATE_OFFSET: egui::Vec2 = egui::vec2(10.0, 10.0);
+
+        if self.state.canvas_locked {
+            return;
+        }
+        let Some(mut object) = self
+            .state
+            .selected_object
+            .and_then(|index| self.state.board.get(index))
+            .cloned()
+        else {
+            return;
+        };
+
+        object.translate(DUPLICATE_OFFSET);
+        self.state.push_undo_snapshot();
+        self.clear_page_local_state();
+        self.add_object_on_grid(object);
+        self.state.selected_object = Some(self.state.board.len() - 1);
+    }
+
+    // 将选中的对象移到最上层或最底层，移动后按对象标识恢复选择
+    fn reorder_selected(&mut self, to_front: bool) {
+        if self.state.canvas_locked {
+            return;
+        }
+        let board = &self.state.board;
+        let selected_objects: Vec<ObjectId> = self
+            .state
+            .selected_objects
+            .iter()
+            .filter_map(|&index| board.id_at(index))
+            .collect();
+        let selected_object = self
+            .state
+            .selected_object
+            .and_then(|index| board.id_at(index));
+        let ids: Vec<ObjectId> = selected_objects
+            .iter()
+            .copied()
+            .chain(selected_object)
+            .collect();
+        if ids.is_empty() {
+            return;
+        }
+
+        self.state.push_undo_snapshot();
+        self.clear_page_local_state();
+        if to_front {
+            self.state.board.bring_to_front(&ids);
+        } else {
+            self.state.board.send_to_back(&ids);
+        }
+        let board = &self.state.board;
+        self.state.selected_object = selected_object.and_then(|id| board.index_of(id));
+        self.state.selected_objects = selected_objects
+            .into_iter()
+            .filter_map(|id| board.index_of(id))
+            .collect();
+    }
+
+    // 粘贴对象并选中：指针在画布内时以指针位置为中心，否则相对原对象偏移
+    fn paste_clipboard(&mut self, ctx: &egui::Context) {
+        const PASTE_OFFSET: egui::Vec2 = egui::vec2(20.0, 20.0);
+
+        if self.state.canvas_locked {
+            return;
+        }
+        let Some(mut object) = self.state.clipboard.clone() else {
+            return;
+        };
+
+        let pointer_pos = ctx
+            .pointer_latest_pos()
+            .filter(|pos| {
+                self.state
+                    .last_canvas_rect
+                    .is_some_and(|rect| rect.contains(*pos))
+            })
+            .map(|pos| self.state.view_transform().inverse() * pos);
+        if let Some(pos) = pointer_pos {
+            let painter = ctx.layer_painter(egui::LayerId::background());
+            let center = AppUtils::calculate_object_bounding_box(&painter, &object).center();
+            object.translate(pos - center);
+        } else {
+            object.translate(PASTE_OFFSET);
+            // 连续粘贴时依次错开
+            if let Some(clipboard) = self.state.clipboard.as_mut() {
+                clipboard.translate(PASTE_OFFSET);
+            }
+        }
+
+        self.state.push_undo_snapshot();
+        self.clear_page_local_state();
+        self.add_object_on_grid(object);
+        self.state.selected_object = Some(self.state.board.len() - 1);
+    }
+
+    // 撤销上一次修改（绘制中或锁定画布时不响应）
+    fn undo(&mut self) {
+        if self.state.canvas_locked || self.state.is_drawing {
+            return;
+        }
+        if self.state.undo() {
+            self.clear_page_local_state();
+        }
+    }
+
+    // 重做上一次撤销的修改
+    fn redo(&mut self) {
+        if self.state.canvas_locked || self.state.is_drawing {
+            return;
+        }
+        if self.state.redo() {
+            self.clear_page_local_state();
+        }
+    }
+
+    // 将当前页保存为画板文件
+    fn save_board(&mut self) {
+        let future = async {
+            rfd::AsyncFileDialog::new()
+                .add_filter("画板", &[DOCUMENT_EXTENSION])
+                .set_file_name(format!("board.{DOCUMENT_EXTENSION}"))
+                .save_file()
+                .await
+        };
+
+        if let Some(path) = futures::executor::block_on(future) {
+            let result = SerializableAppState::from_state(&self.state)
+                .to_json()
+                .map_err(|err| err.to_string())
+                .and_then(|json| std::fs::write(path.path(), json).map_err(|err| err.to_string()));
+            match result {
+                Ok(()) => self.show_toast("已保存画板"),
+                Err(err) => log::error!("Failed to save board: {err}"),
+            }
+        }
+    }
+
+    // 打开画板文件，替换当前页的内容（可撤销）
+    fn open_board(&mut self, ctx: &egui::Context) {
+        let future = async {
+            rfd::AsyncFileDialog::new()
+                .add_filter("画板", &[DOCUMENT_EXTENSION])
+                .pick_file()
+                .await
+        };
+
+        if let Some(path) = futures::executor::block_on(future) {
+            match std::fs::read_to_string(path.path())
+                .map_err(|err| err.to_string())
+                .and_then(|json| {
+                    SerializableAppState::from_json(&json).map_err(|err| err.to_string())
+                }) {
+                Ok(document) => {
+                    self.finish_active_strokes(self.state.tool_switch_stroke_behavior);
+                    self.state.push_undo_snapshot();
+                    self.clear_page_local_state();
+                    document.apply(ctx, &mut self.state);
+                    self.show_toast("已打开画板");
+                }
+                Err(err) => {
+                    log::error!("Failed to open board: {err}");
+                    self.show_toast(format!("无法打开画板: {err}"));
+                }
+            }
+        }
+    }
+
+    // 将调色板与默认样式导出为 JSON 文件
+    fn export_shared_config(&self) {
+        let future = async {
+            rfd::AsyncFileDialog::new()
+                .add_filter("配置", &["json"])
+                .set_file_name("smartboard-config.json")
+                .save_file()
+                .await
+        };
+
+        if let Some(path) = futures::executor::block_on(future) {
+            let result = SharedConfig::from_state(&self.state)
+                .to_json()
+                .map_err(|err| err.to_string())
+                .and_then(|json| std::fs::write(path.path(), json).map_err(|err| err.to_string()));
+            if let Err(err) = result {
+                log::error!("Failed to export config: {err}");
+            }
+        }
+    }
+
+    // 将当前页所有对象的结构信息导出为 JSON 文件
+    fn export_object_metadata(&mut self) {
+        let future = async {
+            rfd::AsyncFileDialog::new()
+                .add_filter("对象信息", &["json"])
+                .set_file_name("smartboard-objects.json")
+                .save_file()
+                .await
+        };
+
+        if let Some(path) = futures::executor::block_on(future) {
+            let canvas_size = self
+                .state
+                .last_canvas_rect
+                .map_or(egui::Vec2::ZERO, |rect| rect.size());
+            let result = self
+                .state
+                .board
+                .metadata(canvas_size, self.state.background_color)
+                .to_json()
+                .map_err(|err| err.to_string())
+                .and_then(|json| std::fs::write(path.path(), json).map_err(|err| err.to_string()));
+            match result {
+                Ok(()) => self.show_toast("已导出对象信息"),
+                Err(err) => log::error!("Failed to export object metadata: {err}"),
+            }
+        }
+    }
+
+    // 将对象导出为 SVG 文件
+    fn export_svg(objects: &[CanvasObject]) {
+        let future = async {
+            rfd::AsyncFileDialog::new()
+                .add_filter("SVG", &["svg"])
+                .set_file_name("smartboard.svg")
+                .save_file()
+                .await
+        };
+
+        if let Some(path) = futures::executor::block_on(future)
+            && let Err(err) = std::fs::write(path.path(), crate::svg_export::to_svg(objects))
+        {
+            log::error!("Failed to export SVG: {err}");
+        }
+    }
+
+    // 从 JSON 文件读取配置，等待用户确认合并或替换
+    fn import_shared_config(&mut self) {
+        let future = async {
+            rfd::AsyncFileDialog::new()
+                .add_filter("配置", &["json"])
+                .pick_file()
+                .await
+        };
+
+        if let Some(path) = futures::executor::block_on(future) {
+            match std::fs::read_to_string(path.path())
+                .map_err(|err| err.to_string())
+                .and_then(|json| SharedConfig::from_json(&json).map_err(|err| err.to_string()))
+            {
+                Ok(config) => self.state.pending_config_import = Some(config),
+                Err(err) => log::error!("Failed to import config: {err}"),
+            }
+        }
+    }
+
+    // 切换工具，并清理与上一个工具相关的状态
+    fn switch_tool(&mut self, tool: CanvasTool) {
+        if self.state.current_tool == tool {
+            return;
+        }
+        let previous_tool = self.state.current_tool;
+        // 最近使用的工具：去重后追加，只保留少量记录
+        const MAX_RECENT_TOOLS: usize = 4;
+        self.state
+            .recent_tools
+            .retain(|recent| *recent != previous_tool && *recent != tool);
+        self.state.recent_tools.push(previous_tool);
+        if self.state.recent_tools.len() > MAX_RECENT_TOOLS {
+            self.state.recent_tools.remove(0);
+        }
+        if let Some(settings) = ToolSettings::capture(previous_tool, &self.state) {
+            self.state.tool_settings.insert(previous_tool, settings);
+        }
+        self.finish_active_strokes(self.state.tool_switch_stroke_behavior);
+
+        self.state.current_tool = tool;
+        if let Some(settings) = self.state.tool_settings.get(&tool).copied() {
+            settings.apply(tool, &mut self.state);
+        }
+        if self.state.stroke_insert_position == StrokeInsertPosition::CurrentLayer
+            && let Some(selected_idx) = self.state.selected_object
+        {
+            // 当前层：新笔迹置于最近选中的对象之上
+            self.state.board.stroke_insert_cursor = Some(selected_idx + 1);
+        }
+        self.state.selected_object = None;
+        self.state.selected_objects.clear();
+        self.state.marquee_start = None;
+        self.state.area_measurement = None;
+        self.state.distance_measurement = None;
+        self.state.pending_shape_placement = None;
+        self.state.eyedropper_active = false;
+        self.state.editing_text = None;
+        self.state.editing_text_draft = None;
+        self.state.crop_operation = None;
+    }
+
+    // 快速切换：在最近使用的两个工具或自定义的两个工具之间来回切换
+    fn quick_toggle_tool(&mut self) {
+        let target = match self.state.quick_toggle_mode {
+            QuickToggleMode::Recent => self.state.recent_tools.last().copied(),
+            QuickToggleMode::Custom => {
+                let [first, second] = self.state.quick_toggle_pair;
+                Some(if self.state.current_tool == first {
+                    second
+                } else {
+                    first
+                })
+            }
+        };
+        if let Some(tool) = target {
+            self.switch_tool(tool);
+        }
+    }
+
+    // 填充点击位置所在的封闭区域：填充区域作为形状放在底层（已有的重叠填充区域之上），使围成区域的笔迹保持可见
+    // 漫水填充限制在当前可见范围内，区域未封闭时提示用户
+    fn fill_region_at(&mut self, pos: Pos2, visible_rect: egui::Rect) {
+        match AppUtils::flood_fill_region(self.state.board.objects(), pos, visible_rect) {
+            Ok(outline) => {
+                // 在已填充的范围内再次填充时新区域需要位于旧区域之上，颜色的改变才可见
+                let bounds = egui::Rect::from_points(&outline);
+                let index = self
+                    .state
+                    .board
+                    .objects()
+                    .iter()
+                    .rposition(|object| match object {
+                        CanvasObject::Shape(shape)
+                            if matches!(shape.shape_type, CanvasShapeType::Region) =>
+                        {
+                            AppUtils::calculate_shape_bounding_box(shape).intersects(bounds)
+                        }
+                        _ => false,
+                    })
+                    .map_or(0, |index| index + 1);
+
+                self.record_brush_color(self.state.brush_color);
+                self.state.push_undo_snapshot();
+                self.state.board.insert_object(
+                    index,
+                    CanvasObject::Shape(AppUtils::region_shape(&outline, self.state.brush_color)),
+                );
+            }
+            Err(message) => self.show_toast(message),
+        }
+    }
+
+    // 测量点击位置处对象的面积：优先选择包含该点的封闭对象，否则选择被点中的对象
+    fn measure_area_at(&mut self, painter: &egui::Painter, pos: Pos2) {
+        let enclosing = self
+            .state
+            .board
+            .objects()
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(i, object)| {
+                AppUtils::closed_object_polygon(object)
+                    .filter(|polygon| AppUtils::point_in_polygon(pos, polygon))
+                    .map(|polygon| (i, Some(polygon)))
+            });
+        let measured = enclosing.or_else(|| {
+            self.state.board.hit_test(pos, painter).map(|i| {
+                let polygon = self
+                    .state
+                    .board
+                    .get(i)
+                    .and_then(AppUtils::closed_object_polygon);
+                (i, polygon)
+            })
+        });
+
+        let Some((object_index, polygon)) = measured else {
+            self.state.area_measurement = None;
+            return;
+        };
+
+        let area = polygon.as_deref().map(AppUtils::polygon_area);
+        self.state.area_measurement = Some(crate::state::AreaMeasurement { object_index, area });
+
+        if self.state.area_measure_place_label
+            && !self.state.canvas_locked
+            && let (Some(area), Some(polygon)) = (area, polygon)
+        {
+            let label_pos = egui::Rect::from_points(&polygon).center();
+            self.state.push_undo_snapshot();
+            self.state.board.add_object(CanvasObject::Text(CanvasText {
+                text: format!("{area:.1} px²"),
+                pos: label_pos,
+                color: self.state.brush_color,
+                font_size: 16.0,
+                max_width: None,
+                align: TextAlign::Left,
+                font: TextFont::Cjk,
+                bold: false,
+                italic: false,
+            }));
+        }
+    }
+
+    // 量角器：第一次点击放置量角器，之后依次放置角的两条边，角测量完成后再次点击重新放置量角器
+    fn place_protractor_point(&mut self, pos: Pos2) {
+        let measurement = match self.state.protractor {
+            Some(measurement @ ProtractorMeasurement { first: None, .. }) => {
+                ProtractorMeasurement {
+                    first: Some(pos),
+                    ..measurement
+                }
+            }
+            Some(measurement @ ProtractorMeasurement { second: None, .. }) => {
+                ProtractorMeasurement {
+                    second: Some(pos),
+                    ..measurement
+                }
+            }
+            _ => ProtractorMeasurement {
+                center: pos,
+                first: None,
+                second: None,
+            },
+        };
+        self.state.protractor = Some(measurement);
+
+        let ProtractorMeasurement {
+            center,
+            first: Some(first),
+            second: Some(second),
+        } = measurement
+        else {
+            return;
+        };
+        if !self.state.protractor_place_shapes || self.state.canvas_locked {
+            return;
+        }
+
+        self.state.push_undo_snapshot();
+        for end in [first, second] {
+            self.add_measurement_line(center, end, ArrowHeads::End, CanvasShapeType::Line);
+        }
+        let (start, sweep) = AppUtils::ray_sweep(center, first, second);
+        // 标签放在角平分线上
+        self.add_measurement_label(
+            center + egui::Vec2::angled(start + sweep / 2.0) * 50.0,
+            format!("{:.1}°", sweep.abs().to_degrees()),
+        );
+    }
+
+    // 距离测量的显示文本：长度与相对水平方向的角度（逆时针为正）
+    fn distance_readout(&self, start: Pos2, end: Pos2) -> String {
+        let delta = end - start;
+        format!(
+            "{}，{:.1}°",
+            AppUtils::format_length(delta.length(), self.state.pixels_per_cm),
+            -delta.angle().to_degrees()
+        )
+    }
+
+    // 距离测量结束：按设置放置两端带箭头的尺寸线与长度标签
+    fn place_distance_annotation(&mut self) {
+        // 短于此长度的拖动视为误触，不放置标注
+        const MIN_ANNOTATION_LENGTH: f32 = 4.0;
+
+        let Some((start, end)) = self.state.distance_measurement else {
+            return;
+        };
+        if !self.state.distance_place_annotation
+            || self.state.canvas_locked
+            || start.distance(end) < MIN_ANNOTATION_LENGTH
+        {
+            return;
+        }
+
+        self.state.push_undo_snapshot();
+        self.add_measurement_line(start, end, ArrowHeads::Both, CanvasShapeType::Arrow);
+        // 标签放在尺寸线中点的一侧
+        let offset = (end - start).normalized().rot90() * -20.0;
+        self.add_measurement_label(
+            start.lerp(end, 0.5) + offset,
+            AppUtils::format_length(start.distance(end), self.state.pixels_per_cm),
+        );
+    }
+
+    // 添加一条测量结果的直线或箭头（使用画笔颜色与形状轮廓宽度）
+    fn add_measurement_line(
+        &mut self,
+        start: Pos2,
+        end: Pos2,
+        arrow_heads: ArrowHeads,
+        shape_type: CanvasShapeType,
+    ) {
+        let mut line = CanvasShape {
+            shape_type,
+            pos: start,
+            size: 0.0,
+            color: self.state.brush_color,
+            rotation: 0.0,
+            stroke_width: self.state.shape_stroke_width,
+            fill: None,
+            arrow_heads,
+            height: None,
+            corner_radius: 0.0,
+            outline: Vec::new(),
+        };
+        AppUtils::set_line_endpoints(&mut line, start, end);
+        self.state.board.add_object(CanvasObject::Shape(line));
+    }
+
+    // 添加一个以 center 为中心的测量结果标签
+    fn add_measurement_label(&mut self, center: Pos2, label: String) {
+        const FONT_SIZE: f32 = 16.0;
+
+        let label_size = egui::vec2(label.chars().count() as f32 * FONT_SIZE / 2.0, FONT_SIZE);
+        self.state.board.add_object(CanvasObject::Text(CanvasText {
+            text: label,
+            pos: center - label_size / 2.0,
+            color: self.state.brush_color,
+            font_size: FONT_SIZE,
+            max_width: None,
+            align: TextAlign::Left,
+            font: TextFont::Cjk,
+            bold: false,
+            italic: false,
+        }));
+    }
+
+    // 导出图片的范围（画布坐标）与缩放：整个可见画布或所有对象的外接矩形，分辨率与屏幕显示一致
+    fn canvas_export_region(&self, ctx: &egui::Context) -> Option<(egui::Rect, f32)> {
+        const CONTENT_PADDING: f32 = 10.0;
+
+        let view = self.state.view_transform();
+        let visible = view.inverse() * self.state.last_canvas_rect?;
+        let region = match self.state.clipboard_export_area {
+            CanvasExportArea::FullCanvas => visible,
+            CanvasExportArea::ContentBounds => {
+                let painter = ctx.layer_painter(egui::LayerId::background());
+                self.state
+                    .board
+                    .objects()
+                    .iter()
+                    .map(|object| Board::bounding_box(object, &painter))
+                    .reduce(|a, b| a.union(b))
+                    .map(|bounds| bounds.expand(CONTENT_PADDING).intersect(visible))
+                    .filter(|bounds| bounds.is_positive())
+                    .unwrap_or(visible)
+            }
+        };
+
+        Some((region, view.scaling * ctx.pixels_per_point()))
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn copy_canvas_to_clipboard(&mut self, ctx: &egui::Context) {
+        let Some((region, scale)) = self.canvas_export_region(ctx) else {
+            return;
+        };
+        let fonts = ctx.fonts(|fonts| fonts.definitions().clone());
+        let image =
+            self.state
+                .board
+                .render_image(region, scale, self.state.background_color, &fonts);
+        match Self::copy_image_to_clipboard(&image) {
+            Ok(()) => self.show_toast("已复制画布到剪贴板"),
+            Err(err) => {
+                log::error!("Failed to copy canvas to clipboard: {err}");
+                self.show_toast(format!("复制到剪贴板失败: {err}"));
+            }
+        }
+    }
+
+    // 将画布导出为 PNG 图片（范围与复制到剪贴板相同）
+    fn export_png(&mut self, ctx: &egui::Context) {
+        let future = async {
+            rfd::AsyncFileDialog::new()
+                .add_filter("PNG", &["png"])
+                .set_file_name("smartboard.png")
+                .save_file()
+                .await
+        };
+
+        if let Some(path) = futures::executor::block_on(future)
+            && let Some((region, scale)) = self.canvas_export_region(ctx)
+        {
+            let fonts = ctx.fonts(|fonts| fonts.definitions().clone());
+            let result = self
+                .state
+                .board
+                .export_png(region, scale, self.state.background_color, &fonts)
+                .and_then(|png| std::fs::write(path.path(), png).map_err(|err| err.to_string()));
+            match result {
+                Ok(()) => self.show_toast("已导出 PNG"),
+                Err(err) => log::error!("Failed to export PNG: {err}"),
+            }
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn copy_image_to_clipboard(image: &egui::ColorImage) -> Result<(), arboard::Error> {
+        let [width, height] = image.size;
+        let bytes: Vec<u8> = image.pixels.iter().flat_map(|p| p.to_array()).collect();
+        arboard::Clipboard::new()?.set_image(arboard::ImageData {
+            width,
+            height,
+            bytes: bytes.into(),
+        })
+    }
+
+    fn show_toast(&mut self, message: impl Into<String>) {
+        self.state.toast = Some((message.into(), Instant::now()));
+    }
+
+    // 在屏幕顶部显示提示消息，数秒后自动消失
+    fn render_toast(&mut self, ctx: &egui::Context) {
+        const TOAST_DURATION: f32 = 2.5;
+
+        let Some((message, shown_at)) = &self.state.toast else {
+            return;
+        };
+
+        if shown_at.elapsed().as_secs_f32() > TOAST_DURATION {
+            self.state.toast = None;
+            return;
+        }
+
+        egui::Area::new(egui::Id::new("toast"))
+            .anchor(egui::Align2::CENTER_TOP, [0.0, 20.0])
+            .interactable(false)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.label(message.as_str());
+                });
+            });
+        ctx.request_repaint_after(std::time::Duration::from_millis(100));
+    }
+
+    // 画布区域变化时按设置平移所有对象，避免内容滑出可见区域
+    fn handle_canvas_resize(&mut self, rect: egui::Rect) {
+        let Some(last_rect) = self.state.last_canvas_rect.replace(rect) else {
+            return;
+        };
+        if last_rect == rect {
+            return;
+        }
+
+        let delta = match self.state.canvas_resize_behavior {
+            CanvasResizeBehavior::Keep => return,
+            CanvasResizeBehavior::Center => rect.center() - last_rect.center(),
+            CanvasResizeBehavior::TopLeft => rect.min - last_rect.min,
+        };
+        if delta == egui::Vec2::ZERO {
+            return;
+        }
+
+        for object in self.state.board.objects_mut() {
+            object.translate(delta);
+        }
+    }
+
+    // 绘制点击波纹：圆环逐渐扩大并淡出，动画期间持续重绘
+    fn draw_tap_ripples(&mut self, ui: &egui::Ui, painter: &egui::Painter) {
+        const RIPPLE_DURATION: f32 = 0.6;
+        const RIPPLE_MAX_RADIUS: f32 = 40.0;
+
+        self.state
+            .tap_ripples
+            .retain(|(_, start)| start.elapsed().as_secs_f32() < RIPPLE_DURATION);
+        if self.state.tap_ripples.is_empty() {
+            return;
+        }
+
+        for (pos, start) in &self.state.tap_ripples {
+            let progress = start.elapsed().as_secs_f32() / RIPPLE_DURATION;
+            let color = self.state.tap_ripple_color.gamma_multiply(1.0 - progress);
+            painter.circle_stroke(
+                *pos,
+                RIPPLE_MAX_RADIUS * progress,
+                Stroke::new(3.0 * (1.0 - progress) + 1.0, color),
+            );
+        }
+        ui.ctx().request_repaint();
+    }
+
+    // 按住笔按钮（次要按钮）并画圈时循环切换快捷颜色，返回手势是否处于进行中
+    fn handle_color_gesture(
+        &mut self,
+        ui: &egui::Ui,
+        painter: &egui::Painter,
+        response: &egui::Response,
+    ) -> bool {
+        // 指针距中心太近时角度不稳定，不计入旋转
+        const MIN_GESTURE_RADIUS: f32 = 15.0;
+        // 每转过该角度切换一种颜色
+        const ANGLE_PER_COLOR: f32 = std::f32::consts::FRAC_PI_2;
+
+        let secondary_down = ui.input(|i| i.pointer.button_down(egui::PointerButton::Secondary));
+        if !self.state.pen_color_gesture_enabled
+            || self.state.quick_colors.is_empty()
+            || !secondary_down
+        {
+            self.state.color_gesture = None;
+            return false;
+        }
+
+        let Some(pos) = ui.input(|i| i.pointer.latest_pos()) else {
+            return self.state.color_gesture.is_some();
+        };
+
+        let gesture = if let Some(gesture) = self.state.color_gesture.as_mut() {
+            gesture
+        } else {
+            if !response.is_pointer_button_down_on() {
+                return false;
+            }
+            let start_index = self
+                .state
+                .quick_colors
+                .iter()
+                .position(|color| *color == self.state.brush_color)
+                .unwrap_or(0);
+            // 手势期间可能已开始的笔画直接丢弃
+            self.finish_active_strokes(ToolSwitchStrokeBehavior::Discard);
+            self.state
+                .color_gesture
+                .insert(crate::state::ColorGestureState {
+                    origin: pos,
+                    last_angle: None,
+                    accumulated_angle: 0.0,
+                    start_index,
+                })
+        };
+
+        let offset = pos - gesture.origin;
+        if offset.length() >= MIN_GESTURE_RADIUS {
+            let angle = offset.angle();
+            if let Some(last_angle) = gesture.last_angle {
+                // 将角度差规范到 -PI..PI，避免跨越 ±PI 时跳变
+                let mut delta = angle - last_angle;
+                if delta > std::f32::consts::PI {
+                    delta -= std::f32::consts::TAU;
+                } else if delta < -std::f32::consts::PI {
+                    delta += std::f32::consts::TAU;
+                }
+                gesture.accumulated_angle += delta;
+            }
+            gesture.last_angle = Some(angle);
+        }
+
+        let color_count = self.state.quick_colors.len() as i64;
+        let steps = (gesture.accumulated_angle / ANGLE_PER_COLOR).trunc() as i64;
+        let index = (gesture.start_index as i64 + steps).rem_euclid(color_count) as usize;
+        let origin = gesture.origin;
+
+        if let Some(color) = self.state.quick_colors.get(index) {
+            self.state.brush_color = *color;
+        }
+        AppUtils::draw_color_wheel(painter, origin, &self.state.quick_colors, index);
+        true
+    }
+
+    // 识别选中笔画（及与其相邻的笔画）中的手写文字，成功时替换为文本，失败时保留笔画
+    #[cfg(feature = "ocr")]
+    fn recognize_handwriting(&mut self) {
+        // 与选中笔画边界框相距在此范围内的笔画视为同一段手写内容
+        const GROUP_MARGIN: f32 = 30.0;
+
+        let Some((selected_idx, CanvasObject::Stroke(selected))) = self
+            .state
+            .selected_object
+            .and_then(|index| Some((index, self.state.board.get(index)?)))
+        else {
+            return;
+        };
+
+        let group_rect = AppUtils::calculate_stroke_bounding_box(selected).expand(GROUP_MARGIN);
+        let color = selected.color;
+        let indices: Vec<usize> = self
+            .state
+            .board
+            .objects()
+            .iter()
+            .enumerate()
+            .filter_map(|(index, object)| match object {
+                CanvasObject::Stroke(stroke)
+                    if index == selected_idx
+                        || AppUtils::calculate_stroke_bounding_box(stroke)
+                            .intersects(group_rect) =>
+                {
+                    Some(index)
+                }
+                _ => None,
+            })
+            .collect();
+        let strokes: Vec<&crate::state::CanvasStroke> = indices
+            .iter()
+            .filter_map(|index| match self.state.board.get(*index) {
+                Some(CanvasObject::Stroke(stroke)) => Some(stroke),
+                _ => None,
+            })
+            .collect();
+        let Some(bounds) = strokes
+            .iter()
+            .map(|stroke| AppUtils::calculate_stroke_bounding_box(stroke))
+            .reduce(|a, b| a.union(b))
+        else {
+            return;
+        };
+
+        match crate::ocr::recognize_strokes(&strokes, bounds) {
+            Ok(text) => {
+                let line_count = text.lines().count().max(1) as f32;
+                let font_size = (bounds.height() / line_count * 0.8).clamp(12.0, 96.0);
+                self.state.push_undo_snapshot();
+                self.state.board.remove_indices(&indices);
+                self.state.board.add_object(CanvasObject::Text(CanvasText {
+                    text,
+                    pos: bounds.min,
+                    color,
+                    font_size,
+                    max_width: None,
+                    align: TextAlign::Left,
+                    font: TextFont::Cjk,
+                    bold: false,
+                    italic: false,
+                }));
+                self.state.selected_object = None;
+                self.show_toast("已将手写内容替换为文本");
+            }
+            Err(err) => {
+                log::error!("Handwriting recognition failed: {err}");
+                self.show_toast(format!("识别失败: {err}"));
+            }
+        }
+    }
+
+    // 处理长按打开径向菜单，返回菜单是否处于打开状态（打开时不处理其他工具输入）
+    fn handle_radial_menu(
+        &mut self,
+        ui: &egui::Ui,
+        painter: &egui::Painter,
+        response: &egui::Response,
+    ) -> bool {
+        // 长按期间允许的最大移动距离
+        const HOLD_MOVE_TOLERANCE: f32 = 8.0;
+
+        let latest_pos = ui.input(|i| i.pointer.latest_pos());
+        let pointer_down = response.is_pointer_button_down_on();
+
+        if let Some(center) = self.state.radial_menu_center {
+            let hovered = latest_pos.and_then(|pos| {
+                AppUtils::radial_menu_item_at(center, pos, &self.state.quick_colors)
+            });
+            AppUtils::draw_radial_menu(painter, center, &self.state.quick_colors, hovered);
+
+            if !pointer_down {
+                // 松开时激活所在的菜单项
+                match hovered {
+                    Some(RadialMenuItem::Tool(tool)) => self.switch_tool(tool),
+                    Some(RadialMenuItem::Color(color)) => {
+                        self.switch_tool(CanvasTool::Brush);
+                        self.state.brush_color = color;
+                    }
+                    None => {}
+                }
+                self.state.radial_menu_center = None;
+            }
+            return true;
+        }
+
+        if !self.state.radial_menu_enabled || !pointer_down {
+            self.state.hold_state = None;
+            return false;
+        }
+
+        let Some(pos) = latest_pos else {
+            return false;
+        };
+
+        // 画笔已开始绘制时不检测长按，慢速书写时停顿不会打开菜单
+        let stroke_in_progress =
+            self.state.current_tool == CanvasTool::Brush && !self.state.active_strokes.is_empty();
+
+        match self.state.hold_state.as_mut() {
+            None => {
+                // 只有在空白画布上按下才开始长按检测
+                let canvas_pos = self.state.view_transform().inverse() * pos;
+                let on_empty_canvas = self.state.board.hit_test(canvas_pos, painter).is_none();
+                self.state.hold_state = Some(HoldState {
+                    origin: pos,
+                    start: Instant::now(),
+                    cancelled: !on_empty_canvas || stroke_in_progress,
+                });
+            }
+            Some(hold) if !hold.cancelled => {
+                if stroke_in_progress || pos.distance(hold.origin) > HOLD_MOVE_TOLERANCE {
+                    hold.cancelled = true;
+                } else if hold.start.elapsed().as_secs_f32() >= self.state.radial_menu_hold_duration
+                {
+                    let origin = hold.origin;
+                    self.state.hold_state = None;
+                    self.state.radial_menu_center = Some(origin);
+                    return true;
+                } else {
+                    ui.ctx().request_repaint();
+                }
+            }
+            Some(_) => {}
+        }
+
+        false
+    }
+
+    // 移除已完全消失的渐隐笔迹；存在渐隐笔迹时持续重绘，使动画在按需重绘模式下也保持流畅
+    // 渐隐笔迹的移除不记入撤销历史
+    fn update_fading_strokes(&mut self, ctx: &egui::Context) {
+        let fade = |object: &CanvasObject| match object {
+            CanvasObject::Stroke(stroke) => stroke.fade,
+            _ => None,
+        };
+        let objects = self.state.board.objects();
+        if !objects.iter().any(|object| fade(object).is_some()) {
+            return;
+        }
+
+        let finished: Vec<usize> = objects
+            .iter()
+            .enumerate()
+            .filter(|(_, object)| fade(object).is_some_and(|fade| fade.is_finished()))
+            .map(|(i, _)| i)
+            .collect();
+        if !finished.is_empty() {
+            self.clear_page_local_state();
+            self.state.board.remove_indices(&finished);
+        }
+        ctx.request_repaint();
+    }
+
+    // 柔和擦除：降低橡皮擦经过的笔画的不透明度，每次擦除对同一笔画只生效一次
+    fn soft_erase_at(&mut self, pos: Pos2, eraser_size: f32) {
+        let keep = 1.0 - self.state.soft_eraser_strength;
+        for (id, object) in self.state.board.objects_with_ids_mut() {
+            if let CanvasObject::Stroke(stroke) = object
+                && !self.state.soft_erased_strokes.contains(&id)
+                && AppUtils::point_intersects_stroke(pos, stroke, eraser_size)
+            {
+                stroke.color = stroke.color.gamma_multiply(keep);
+                self.state.soft_erased_strokes.push(id);
+            }
+        }
+    }
+
+    // 结束一次柔和擦除，移除本次经过后已几乎完全透明的笔画
+    fn finish_soft_erase_pass(&mut self) {
+        const MIN_VISIBLE_ALPHA: u8 = 8;
+
+        if self.state.soft_erased_strokes.is_empty() {
+            return;
+        }
+        let erased = std::mem::take(&mut self.state.soft_erased_strokes);
+        let faded: Vec<ObjectId> = self
+            .state
+            .board
+            .objects_with_ids()
+            .filter(|(id, object)| {
+                erased.contains(id)
+                    && matches!(object, CanvasObject::Stroke(stroke) if stroke.color.a() < MIN_VISIBLE_ALPHA)
+            })
+            .map(|(id, _)| id)
+            .collect();
+        self.state.board.remove_ids(&faded);
+    }
+
+    // 根据橡皮擦的移动速度计算实际大小（未启用动态大小时返回设置的大小）
+    fn update_dynamic_eraser_size(&mut self, pos: Pos2) -> f32 {
+        if !self.state.dynamic_eraser_size {
+            return self.state.eraser_size;
+        }
+
+        let now = Instant::now();
+        if let Some((last_pos, last_time)) = self.state.eraser_last_sample {
+            let time_delta = now.duration_since(last_time).as_secs_f32().max(0.001);
+            let speed = last_pos.distance(pos) / time_delta;
+            // 平滑速度，避免大小剧烈跳动
+            self.state.eraser_speed = self.state.eraser_speed * 0.7 + speed * 0.3;
+        } else {
+            self.state.eraser_speed = 0.0;
+        }
+        self.state.eraser_last_sample = Some((pos, now));
+
+        AppUtils::calculate_dynamic_eraser_size(
+            self.state.eraser_size,
+            self.state.eraser_speed,
+            self.state.dynamic_eraser_min_scale,
+            self.state.dynamic_eraser_max_scale,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 模拟一次指针拖动：按下并移动，拖动尚未结束
+    fn start_drag(app: &mut App) {
+        app.begin_active_stroke(
+            POINTER_STROKE_ID,
+            egui::pos2(10.0, 10.0),
+            PointerInputType::Pen,
+            None,
+        );
+        app.extend_active_stroke(POINTER_STROKE_ID, egui::pos2(60.0, 10.0), None);
+        app.extend_active_stroke(POINTER_STROKE_ID, egui::pos2(110.0, 10.0), None);
+    }
+
+    // 在无界面的 egui 上下文中运行一帧，输入只包含指定的事件
+    fn run_frame(app: &mut App, events: Vec<egui::Event>) {
+        let ctx = egui::Context::default();
+        let input = egui::RawInput {
+            events,
+            ..Default::default()
+        };
+        _ = ctx.run(input, |ctx| app.handle_pointer_leave(ctx));
+    }
+
+    #[test]
+    fn pointer_gone_mid_drag_commits_stroke() {
+        let mut app = App::default();
+        app.state.commit_on_pointer_leave = true;
+        start_drag(&mut app);
+
+        run_frame(&mut app, vec![egui::Event::PointerGone]);
+
+        assert!(app.state.active_strokes.is_empty(), "stroke still active");
+        assert!(!app.state.is_drawing, "still drawing after pointer left");
+        assert!(
+            matches!(
+                app.state.board.objects(),
+                [CanvasObject::Stroke(stroke)]
+                    if stroke.points.first() == Some(&egui::pos2(10.0, 10.0))
+            ),
+            "expected the partial stroke to be committed"
+        );
+    }
+
+    #[test]
+    fn pointer_gone_keeps_stroke_when_disabled() {
+        let mut app = App::default();
+        app.state.commit_on_pointer_leave = false;
+        start_drag(&mut app);
+
+        run_frame(&mut app, vec![egui::Event::PointerGone]);
+
+        assert_eq!(
+            app.state.active_strokes.len(),
+            1,
+            "stroke should stay active"
+        );
+        assert!(
+            app.state.board.objects().is_empty(),
+            "nothing should be committed"
+        );
+    }
+
+    #[test]
+    fn pointer_moves_do_not_commit_stroke() {
+        let mut app = App::default();
+        app.state.commit_on_pointer_leave = true;
+        start_drag(&mut app);
+
+        run_frame(
+            &mut app,
+            vec![egui::Event::PointerMoved(egui::pos2(120.0, 10.0))],
+        );
+
+        assert_eq!(
+            app.state.active_strokes.len(),
+            1,
+            "stroke should stay active"
+        );
+        assert!(app.state.is_drawing, "drawing should continue");
+    }
+
+    #[test]
+    fn switching_tool_mid_drag_commits_stroke() {
+        let mut app = App::default();
+        app.state.tool_switch_stroke_behavior = ToolSwitchStrokeBehavior::Commit;
+        start_drag(&mut app);
+
+        app.switch_tool(CanvasTool::Select);
+
+        assert!(
+            app.state.current_tool == CanvasTool::Select,
+            "tool not switched"
+        );
+        assert!(app.state.active_strokes.is_empty(), "stroke still active");
+        assert!(!app.state.is_drawing, "still drawing after switching tool");
+        assert!(
+            matches!(app.state.board.objects(), [CanvasObject::Stroke(_)]),
+            "expected the partial stroke to be committed"
+        );
+    }
+
+    #[test]
+    fn switching_tool_mid_drag_discards_stroke() {
+        let mut app = App::default();
+        app.state.tool_switch_stroke_behavior = ToolSwitchStrokeBehavior::Discard;
+        start_drag(&mut app);
+
+        app.switch_tool(CanvasTool::Select);
+
+        assert!(app.state.active_strokes.is_empty(), "stroke still active");
+        assert!(!app.state.is_drawing, "still drawing after switching tool");
+        assert!(
+            app.state.board.objects().is_empty(),
+            "the partial stroke should be discarded"
+        );
+    }
+
+    #[test]
+    fn locked_canvas_does_not_commit_polyline() {
+        let mut app = App::default();
+        app.state.pen_points = vec![egui::pos2(10.0, 10.0), egui::pos2(60.0, 10.0)];
+        app.state.canvas_locked = true;
+
+        app.finish_pen_path();
+
+        assert!(
+            app.state.board.objects().is_empty(),
+            "a locked canvas should not accept the polyline"
+        );
+    }
+
+    #[test]
+    fn refilling_a_region_places_the_new_fill_on_top() {
+        let mut app = App::default();
+        app.state
+            .board
+            .add_object(CanvasObject::Shape(AppUtils::fit_recognized_shape(
+                CanvasShapeType::Rectangle,
+                &[egui::pos2(10.0, 10.0), egui::pos2(110.0, 110.0)],
+                Color32::BLACK,
+                4.0,
+            )));
+        let visible = egui::Rect::from_min_size(Pos2::ZERO, egui::vec2(200.0, 200.0));
+
+        app.state.brush_color = Color32::RED;
+        app.fill_region_at(egui::pos2(60.0, 60.0), visible);
+        app.state.brush_color = Color32::BLUE;
+        app.fill_region_at(egui::pos2(60.0, 60.0), visible);
+
+        let fills: Vec<Color32> = app
+            .state
+            .board
+            .objects()
+            .iter()
+            .filter_map(|object| match object {
+                CanvasObject::Shape(shape) => Some(shape.color),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            fills,
+            vec![Color32::RED, Color32::BLUE, Color32::BLACK],
+            "the second fill should be above the first and below the outline"
+        );
+    }
+
+    #[test]
+    fn touches_on_floating_windows_do_not_reach_the_canvas() {
+        let ctx = egui::Context::default();
+        let mut hits = (true, false);
+        // 第二帧才能得到上一帧窗口的位置
+        for _ in 0..2 {
+            let input = egui::RawInput {
+                screen_rect: Some(egui::Rect::from_min_size(
+                    Pos2::ZERO,
+                    egui::vec2(400.0, 400.0),
+                )),
+                ..Default::default()
+            };
+            _ = ctx.run(input, |ctx| {
+                egui::Window::new("工具栏")
+                    .fixed_pos([50.0, 50.0])
+                    .show(ctx, |ui| {
+                        _ = ui.button("画笔");
+                    });
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    hits = (
+                        App::touch_on_canvas(ui, egui::pos2(60.0, 60.0)),
+                        App::touch_on_canvas(ui, egui::pos2(300.0, 300.0)),
+                    );
+                });
+            });
+        }
+
+        assert!(!hits.0, "a touch on the toolbar should not start a stroke");
+        assert!(hits.1, "a touch on the open canvas should start a stroke");
+    }
+
+    #[test]
+    fn page_thumbnail_key_changes_with_page_content() {
+        let canvas = egui::Rect::from_min_size(Pos2::ZERO, egui::vec2(800.0, 600.0));
+        let size = egui::vec2(160.0, 120.0);
+        let mut objects = vec![CanvasObject::Shape(AppUtils::fit_recognized_shape(
+            CanvasShapeType::Rectangle,
+            &[egui::pos2(10.0, 10.0), egui::pos2(110.0, 110.0)],
+            Color32::BLACK,
+            4.0,
+        ))];
+        let key = App::page_thumbnail_key(&objects, canvas, size, Color32::WHITE);
+
+        assert_eq!(
+            App::page_thumbnail_key(&objects, canvas, size, Color32::WHITE),
+            key,
+            "an unchanged page should reuse its thumbnail"
+        );
+        assert_ne!(
+            App::page_thumbnail_key(&objects, canvas, size, Color32::BLACK),
+            key,
+            "a new background should render a new thumbnail"
+        );
+        if let Some(object) = objects.first_mut() {
+            object.translate(egui::vec2(5.0, 0.0));
+        }
+        assert_ne!(
+            App::page_thumbnail_key(&objects, canvas, size, Color32::WHITE),
+            key,
+            "moving an object should render a new thumbnail"
+        );
     }
 }
@@ -0,0 +1,1234 @@
+// 画板模型：画布对象及对其进行的编辑操作（添加、命中测试、选择、移动与调整大小、擦除、替换颜色、导出）
+// 不依赖 egui 的 update 循环，界面层只负责把输入转换为这些操作，因此也可以在无界面环境中使用
+
+use std::collections::{HashMap, HashSet};
+
+use egui::emath::TSTransform;
+use egui::{Color32, ColorImage, Pos2, Rect, Vec2};
+
+use crate::metadata::BoardMetadata;
+use crate::state::{CanvasObject, CanvasShapeType, CanvasText, ResizeAnchor, ResizeOperation};
+use crate::utils::AppUtils;
+
+// 测量文本对象的尺寸：界面中使用 egui 的字体排版，无界面时可以提供其他实现
+pub trait TextMeasure {
+    fn text_size(&self, text: &CanvasText) -> Vec2;
+}
+
+impl TextMeasure for egui::Painter {
+    fn text_size(&self, text: &CanvasText) -> Vec2 {
+        text.box_size(&text.galley(self))
+    }
+}
+
+// 对象的稳定标识：添加对象时分配，其他对象被删除或调整上下顺序后保持不变
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ObjectId(u64);
+
+// 画板对象及其标识的快照（用于撤销与重做，恢复后对象标识不变）
+#[derive(Clone, Default)]
+pub struct BoardSnapshot {
+    objects: Vec<CanvasObject>,
+    ids: Vec<ObjectId>,
+}
+
+// 对象按绘制顺序紧密存放（遍历与渲染时连续访问），标识存放在一一对应的数组中
+// 删除与调整顺序按标识集合一次遍历完成，不会像逐个 Vec::remove 那样反复移动后方的对象
+#[derive(Clone, Default)]
+pub struct Board {
+    objects: Vec<CanvasObject>, // 所有画布对象（靠后的对象位于上层）
+    ids: Vec<ObjectId>,         // 与 objects 一一对应的对象标识
+    next_id: u64,               // 下一个分配的标识
+    pub stroke_insert_cursor: Option<usize>, // 当前层/底层模式下下一条笔迹的插入索引
+}
+
+impl Board {
+    pub fn objects(&self) -> &[CanvasObject] {
+        &self.objects
+    }
+
+    // 可以原位修改对象，但不能增删（增删需要同步维护标识）
+    pub fn objects_mut(&mut self) -> &mut [CanvasObject] {
+        &mut self.objects
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&CanvasObject> {
+        self.objects.get(index)
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut CanvasObject> {
+        self.objects.get_mut(index)
+    }
+
+    pub fn id_at(&self, index: usize) -> Option<ObjectId> {
+        self.ids.get(index).copied()
+    }
+
+    // 标识对应对象的当前索引（对象已被删除时返回 None）
+    pub fn index_of(&self, id: ObjectId) -> Option<usize> {
+        self.ids.iter().position(|candidate| *candidate == id)
+    }
+
+    // 同时遍历对象与其标识
+    pub fn objects_with_ids(&self) -> impl Iterator<Item = (ObjectId, &CanvasObject)> {
+        self.ids.iter().copied().zip(&self.objects)
+    }
+
+    pub fn objects_with_ids_mut(&mut self) -> impl Iterator<Item = (ObjectId, &mut CanvasObject)> {
+        self.ids.iter().copied().zip(self.objects.iter_mut())
+    }
+
+    fn allocate_id(&mut self) -> ObjectId {
+        let id = ObjectId(self.next_id);
+        self.next_id += 1;
+        id
+    }
+
+    pub fn add_object(&mut self, object: CanvasObject) -> ObjectId {
+        let id = self.allocate_id();
+        self.objects.push(object);
+        self.ids.push(id);
+        id
+    }
+
+    // 在指定索引处插入对象，插入点不晚于笔迹插入索引时插入索引随之后移
+    pub fn insert_object(&mut self, index: usize, object: CanvasObject) -> ObjectId {
+        let index = index.min(self.objects.len());
+        let id = self.allocate_id();
+        self.objects.insert(index, object);
+        self.ids.insert(index, id);
+        if let Some(cursor) = &mut self.stroke_insert_cursor
+            && index <= *cursor
+        {
+            *cursor += 1;
+        }
+        id
+    }
+
+    // 删除单个对象，笔迹插入索引随之调整
+    pub fn remove(&mut self, index: usize) -> Option<CanvasObject> {
+        if index >= self.objects.len() {
+            return None;
+        }
+        if let Some(cursor) = self.insert_cursor()
+            && index < cursor
+        {
+            self.stroke_insert_cursor = Some(cursor - 1);
+        }
+        self.ids.remove(index);
+        Some(self.objects.remove(index))
+    }
+
+    // 删除所有对象（标识不会被重新分配）
+    pub fn clear(&mut self) {
+        self.objects.clear();
+        self.ids.clear();
+    }
+
+    // 替换全部对象（加载文件或切换页面时），为新对象分配标识
+    pub fn set_objects(&mut self, objects: Vec<CanvasObject>) {
+        self.ids = (0..objects.len()).map(|_| self.allocate_id()).collect();
+        self.objects = objects;
+    }
+
+    // 取出全部对象（切换页面时保存当前页），画板随之清空
+    pub fn take_objects(&mut self) -> Vec<CanvasObject> {
+        self.ids.clear();
+        std::mem::take(&mut self.objects)
+    }
+
+    pub fn snapshot(&self) -> BoardSnapshot {
+        BoardSnapshot {
+            objects: self.objects.clone(),
+            ids: self.ids.clone(),
+        }
+    }
+
+    // 恢复快照并返回恢复之前的内容
+    pub fn restore(&mut self, snapshot: BoardSnapshot) -> BoardSnapshot {
+        BoardSnapshot {
+            objects: std::mem::replace(&mut self.objects, snapshot.objects),
+            ids: std::mem::replace(&mut self.ids, snapshot.ids),
+        }
+    }
+
+    // 一次性删除多个索引处的对象，笔迹插入索引减去其前方被删除的对象数
+    pub fn remove_indices(&mut self, indices: &[usize]) {
+        if let Some(cursor) = self.insert_cursor() {
+            let mut before: Vec<usize> = indices
+                .iter()
+                .copied()
+                .filter(|index| *index < cursor)
+                .collect();
+            before.sort_unstable();
+            before.dedup();
+            self.stroke_insert_cursor = Some(cursor - before.len());
+        }
+        AppUtils::remove_indices(&mut self.objects, indices);
+        AppUtils::remove_indices(&mut self.ids, indices);
+    }
+
+    // 按标识一次性删除多个对象，返回删除的数量
+    pub fn remove_ids(&mut self, ids: &[ObjectId]) -> usize {
+        let ids: HashSet<ObjectId> = ids.iter().copied().collect();
+        let indices: Vec<usize> = self
+            .ids
+            .iter()
+            .enumerate()
+            .filter(|(_, id)| ids.contains(id))
+            .map(|(index, _)| index)
+            .collect();
+        self.remove_indices(&indices);
+        indices.len()
+    }
+
+    // 将指定对象移到最上层，其余对象保持原有的上下顺序
+    pub fn bring_to_front(&mut self, ids: &[ObjectId]) {
+        self.move_to_layer(ids, true);
+    }
+
+    // 将指定对象移到最底层，其余对象保持原有的上下顺序
+    pub fn send_to_back(&mut self, ids: &[ObjectId]) {
+        self.move_to_layer(ids, false);
+    }
+
+    // 一次遍历把对象分为移动与不移动的两组后重新拼接，笔迹插入索引保持在原来的对象之前
+    fn move_to_layer(&mut self, ids: &[ObjectId], to_front: bool) {
+        let moving: HashSet<ObjectId> = ids.iter().copied().collect();
+        let cursor = self.insert_cursor();
+        let objects = std::mem::take(&mut self.objects);
+        let old_ids = std::mem::take(&mut self.ids);
+
+        let mut moved = Vec::new();
+        let mut kept = Vec::with_capacity(objects.len());
+        let mut moved_before_cursor = 0;
+        for (index, entry) in old_ids.into_iter().zip(objects).enumerate() {
+            if moving.contains(&entry.0) {
+                if cursor.is_some_and(|cursor| index < cursor) {
+                    moved_before_cursor += 1;
+                }
+                moved.push(entry);
+            } else {
+                kept.push(entry);
+            }
+        }
+
+        if let Some(cursor) = cursor {
+            let cursor = cursor - moved_before_cursor;
+            self.stroke_insert_cursor = Some(if to_front {
+                cursor
+            } else {
+                cursor + moved.len()
+            });
+        }
+        let (bottom, top) = if to_front {
+            (kept, moved)
+        } else {
+            (moved, kept)
+        };
+        (self.ids, self.objects) = bottom.into_iter().chain(top).unzip();
+    }
+
+    // 笔迹插入索引（限制在对象数量之内），未设置时返回 None
+    pub fn insert_cursor(&self) -> Option<usize> {
+        self.stroke_insert_cursor
+            .map(|cursor| cursor.min(self.objects.len()))
+    }
+
+    // 计算对象的包围盒
+    pub fn bounding_box(object: &CanvasObject, measure: &impl TextMeasure) -> Rect {
+        match object {
+            CanvasObject::Image(img) => Rect::from_min_size(img.pos, img.size),
+            CanvasObject::Text(text) => Rect::from_min_size(text.pos, measure.text_size(text)),
+            CanvasObject::Shape(shape) => AppUtils::calculate_shape_bounding_box(shape),
+            CanvasObject::Stroke(stroke) => AppUtils::calculate_stroke_bounding_box(stroke),
+        }
+    }
+
+    // 判断位置是否命中对象（tolerance 为判定直径）
+    // - 笔画与未填充的形状只在轮廓附近命中，填充的封闭形状内部也会命中
+    // - 图片与文本按包围盒判断
+    fn hits(object: &CanvasObject, pos: Pos2, tolerance: f32, measure: &impl TextMeasure) -> bool {
+        match object {
+            CanvasObject::Stroke(stroke) => {
+                AppUtils::point_intersects_stroke(pos, stroke, tolerance)
+            }
+            CanvasObject::Shape(shape) => {
+                let inside = shape.fill.is_some()
+                    && AppUtils::closed_object_polygon(object)
+                        .is_some_and(|polygon| AppUtils::point_in_polygon(pos, &polygon));
+                let reach = tolerance / 2.0 + shape.stroke_width / 2.0;
+                inside
+                    || AppUtils::shape_outline(shape).iter().any(|polyline| {
+                        polyline.windows(2).any(|segment| match segment {
+                            [a, b] => {
+                                AppUtils::point_to_line_segment_distance(pos, *a, *b) <= reach
+                            }
+                            _ => false,
+                        })
+                    })
+            }
+            CanvasObject::Image(_) | CanvasObject::Text(_) => {
+                Self::bounding_box(object, measure).contains(pos)
+            }
+        }
+    }
+
+    // 查找位于指定位置的最上层对象
+    pub fn hit_test(&self, pos: Pos2, measure: &impl TextMeasure) -> Option<usize> {
+        self.objects
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, object)| Self::hits(object, pos, 10.0, measure))
+            .map(|(i, _)| i)
+    }
+
+    // 查找包围盒与指定区域相交的所有对象（框选）
+    pub fn objects_in_rect(&self, rect: Rect, measure: &impl TextMeasure) -> Vec<usize> {
+        self.objects
+            .iter()
+            .enumerate()
+            .filter(|(_, object)| Self::bounding_box(object, measure).intersects(rect))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    // 判断位置是否位于图片或笔画上（选择工具据此决定是否取消当前选择）
+    pub fn over_image_or_stroke(&self, pos: Pos2) -> bool {
+        self.objects.iter().any(|object| match object {
+            CanvasObject::Image(img) => Rect::from_min_size(img.pos, img.size).contains(pos),
+            _ => false,
+        }) || self.objects.iter().any(|object| match object {
+            CanvasObject::Stroke(stroke) => AppUtils::point_intersects_stroke(pos, stroke, 10.0),
+            _ => false,
+        })
+    }
+
+    // 选中对象显示调整大小与旋转手柄的范围，笔画没有手柄
+    pub fn handle_rect(object: &CanvasObject, measure: &impl TextMeasure) -> Option<Rect> {
+        match object {
+            CanvasObject::Stroke(_) => None,
+            _ => Some(Self::bounding_box(object, measure)),
+        }
+    }
+
+    // 整体平移多个对象
+    pub fn move_objects(&mut self, indices: &[usize], delta: Vec2) {
+        for &index in indices {
+            if let Some(object) = self.objects.get_mut(index) {
+                object.translate(delta);
+            }
+        }
+    }
+
+    // 按调整大小操作与当前指针位置调整对象
+    // - 图片沿锚点所在的边改变尺寸（不小于 20），keep_image_aspect 时保持原有宽高比
+    // - 文本拖动对角锚点时改变字号，拖动左右锚点时改变段落宽度
+    // - 记录了自身范围的形状分别调整宽高（shift 时保持宽高比），其他形状只改变边长
+    pub fn resize_object(
+        &mut self,
+        index: usize,
+        operation: &ResizeOperation,
+        pos: Pos2,
+        keep_image_aspect: bool,
+        shift: bool,
+    ) {
+        let Some(object) = self.objects.get_mut(index) else {
+            return;
+        };
+        let delta = pos - operation.start_pos;
+
+        match object {
+            CanvasObject::Image(img) => {
+                let (dx, dy) = match operation.anchor {
+                    ResizeAnchor::TopLeft => (-1.0, -1.0),
+                    ResizeAnchor::TopRight => (1.0, -1.0),
+                    ResizeAnchor::BottomLeft => (-1.0, 1.0),
+                    ResizeAnchor::BottomRight => (1.0, 1.0),
+                    ResizeAnchor::Top => (0.0, -1.0),
+                    ResizeAnchor::Bottom => (0.0, 1.0),
+                    ResizeAnchor::Left => (-1.0, 0.0),
+                    ResizeAnchor::Right => (1.0, 0.0),
+                };
+                let mut new_size = operation.start_size;
+                let mut new_pos = operation.start_object_pos;
+                if dx != 0.0 {
+                    new_size.x = (operation.start_size.x + dx * delta.x).max(20.0);
+                }
+                if dy != 0.0 {
+                    new_size.y = (operation.start_size.y + dy * delta.y).max(20.0);
+                }
+                // 拖动左边或上边时对侧保持不动
+                if dx < 0.0 {
+                    new_pos.x = operation.start_object_pos.x + delta.x;
+                }
+                if dy < 0.0 {
+                    new_pos.y = operation.start_object_pos.y + delta.y;
+                }
+
+                if keep_image_aspect && img.aspect_ratio > 0.0 {
+                    let target_aspect = img.aspect_ratio;
+                    let current_aspect = new_size.x / new_size.y;
+
+                    if current_aspect.abs() > 0.01 {
+                        if current_aspect > target_aspect {
+                            new_size.x = new_size.y * target_aspect;
+                        } else {
+                            new_size.y = new_size.x / target_aspect;
+                        }
+                    }
+                }
+
+                img.pos = new_pos;
+                img.size = new_size;
+            }
+            CanvasObject::Text(text) => match operation.anchor {
+                ResizeAnchor::TopLeft | ResizeAnchor::BottomRight => {
+                    text.font_size = (operation.start_size.x + delta.x).max(8.0);
+                }
+                // 横向调整时改变段落宽度，文本重新换行而字号不变
+                ResizeAnchor::Right => {
+                    text.max_width = Some((operation.start_size.x + delta.x).max(text.font_size));
+                }
+                ResizeAnchor::Left => {
+                    let width = (operation.start_size.x - delta.x).max(text.font_size);
+                    text.max_width = Some(width);
+                    text.pos.x = operation.start_object_pos.x + operation.start_size.x - width;
+                }
+                _ => {}
+            },
+            CanvasObject::Shape(shape) if let Some(start_rect) = operation.start_shape_rect => {
+                let rect = AppUtils::resize_shape_rect(
+                    start_rect,
+                    shape.rotation,
+                    operation.anchor,
+                    delta,
+                    10.0,
+                    shift,
+                );
+                shape.set_frame(rect);
+            }
+            CanvasObject::Shape(shape) => {
+                shape.size = match operation.anchor {
+                    ResizeAnchor::TopLeft | ResizeAnchor::BottomRight => {
+                        operation.start_size.x + delta.x
+                    }
+                    ResizeAnchor::TopRight | ResizeAnchor::BottomLeft => {
+                        operation.start_size.x - delta.x
+                    }
+                    ResizeAnchor::Top | ResizeAnchor::Bottom => operation.start_size.y + delta.y,
+                    ResizeAnchor::Left | ResizeAnchor::Right => operation.start_size.x + delta.x,
+                }
+                .max(10.0);
+            }
+            CanvasObject::Stroke(_) => {}
+        }
+    }
+
+    // 吸取指定位置处最上层对象的颜色：图片取对应像素，填充的形状在内部取填充颜色
+    pub fn color_at(&self, pos: Pos2, measure: &impl TextMeasure) -> Option<Color32> {
+        let object = self.objects.get(self.hit_test(pos, measure)?)?;
+        match object {
+            CanvasObject::Stroke(stroke) => Some(stroke.color),
+            CanvasObject::Text(text) => Some(text.color),
+            CanvasObject::Shape(shape) => Some(
+                shape
+                    .fill
+                    .filter(|_| {
+                        AppUtils::closed_object_polygon(object)
+                            .is_some_and(|polygon| AppUtils::point_in_polygon(pos, &polygon))
+                    })
+                    .unwrap_or(shape.color),
+            ),
+            CanvasObject::Image(img) => {
+                let [left, top, width, height] = img.crop_region();
+                let uv = img.mirror_uv((pos - img.pos) / img.size);
+                let x = left + ((uv.x * width as f32) as u32).min(width.saturating_sub(1));
+                let y = top + ((uv.y * height as f32) as u32).min(height.saturating_sub(1));
+                let [r, g, b, a] = img.source.get_pixel_checked(x, y)?.0;
+                Some(Color32::from_rgba_unmultiplied(r, g, b, a))
+            }
+        }
+    }
+
+    // 对象擦除：删除橡皮擦位置处的所有对象，返回删除的数量
+    pub fn erase_objects_at(
+        &mut self,
+        pos: Pos2,
+        eraser_size: f32,
+        measure: &impl TextMeasure,
+    ) -> usize {
+        let to_remove: Vec<usize> = self
+            .objects
+            .iter()
+            .enumerate()
+            .filter(|(_, object)| Self::hits(object, pos, eraser_size, measure))
+            .map(|(i, _)| i)
+            .collect();
+
+        self.remove_indices(&to_remove);
+        to_remove.len()
+    }
+
+    // 像素擦除：擦去橡皮擦覆盖范围内的笔迹，笔画被分割为多段（分割后的各段保持原笔画的上下位置）
+    // - 形状的轮廓被擦到时，将其转换为等效的笔画，再局部擦除（填充的三角形转换后只保留轮廓）
+    // - 填充区域没有可擦除的轮廓，保持不变
+    // - 文本无法分割，当橡皮擦覆盖其边界框的面积达到阈值时整体删除
+    pub fn pixel_erase_at(&mut self, pos: Pos2, eraser_size: f32, measure: &impl TextMeasure) {
+        const TEXT_ERASE_COVERAGE: f32 = 0.3;
+        let eraser_radius = eraser_size / 2.0;
+
+        self.replace_each(|object| match object {
+            CanvasObject::Stroke(stroke) => AppUtils::split_stroke_at(stroke, pos, eraser_radius)
+                .into_iter()
+                .map(CanvasObject::Stroke)
+                .collect(),
+            CanvasObject::Shape(shape) if matches!(shape.shape_type, CanvasShapeType::Region) => {
+                vec![CanvasObject::Shape(shape)]
+            }
+            CanvasObject::Shape(shape) => {
+                let outline_strokes = AppUtils::shape_to_strokes(&shape);
+                if outline_strokes
+                    .iter()
+                    .any(|stroke| AppUtils::point_intersects_stroke(pos, stroke, eraser_size))
+                {
+                    outline_strokes
+                        .into_iter()
+                        .flat_map(|stroke| AppUtils::split_stroke_at(stroke, pos, eraser_radius))
+                        .map(CanvasObject::Stroke)
+                        .collect()
+                } else {
+                    vec![CanvasObject::Shape(shape)]
+                }
+            }
+            CanvasObject::Text(text) => {
+                let text_rect = Rect::from_min_size(text.pos, measure.text_size(&text));
+                if AppUtils::circle_rect_coverage(pos, eraser_radius, text_rect)
+                    < TEXT_ERASE_COVERAGE
+                {
+                    vec![CanvasObject::Text(text)]
+                } else {
+                    Vec::new()
+                }
+            }
+            image @ CanvasObject::Image(_) => vec![image],
+        });
+    }
+
+    // 笔画擦除：笔画只删去橡皮擦经过的部分，其他对象被擦到时整体删除（对象保持原有的上下顺序）
+    pub fn erase_stroke_segments_at(
+        &mut self,
+        pos: Pos2,
+        eraser_size: f32,
+        measure: &impl TextMeasure,
+    ) {
+        let eraser_radius = eraser_size / 2.0;
+        self.replace_each(|object| match object {
+            CanvasObject::Stroke(stroke) => AppUtils::split_stroke_at(stroke, pos, eraser_radius)
+                .into_iter()
+                .map(CanvasObject::Stroke)
+                .collect(),
+            other if Self::hits(&other, pos, eraser_size, measure) => Vec::new(),
+            other => vec![other],
+        });
+    }
+
+    // 将每个对象原位替换为零个或多个对象，笔迹插入索引按其前方对象数量的变化调整
+    // 替换结果的第一个对象沿用原对象的标识，其余对象分配新的标识
+    fn replace_each(&mut self, mut replace: impl FnMut(CanvasObject) -> Vec<CanvasObject>) {
+        let cursor = self.insert_cursor();
+        let objects = std::mem::take(&mut self.objects);
+        let old_ids = std::mem::take(&mut self.ids);
+        let count = objects.len();
+        let mut replaced = Vec::with_capacity(count);
+        let mut ids = Vec::with_capacity(count);
+        for (i, (id, object)) in old_ids.into_iter().zip(objects).enumerate() {
+            if cursor == Some(i) {
+                self.stroke_insert_cursor = Some(replaced.len());
+            }
+            for (piece, object) in replace(object).into_iter().enumerate() {
+                ids.push(if piece == 0 { id } else { self.allocate_id() });
+                replaced.push(object);
+            }
+        }
+        if cursor == Some(count) {
+            self.stroke_insert_cursor = Some(replaced.len());
+        }
+        self.objects = replaced;
+        self.ids = ids;
+    }
+
+    // 统计颜色与指定颜色相近（RGB 距离不超过容差）的对象数量
+    pub fn count_color_matches(&self, color: Color32, tolerance: f32) -> usize {
+        self.objects
+            .iter()
+            .filter_map(CanvasObject::replaceable_color)
+            .filter(|c| AppUtils::color_distance(*c, color) <= tolerance)
+            .count()
+    }
+
+    // 将相近颜色统一替换为目标颜色，保留各对象原有的不透明度，返回替换的数量
+    pub fn replace_color(&mut self, from: Color32, to: Color32, tolerance: f32) -> usize {
+        let [r, g, b, _] = to.to_srgba_unmultiplied();
+        let mut replaced = 0;
+        for object in &mut self.objects {
+            if let Some(color) = object.replaceable_color_mut()
+                && AppUtils::color_distance(*color, from) <= tolerance
+            {
+                *color = Color32::from_rgba_unmultiplied(r, g, b, color.a());
+                replaced += 1;
+            }
+        }
+        replaced
+    }
+
+    // 导出所有对象的结构信息
+    pub fn metadata(&self, canvas_size: Vec2, background: Color32) -> BoardMetadata {
+        BoardMetadata::new(&self.objects, canvas_size, background)
+    }
+
+    // 将画布上的指定区域渲染为图片，不依赖窗口与 GPU
+    // 使用独立的 egui 上下文排版并细分对象，再由软件光栅化绘制；scale 为每个画布单位对应的像素数
+    pub fn render_image(
+        &self,
+        region: Rect,
+        scale: f32,
+        background: Color32,
+        fonts: &egui::FontDefinitions,
+    ) -> ColorImage {
+        Self::render_objects(&self.objects, region, scale, background, fonts)
+    }
+
+    // 渲染任意一组对象（例如未打开的页面）
+    pub fn render_objects(
+        objects: &[CanvasObject],
+        region: Rect,
+        scale: f32,
+        background: Color32,
+        fonts: &egui::FontDefinitions,
+    ) -> ColorImage {
+        let ctx = egui::Context::default();
+        ctx.set_fonts(fonts.clone());
+
+        let mut input = egui::RawInput {
+            screen_rect: Some(Rect::from_min_size(Pos2::ZERO, region.size())),
+            ..Default::default()
+        };
+        input
+            .viewports
+            .entry(egui::ViewportId::ROOT)
+            .or_default()
+            .native_pixels_per_point = Some(scale);
+
+        let layer = egui::LayerId::background();
+        let output = ctx.run(input, |ctx| {
+            ctx.set_transform_layer(layer, TSTransform::from_translation(-region.min.to_vec2()));
+            let painter = egui::Painter::new(ctx.clone(), layer, region);
+            for object in objects {
+                if Self::bounding_box(object, &painter).intersects(region) {
+                    object.draw(&painter, false);
+                }
+            }
+        });
+
+        // 非图片对象使用字体纹理（其中包含纯白像素），图片使用裁剪后的原图
+        let mut textures = HashMap::new();
+        textures.insert(egui::TextureId::default(), ctx.fonts(|fonts| fonts.image()));
+        for object in objects {
+            if let CanvasObject::Image(img) = object {
+                let pixels = img.visible_pixels();
+                let (width, height) = pixels.dimensions();
+                textures.insert(
+                    img.texture.id(),
+                    ColorImage::from_rgba_unmultiplied([width as usize, height as usize], &pixels),
+                );
+            }
+        }
+
+        let primitives = ctx.tessellate(output.shapes, output.pixels_per_point);
+        let size = (region.size() * scale).round().max(Vec2::splat(1.0));
+        crate::raster::rasterize(
+            &primitives,
+            &textures,
+            [size.x as usize, size.y as usize],
+            output.pixels_per_point,
+            background,
+        )
+    }
+
+    // 将画布上的指定区域渲染并编码为 PNG
+    pub fn export_png(
+        &self,
+        region: Rect,
+        scale: f32,
+        background: Color32,
+        fonts: &egui::FontDefinitions,
+    ) -> Result<Vec<u8>, String> {
+        let image = self.render_image(region, scale, background, fonts);
+        let [width, height] = image.size;
+        let pixels = image
+            .pixels
+            .iter()
+            .flat_map(|pixel| pixel.to_srgba_unmultiplied())
+            .collect();
+        let image = image::RgbaImage::from_raw(width as u32, height as u32, pixels)
+            .ok_or_else(|| "invalid image size".to_owned())?;
+
+        let mut png = std::io::Cursor::new(Vec::new());
+        image
+            .write_to(&mut png, image::ImageFormat::Png)
+            .map_err(|err| err.to_string())?;
+        Ok(png.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    use crate::state::{
+        CanvasImage, CanvasStroke, PointerInputType, StrokeCache, TextAlign, TextFont,
+    };
+
+    // 固定尺寸的文本测量，测试不需要加载字体
+    struct FixedMeasure;
+
+    impl TextMeasure for FixedMeasure {
+        fn text_size(&self, _text: &CanvasText) -> Vec2 {
+            Vec2::new(40.0, 20.0)
+        }
+    }
+
+    fn text(pos: (f32, f32)) -> CanvasObject {
+        CanvasObject::Text(CanvasText {
+            text: "text".to_owned(),
+            pos: Pos2::new(pos.0, pos.1),
+            color: Color32::BLACK,
+            font_size: 16.0,
+            max_width: None,
+            align: TextAlign::Left,
+            font: TextFont::Cjk,
+            bold: false,
+            italic: false,
+        })
+    }
+
+    fn image(ctx: &egui::Context, rect: Rect) -> CanvasObject {
+        let source = image::RgbaImage::new(1, 1);
+        CanvasObject::Image(CanvasImage {
+            texture: ctx.load_texture(
+                "test_image",
+                egui::ColorImage::from_rgba_unmultiplied([1, 1], &source),
+                egui::TextureOptions::LINEAR,
+            ),
+            pos: rect.min,
+            size: rect.size(),
+            aspect_ratio: 1.0,
+            marked_for_deletion: false,
+            corner_radius: 0.0,
+            border_width: 0.0,
+            border_color: Color32::BLACK,
+            source: Arc::new(source),
+            crop: None,
+            flip_x: false,
+            flip_y: false,
+        })
+    }
+
+    fn rectangle(min: (f32, f32), max: (f32, f32), fill: Option<Color32>) -> CanvasObject {
+        let mut shape = AppUtils::fit_recognized_shape(
+            CanvasShapeType::Rectangle,
+            &[Pos2::new(min.0, min.1), Pos2::new(max.0, max.1)],
+            Color32::RED,
+            2.0,
+        );
+        shape.fill = fill;
+        CanvasObject::Shape(shape)
+    }
+
+    fn stroke(points: &[(f32, f32)], width: f32) -> CanvasObject {
+        CanvasObject::Stroke(CanvasStroke {
+            points: points.iter().map(|&(x, y)| Pos2::new(x, y)).collect(),
+            widths: vec![width; points.len()],
+            forces: Vec::new(),
+            color: Color32::BLACK,
+            base_width: width,
+            follow_background: false,
+            input_type: PointerInputType::Pen,
+            highlighter: false,
+            fade: None,
+            cache: StrokeCache::default(),
+        })
+    }
+
+    fn board_with(count: usize) -> Board {
+        let mut board = Board::default();
+        board.set_objects(
+            (0..count)
+                .map(|i| stroke(&[(i as f32 * 10.0, 0.0), (i as f32 * 10.0 + 5.0, 0.0)], 2.0))
+                .collect(),
+        );
+        board
+    }
+
+    #[test]
+    fn insert_cursor_shifts_with_removals_before_it() {
+        let mut board = board_with(5);
+        board.stroke_insert_cursor = Some(3);
+        board.remove_indices(&[0, 4, 0]);
+        assert_eq!(
+            board.insert_cursor(),
+            Some(2),
+            "only removals before the cursor move it"
+        );
+    }
+
+    #[test]
+    fn insert_cursor_is_clamped_to_object_count() {
+        let mut board = board_with(2);
+        board.stroke_insert_cursor = Some(10);
+        assert_eq!(
+            board.insert_cursor(),
+            Some(2),
+            "cursor should be clamped to the object count"
+        );
+        board.remove_indices(&[1]);
+        assert_eq!(
+            board.insert_cursor(),
+            Some(1),
+            "cursor should stay in range after removal"
+        );
+    }
+
+    #[test]
+    fn insert_cursor_moves_past_objects_inserted_below() {
+        let mut board = board_with(2);
+        board.stroke_insert_cursor = Some(1);
+        board.insert_object(0, stroke(&[(0.0, 0.0)], 2.0));
+        assert_eq!(
+            board.insert_cursor(),
+            Some(2),
+            "inserting below the cursor should shift it"
+        );
+        board.insert_object(3, stroke(&[(0.0, 0.0)], 2.0));
+        assert_eq!(
+            board.insert_cursor(),
+            Some(2),
+            "inserting above the cursor should not move it"
+        );
+    }
+
+    #[test]
+    fn hit_test_returns_topmost_object() {
+        let mut board = board_with(0);
+        board.add_object(stroke(&[(0.0, 0.0), (100.0, 0.0)], 2.0));
+        board.add_object(text((40.0, -10.0)));
+        assert_eq!(
+            board.hit_test(Pos2::new(50.0, 0.0), &FixedMeasure),
+            Some(1),
+            "the text above the stroke should be hit first"
+        );
+        assert_eq!(
+            board.hit_test(Pos2::new(10.0, 3.0), &FixedMeasure),
+            Some(0),
+            "the stroke should be hit within the tolerance"
+        );
+        assert_eq!(
+            board.hit_test(Pos2::new(10.0, 30.0), &FixedMeasure),
+            None,
+            "empty canvas should not be hit"
+        );
+    }
+
+    #[test]
+    fn hit_test_only_hits_filled_shape_interiors() {
+        let mut board = board_with(0);
+        board.add_object(rectangle((0.0, 0.0), (100.0, 100.0), None));
+        assert_eq!(
+            board.hit_test(Pos2::new(50.0, 1.0), &FixedMeasure),
+            Some(0),
+            "the outline should be hit"
+        );
+        assert_eq!(
+            board.hit_test(Pos2::new(50.0, 50.0), &FixedMeasure),
+            None,
+            "an unfilled shape should not be hit inside"
+        );
+
+        board.objects = vec![rectangle((0.0, 0.0), (100.0, 100.0), Some(Color32::RED))];
+        assert_eq!(
+            board.hit_test(Pos2::new(50.0, 50.0), &FixedMeasure),
+            Some(0),
+            "a filled shape should be hit inside"
+        );
+    }
+
+    #[test]
+    fn erase_objects_at_removes_every_hit_object() {
+        let mut board = board_with(3);
+        board.add_object(text((0.0, -10.0)));
+        board.stroke_insert_cursor = Some(3);
+        let removed = board.erase_objects_at(Pos2::new(2.0, 0.0), 4.0, &FixedMeasure);
+        assert_eq!(removed, 2, "the first stroke and the text should be erased");
+        assert_eq!(board.objects.len(), 2, "two strokes should remain");
+        assert_eq!(
+            board.insert_cursor(),
+            Some(2),
+            "the cursor should move past the erased stroke"
+        );
+    }
+
+    #[test]
+    fn pixel_erase_at_splits_strokes_and_removes_covered_text() {
+        let mut board = board_with(0);
+        let text_id = board.add_object(text((0.0, 100.0)));
+        let points: Vec<(f32, f32)> = (0..=10).map(|i| (i as f32 * 10.0, 0.0)).collect();
+        let stroke_id = board.add_object(stroke(&points, 2.0));
+
+        board.pixel_erase_at(Pos2::new(50.0, 0.0), 10.0, &FixedMeasure);
+        assert_eq!(
+            board.objects.len(),
+            3,
+            "the stroke should be split in two and the text kept"
+        );
+        assert_eq!(
+            board.ids.get(..2),
+            Some([text_id, stroke_id].as_slice()),
+            "the first piece should keep the id of the split stroke"
+        );
+        assert!(
+            board.id_at(2).is_some_and(|id| id != stroke_id),
+            "the second piece should get a new id"
+        );
+        assert!(
+            matches!(board.objects.first(), Some(CanvasObject::Text(_))),
+            "the split strokes should stay above the text they were drawn over"
+        );
+
+        board.pixel_erase_at(Pos2::new(20.0, 110.0), 40.0, &FixedMeasure);
+        assert!(
+            !board
+                .objects
+                .iter()
+                .any(|object| matches!(object, CanvasObject::Text(_))),
+            "text mostly covered by the eraser should be removed"
+        );
+    }
+
+    #[test]
+    fn replace_color_keeps_opacity_and_skips_distant_colors() {
+        let mut board = board_with(2);
+        if let Some(CanvasObject::Stroke(stroke)) = board.objects.get_mut(1) {
+            stroke.color = Color32::from_rgba_unmultiplied(250, 0, 0, 128);
+        }
+        board.add_object(rectangle((0.0, 0.0), (10.0, 10.0), None));
+
+        assert_eq!(
+            board.count_color_matches(Color32::RED, 10.0),
+            2,
+            "the red stroke and the red shape should match"
+        );
+        let replaced = board.replace_color(Color32::RED, Color32::BLUE, 10.0);
+        assert_eq!(replaced, 2, "both red objects should be replaced");
+        assert_eq!(
+            board
+                .objects
+                .get(1)
+                .and_then(CanvasObject::replaceable_color),
+            Some(Color32::from_rgba_unmultiplied(0, 0, 255, 128)),
+            "the replaced color should keep its opacity"
+        );
+        assert_eq!(
+            board
+                .objects
+                .first()
+                .and_then(CanvasObject::replaceable_color),
+            Some(Color32::BLACK),
+            "colors outside the tolerance should be kept"
+        );
+    }
+
+    #[test]
+    fn remove_indices_ignores_duplicates_and_out_of_range() {
+        let mut board = board_with(4);
+        board.remove_indices(&[2, 0, 2, 9]);
+        let starts: Vec<f32> = board
+            .objects
+            .iter()
+            .filter_map(|object| match object {
+                CanvasObject::Stroke(stroke) => stroke.points.first().map(|p| p.x),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            starts,
+            vec![10.0, 30.0],
+            "the remaining objects should keep their order"
+        );
+    }
+
+    #[test]
+    fn render_image_draws_objects_over_background() {
+        let mut board = board_with(0);
+        board.add_object(stroke(&[(10.0, 20.0), (90.0, 20.0)], 10.0));
+        let region = Rect::from_min_size(Pos2::new(10.0, 10.0), Vec2::new(80.0, 20.0));
+        let image =
+            board.render_image(region, 2.0, Color32::WHITE, &egui::FontDefinitions::empty());
+
+        assert_eq!(
+            image.size,
+            [160, 40],
+            "the image should be scaled by the factor"
+        );
+        let pixel = |x: usize, y: usize| image.pixels.get(y * 160 + x).copied();
+        assert_eq!(
+            pixel(80, 20),
+            Some(Color32::BLACK),
+            "the stroke should be drawn at its canvas position"
+        );
+        assert_eq!(
+            pixel(80, 2),
+            Some(Color32::WHITE),
+            "the background should fill the rest of the region"
+        );
+    }
+
+    #[test]
+    fn pixel_erase_keeps_bottom_strokes_under_images() {
+        let ctx = egui::Context::default();
+        let mut board = board_with(0);
+        board.add_object(image(
+            &ctx,
+            Rect::from_min_size(Pos2::new(200.0, 0.0), Vec2::splat(100.0)),
+        ));
+        // 底层模式：笔迹插入在图片之下
+        board.stroke_insert_cursor = Some(0);
+        let points: Vec<(f32, f32)> = (0..=10).map(|i| (i as f32 * 10.0, 50.0)).collect();
+        board.insert_object(0, stroke(&points, 2.0));
+        board.stroke_insert_cursor = Some(1);
+
+        board.pixel_erase_at(Pos2::new(50.0, 50.0), 10.0, &FixedMeasure);
+        assert!(
+            matches!(
+                board.objects.as_slice(),
+                [
+                    CanvasObject::Stroke(_),
+                    CanvasObject::Stroke(_),
+                    CanvasObject::Image(_)
+                ]
+            ),
+            "the split stroke should stay below the image"
+        );
+        assert_eq!(
+            board.insert_cursor(),
+            Some(2),
+            "the cursor should follow the extra stroke piece"
+        );
+
+        board.erase_stroke_segments_at(Pos2::new(20.0, 50.0), 10.0, &FixedMeasure);
+        assert_eq!(
+            board.insert_cursor(),
+            Some(3),
+            "splitting a stroke below the cursor should shift it"
+        );
+        assert!(
+            matches!(board.objects.last(), Some(CanvasObject::Image(_))),
+            "the image should stay on top"
+        );
+    }
+
+    #[test]
+    fn remove_ids_keeps_ids_of_remaining_objects() {
+        let mut board = board_with(5);
+        let [a, b, c, d, e] = std::array::from_fn(|i| ObjectId(i as u64));
+        board.stroke_insert_cursor = Some(4);
+
+        let removed = board.remove_ids(&[d, a, a]);
+        assert_eq!(removed, 2, "each id should be removed once");
+        assert_eq!(
+            board.ids,
+            vec![b, c, e],
+            "remaining objects should keep their ids and order"
+        );
+        assert_eq!(
+            board.index_of(e),
+            Some(2),
+            "ids should resolve to the shifted index"
+        );
+        assert_eq!(board.index_of(a), None, "removed ids should not resolve");
+        assert_eq!(
+            board.insert_cursor(),
+            Some(2),
+            "cursor should move back past removed objects"
+        );
+    }
+
+    #[test]
+    fn reordering_moves_objects_and_keeps_relative_order() {
+        let mut board = board_with(5);
+        let [a, b, c, d, e] = std::array::from_fn(|i| ObjectId(i as u64));
+        board.stroke_insert_cursor = Some(2);
+
+        board.bring_to_front(&[d, a]);
+        assert_eq!(
+            board.ids,
+            vec![b, c, e, a, d],
+            "moved objects should end up on top in their original order"
+        );
+        assert_eq!(
+            board.insert_cursor(),
+            Some(1),
+            "cursor should stay below the same object"
+        );
+
+        board.send_to_back(&[d, e]);
+        assert_eq!(
+            board.ids,
+            vec![e, d, b, c, a],
+            "moved objects should end up at the bottom in their current order"
+        );
+        assert_eq!(
+            board.insert_cursor(),
+            Some(3),
+            "cursor should stay below the same object"
+        );
+    }
+
+    #[test]
+    fn restoring_a_snapshot_keeps_ids() {
+        let mut board = board_with(3);
+        let id = board.id_at(1);
+        let snapshot = board.snapshot();
+        board.remove(0);
+        board.add_object(text((0.0, 0.0)));
+
+        let newer = board.restore(snapshot);
+        assert_eq!(board.id_at(1), id, "restored objects should keep their ids");
+        assert_eq!(
+            newer.ids.len(),
+            3,
+            "the replaced content should be returned"
+        );
+        let added = board.add_object(text((0.0, 0.0)));
+        assert!(
+            !newer.ids.contains(&added),
+            "ids allocated after a restore should not repeat earlier ones"
+        );
+    }
+
+    // 基准测试：在压力测试数据上比较按标识批量操作与逐个 Vec::remove 的耗时
+    // 运行方式：cargo test --release -- --ignored --nocapture
+    fn stress_board() -> Board {
+        let mut board = Board::default();
+        board.set_objects(
+            AppUtils::stress_test_strokes()
+                .into_iter()
+                .map(CanvasObject::Stroke)
+                .collect(),
+        );
+        board
+    }
+
+    // 每一轮在输入的新副本上计时（复制与释放不计入），返回所有轮次的总耗时
+    fn bench<T: Clone>(input: &T, mut run: impl FnMut(&mut T)) -> Duration {
+        const ROUNDS: usize = 50;
+        (0..ROUNDS)
+            .map(|_| {
+                let mut input = input.clone();
+                let start = Instant::now();
+                run(&mut input);
+                start.elapsed()
+            })
+            .sum()
+    }
+
+    #[expect(clippy::print_stdout, reason = "benchmarks report their timings")]
+    fn report(name: &str, vec_remove: Duration, board: Duration) {
+        println!(
+            "{name}: Vec::remove {vec_remove:?}, Board {board:?} ({:.1}x)",
+            vec_remove.as_secs_f64() / board.as_secs_f64()
+        );
+    }
+
+    fn start_points(objects: &[CanvasObject]) -> Vec<Option<Pos2>> {
+        objects
+            .iter()
+            .map(|object| match object {
+                CanvasObject::Stroke(stroke) => stroke.points.first().copied(),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    #[ignore = "benchmark"]
+    fn bench_delete_on_stress_dataset() {
+        // 删除三分之一的对象（相当于橡皮擦或框选删除扫过大片区域）
+        let board = stress_board();
+        let indices: Vec<usize> = (0..board.len()).step_by(3).collect();
+        let ids: Vec<ObjectId> = indices.iter().filter_map(|&i| board.id_at(i)).collect();
+
+        let objects = board.objects.clone();
+        let vec_remove = bench(&objects, |objects| {
+            for &index in indices.iter().rev() {
+                objects.remove(index);
+            }
+        });
+        let by_id = bench(&board, |board| {
+            board.remove_ids(&ids);
+        });
+        report("delete", vec_remove, by_id);
+
+        let mut expected = objects;
+        for &index in indices.iter().rev() {
+            expected.remove(index);
+        }
+        let mut board = board;
+        board.remove_ids(&ids);
+        assert_eq!(
+            start_points(&board.objects),
+            start_points(&expected),
+            "both approaches should remove the same objects"
+        );
+    }
+
+    #[test]
+    #[ignore = "benchmark"]
+    fn bench_reorder_on_stress_dataset() {
+        // 将十分之一的对象移到最上层
+        let board = stress_board();
+        let indices: Vec<usize> = (0..board.len()).step_by(10).collect();
+        let ids: Vec<ObjectId> = indices.iter().filter_map(|&i| board.id_at(i)).collect();
+
+        let bring_to_front = |objects: &mut Vec<CanvasObject>| {
+            let mut moved: Vec<CanvasObject> = indices
+                .iter()
+                .rev()
+                .map(|&index| objects.remove(index))
+                .collect();
+            moved.reverse();
+            objects.extend(moved);
+        };
+        let objects = board.objects.clone();
+        let vec_remove = bench(&objects, bring_to_front);
+        let by_id = bench(&board, |board| board.bring_to_front(&ids));
+        report("reorder", vec_remove, by_id);
+
+        let mut expected = objects;
+        bring_to_front(&mut expected);
+        let mut board = board;
+        board.bring_to_front(&ids);
+        assert_eq!(
+            start_points(&board.objects),
+            start_points(&expected),
+            "both approaches should produce the same order"
+        );
+    }
+}
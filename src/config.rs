@@ -0,0 +1,160 @@
+use egui::Color32;
+use serde::{Deserialize, Serialize};
+
+use crate::state::AppState;
+
+// 可分享的配置文件（调色板与默认样式），用于在多台设备之间统一设置
+// 与本机设置的持久化不同，这里只包含适合分发的内容
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SharedConfig {
+    pub quick_colors: Vec<Color32>, // 快捷颜色
+    pub quick_widths: Vec<f32>,     // 快捷宽度
+    pub brush_color: Color32,       // 默认画笔颜色
+    pub brush_width: f32,           // 默认画笔宽度
+    pub eraser_size: f32,           // 默认橡皮擦大小
+    pub background_color: Color32,  // 默认背景颜色
+}
+
+impl Default for SharedConfig {
+    fn default() -> Self {
+        Self::from_state(&AppState::default())
+    }
+}
+
+// 导入配置的方式
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ConfigImportMode {
+    Merge,   // 合并：保留当前设置，追加新的快捷颜色与宽度
+    Replace, // 替换：完全使用导入的设置
+}
+
+impl SharedConfig {
+    pub fn from_state(state: &AppState) -> Self {
+        Self {
+            quick_colors: state.quick_colors.clone(),
+            quick_widths: state.quick_widths.clone(),
+            brush_color: state.brush_color,
+            brush_width: state.brush_width,
+            eraser_size: state.eraser_size,
+            background_color: state.background_color,
+        }
+    }
+
+    // 导入的宽度：丢弃非正数与非有限值，其余限制在画笔宽度范围内
+    fn valid_widths(&self, state: &AppState) -> Vec<f32> {
+        self.quick_widths
+            .iter()
+            .filter(|width| width.is_finite() && **width > 0.0)
+            .map(|width| width.clamp(state.min_brush_width, state.max_brush_width))
+            .collect()
+    }
+
+    pub fn apply(&self, state: &mut AppState, mode: ConfigImportMode) {
+        let widths = self.valid_widths(state);
+        match mode {
+            ConfigImportMode::Merge => {
+                for color in &self.quick_colors {
+                    if !state.quick_colors.contains(color) {
+                        state.quick_colors.push(*color);
+                    }
+                }
+                for width in &widths {
+                    if !state
+                        .quick_widths
+                        .iter()
+                        .any(|w| (w - width).abs() < f32::EPSILON)
+                    {
+                        state.quick_widths.push(*width);
+                    }
+                }
+                state.quick_widths.sort_by(f32::total_cmp);
+            }
+            ConfigImportMode::Replace => {
+                // 导入的列表为空（或宽度全部无效）时保留当前的快捷颜色与宽度
+                if !self.quick_colors.is_empty() {
+                    state.quick_colors = self.quick_colors.clone();
+                }
+                if !widths.is_empty() {
+                    state.quick_widths = widths;
+                    state.quick_widths.sort_by(f32::total_cmp);
+                    state.quick_widths.dedup();
+                }
+                state.brush_color = self.brush_color;
+                if self.brush_width.is_finite() {
+                    state.brush_width = self
+                        .brush_width
+                        .clamp(state.min_brush_width, state.max_brush_width);
+                }
+                if self.eraser_size.is_finite() && self.eraser_size > 0.0 {
+                    state.eraser_size = self.eraser_size;
+                }
+                state.background_color = self.background_color;
+            }
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replace_drops_invalid_and_clamps_widths() {
+        let mut state = AppState::default();
+        let config = SharedConfig {
+            quick_widths: vec![0.0, -3.0, f32::NAN, 0.5, 4.0, 500.0],
+            brush_width: 500.0,
+            ..SharedConfig::from_state(&state)
+        };
+        config.apply(&mut state, ConfigImportMode::Replace);
+        assert_eq!(
+            state.quick_widths,
+            vec![state.min_brush_width, 4.0, state.max_brush_width],
+            "non-positive widths should be dropped and the rest clamped"
+        );
+        assert!(
+            (state.brush_width - state.max_brush_width).abs() < f32::EPSILON,
+            "brush width should be clamped to the range"
+        );
+    }
+
+    #[test]
+    fn replace_keeps_current_widths_when_import_is_empty() {
+        let mut state = AppState::default();
+        let before = state.quick_widths.clone();
+        let config = SharedConfig {
+            quick_widths: vec![0.0],
+            ..SharedConfig::from_state(&state)
+        };
+        config.apply(&mut state, ConfigImportMode::Replace);
+        assert_eq!(
+            state.quick_widths, before,
+            "current widths should be kept when none are valid"
+        );
+    }
+
+    #[test]
+    fn merge_skips_invalid_widths() {
+        let mut state = AppState::default();
+        let before = state.quick_widths.len();
+        let config = SharedConfig {
+            quick_widths: vec![-1.0, 0.0],
+            ..SharedConfig::from_state(&state)
+        };
+        config.apply(&mut state, ConfigImportMode::Merge);
+        assert_eq!(
+            state.quick_widths.len(),
+            before,
+            "merge should not append invalid widths"
+        );
+    }
+}
@@ -0,0 +1,194 @@
+// 画板文件（.smartboard）：以 JSON 保存当前页的画布对象与常用样式，打开时恢复
+// 图片纹理无法直接序列化，保存为 base64 编码的 PNG 数据，打开时重新加载为纹理；
+// 读取时逐个解析对象，无法识别或无法解码的对象会被跳过并记录警告，其余内容照常打开
+
+use std::sync::Arc;
+
+use base64::prelude::{BASE64_STANDARD, Engine as _};
+use egui::{Color32, Pos2};
+use serde::{Deserialize, Serialize};
+
+use crate::state::{
+    AppState, CanvasImage, CanvasObject, CanvasShape, CanvasStroke, CanvasText, ThemeMode,
+};
+use crate::utils::AppUtils;
+
+// 当前文件格式版本
+const DOCUMENT_VERSION: u32 = 1;
+
+pub const DOCUMENT_EXTENSION: &str = "smartboard";
+
+#[derive(Serialize, Deserialize)]
+pub struct SerializableAppState {
+    pub version: u32,
+    pub canvas_objects: Vec<serde_json::Value>, // 画布对象（逐个解析，见 SerializableObject）
+    pub brush_color: Color32,
+    pub brush_width: f32,
+    pub background_color: Color32,
+    pub theme_mode: ThemeMode,
+    pub quick_colors: Vec<Color32>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum SerializableObject {
+    Stroke(CanvasStroke),
+    Image(SerializableImage),
+    Text(CanvasText),
+    Shape(CanvasShape),
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializableImage {
+    png_base64: String, // PNG 格式的图片数据
+    pos: Pos2,
+    size: egui::Vec2,
+    aspect_ratio: f32,
+    corner_radius: f32,
+    border_width: f32,
+    border_color: Color32,
+    #[serde(default)]
+    crop: Option<[u32; 4]>, // 裁剪区域（保存完整原图，打开后仍可还原）
+    #[serde(default)]
+    flip_x: bool,
+    #[serde(default)]
+    flip_y: bool,
+}
+
+impl SerializableImage {
+    fn from_image(img: &CanvasImage) -> Result<Self, String> {
+        Ok(Self {
+            png_base64: AppUtils::png_base64(&img.source)?,
+            pos: img.pos,
+            size: img.size,
+            aspect_ratio: img.aspect_ratio,
+            corner_radius: img.corner_radius,
+            border_width: img.border_width,
+            border_color: img.border_color,
+            crop: img.crop,
+            flip_x: img.flip_x,
+            flip_y: img.flip_y,
+        })
+    }
+
+    fn into_image(self, ctx: &egui::Context) -> Result<CanvasImage, String> {
+        let bytes = BASE64_STANDARD
+            .decode(&self.png_base64)
+            .map_err(|err| format!("图片数据不是有效的 base64: {err}"))?;
+        let source = image::load_from_memory(&bytes)
+            .map_err(|err| err.to_string())?
+            .to_rgba8();
+        let (width, height) = source.dimensions();
+        let texture = ctx.load_texture(
+            "inserted_image",
+            egui::ColorImage::from_rgba_unmultiplied([width as usize, height as usize], &source),
+            egui::TextureOptions::LINEAR,
+        );
+        let mut image = CanvasImage {
+            texture,
+            pos: self.pos,
+            size: self.size,
+            aspect_ratio: self.aspect_ratio,
+            marked_for_deletion: false,
+            corner_radius: self.corner_radius,
+            border_width: self.border_width,
+            border_color: self.border_color,
+            source: Arc::new(source),
+            crop: None,
+            flip_x: self.flip_x,
+            flip_y: self.flip_y,
+        };
+        if let Some([x, y, crop_width, crop_height]) = self.crop
+            && crop_width > 0
+            && crop_height > 0
+            && x.saturating_add(crop_width) <= width
+            && y.saturating_add(crop_height) <= height
+        {
+            image.set_crop(ctx, self.crop);
+        }
+        Ok(image)
+    }
+}
+
+impl SerializableAppState {
+    pub fn from_state(state: &AppState) -> Self {
+        let canvas_objects = state
+            .board
+            .objects()
+            .iter()
+            .filter_map(|object| {
+                let object = match object {
+                    // 渐隐笔迹只是临时标注，不保存
+                    CanvasObject::Stroke(stroke) if stroke.fade.is_some() => return None,
+                    CanvasObject::Stroke(stroke) => SerializableObject::Stroke(stroke.clone()),
+                    CanvasObject::Text(text) => SerializableObject::Text(text.clone()),
+                    CanvasObject::Shape(shape) => SerializableObject::Shape(shape.clone()),
+                    CanvasObject::Image(img) => match SerializableImage::from_image(img) {
+                        Ok(img) => SerializableObject::Image(img),
+                        Err(err) => {
+                            log::warn!("Skipping image that could not be encoded: {err}");
+                            return None;
+                        }
+                    },
+                };
+                serde_json::to_value(object)
+                    .map_err(|err| log::warn!("Skipping object that could not be saved: {err}"))
+                    .ok()
+            })
+            .collect();
+
+        Self {
+            version: DOCUMENT_VERSION,
+            canvas_objects,
+            brush_color: state.brush_color,
+            brush_width: state.brush_width,
+            background_color: state.background_color,
+            theme_mode: state.theme_mode,
+            quick_colors: state.quick_colors.clone(),
+        }
+    }
+
+    // 用文件内容替换当前页的对象与样式
+    pub fn apply(self, ctx: &egui::Context, state: &mut AppState) {
+        if self.version > DOCUMENT_VERSION {
+            log::warn!(
+                "Board file version {} is newer than supported version {DOCUMENT_VERSION}",
+                self.version
+            );
+        }
+
+        let objects = self
+            .canvas_objects
+            .into_iter()
+            .filter_map(|value| {
+                let object = serde_json::from_value(value)
+                    .map_err(|err| log::warn!("Skipping unrecognized object: {err}"))
+                    .ok()?;
+                match object {
+                    SerializableObject::Stroke(stroke) => Some(CanvasObject::Stroke(stroke)),
+                    SerializableObject::Text(text) => Some(CanvasObject::Text(text)),
+                    SerializableObject::Shape(shape) => Some(CanvasObject::Shape(shape)),
+                    SerializableObject::Image(img) => img
+                        .into_image(ctx)
+                        .map(CanvasObject::Image)
+                        .map_err(|err| log::warn!("Skipping image that could not be loaded: {err}"))
+                        .ok(),
+                }
+            })
+            .collect();
+        state.board.set_objects(objects);
+        state.brush_color = self.brush_color;
+        state.brush_width = self.brush_width;
+        state.background_color = self.background_color;
+        state.theme_mode = self.theme_mode;
+        state.quick_colors = self.quick_colors;
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
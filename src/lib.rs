@@ -1,6 +1,15 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
 mod app;
+mod board;
+mod config;
+mod document;
+mod metadata;
+#[cfg(feature = "ocr")]
+mod ocr;
+mod preferences;
+mod raster;
 mod state;
+mod svg_export;
 mod utils;
 pub use app::App;
@@ -6,6 +6,8 @@
 fn main() -> eframe::Result {
     env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
 
+    // The present mode can only be chosen when the window surface is created
+    let present_mode = smartboard_eframe::App::startup_present_mode();
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([400.0, 300.0])
@@ -15,12 +17,18 @@ fn main() -> eframe::Result {
                 eframe::icon_data::from_png_bytes(&include_bytes!("../assets/icon-256.png")[..])
                     .expect("Failed to load icon"),
             ),
+        // Window geometry is restored from the preferences together with the last tool
+        persist_window: false,
+        wgpu_options: eframe::egui_wgpu::WgpuConfiguration {
+            present_mode,
+            ..Default::default()
+        },
         ..Default::default()
     };
     eframe::run_native(
-        "eframe template",
+        smartboard_eframe::App::APP_NAME,
         native_options,
-        Box::new(|cc| Ok(Box::new(smartboard_eframe::App::new(cc)))),
+        Box::new(move |cc| Ok(Box::new(smartboard_eframe::App::new(cc, present_mode)))),
     )
 }
 
@@ -50,7 +58,12 @@ fn main() {
             .start(
                 canvas,
                 web_options,
-                Box::new(|cc| Ok(Box::new(smartboard_eframe::App::new(cc)))),
+                Box::new(|cc| {
+                    Ok(Box::new(smartboard_eframe::App::new(
+                        cc,
+                        eframe::wgpu::PresentMode::default(),
+                    )))
+                }),
             )
             .await;
 
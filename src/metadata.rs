@@ -0,0 +1,182 @@
+// 对象信息导出：将画布上所有对象的结构写为 JSON，便于其他工具处理画板内容
+//
+// 导出格式（version 1）：
+//
+// {
+//   "version": 1,
+//   "canvas": { "width": 1280.0, "height": 720.0, "background": "#ffffffff" },
+//   "objects": [
+//     { "type": "stroke", "color": "#000000ff", "base_width": 3.0,
+//       "points": [[x, y], ...], "widths": [w, ...], "forces": [f, ...],
+//       "input": "pen" | "finger" },
+//       （forces 为各点的压力 0.0 到 1.0，输入未报告压力时为空数组）
+//     { "type": "image", "position": [x, y], "size": [w, h],
+//       "corner_radius": 0.0, "border_width": 0.0, "border_color": "#000000ff" },
+//     { "type": "text", "position": [x, y], "text": "...", "color": "#000000ff",
+//       "font_size": 24.0 },
+//     { "type": "shape",
+//       "shape": "line" | "arrow" | "rectangle" | "triangle" | "circle" | "polygon" | "star"
+//              | "region",
+//       "position": [x, y], "size": 100.0, "color": "#000000ff", "rotation": 0.0,
+//       "stroke_width": 2.0, "fill": "#ff000080" | null, "height": 100.0, "corner_radius": 0.0,
+//       "outline": [[x, y], ...] }
+//       （fill 为填充颜色，未填充时为 null；outline 只在填充区域中出现，为画布坐标下的轮廓顶点）
+//   ]
+// }
+//
+// 坐标单位为逻辑像素，原点位于画布左上角；颜色为 sRGBA 十六进制字符串；
+// objects 按绘制顺序排列（靠后的对象位于上层）。新增字段只会追加，不会修改已有字段的含义
+
+use egui::{Color32, Pos2};
+use serde::Serialize;
+
+use crate::state::{CanvasObject, CanvasShapeType, PointerInputType};
+
+// 当前导出格式版本
+const METADATA_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+pub struct BoardMetadata {
+    version: u32,
+    canvas: CanvasMetadata,
+    objects: Vec<ObjectMetadata>,
+}
+
+#[derive(Serialize)]
+struct CanvasMetadata {
+    width: f32,
+    height: f32,
+    background: String,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ObjectMetadata {
+    Stroke {
+        color: String,
+        base_width: f32,
+        points: Vec<[f32; 2]>,
+        widths: Vec<f32>,
+        forces: Vec<f32>,
+        input: &'static str,
+    },
+    Image {
+        position: [f32; 2],
+        size: [f32; 2],
+        corner_radius: f32,
+        border_width: f32,
+        border_color: String,
+    },
+    Text {
+        position: [f32; 2],
+        text: String,
+        color: String,
+        font_size: f32,
+    },
+    Shape {
+        shape: &'static str,
+        position: [f32; 2],
+        size: f32,
+        color: String,
+        rotation: f32,
+        stroke_width: f32,
+        fill: Option<String>,
+        height: f32,
+        corner_radius: f32,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        outline: Vec<[f32; 2]>,
+    },
+}
+
+fn color_hex(color: Color32) -> String {
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    format!("#{r:02x}{g:02x}{b:02x}{a:02x}")
+}
+
+fn point(pos: Pos2) -> [f32; 2] {
+    [pos.x, pos.y]
+}
+
+fn shape_name(shape_type: CanvasShapeType) -> &'static str {
+    match shape_type {
+        CanvasShapeType::Line => "line",
+        CanvasShapeType::Arrow => "arrow",
+        CanvasShapeType::Rectangle => "rectangle",
+        CanvasShapeType::Triangle => "triangle",
+        CanvasShapeType::Circle => "circle",
+        CanvasShapeType::Polygon { .. } => "polygon",
+        CanvasShapeType::Star { .. } => "star",
+        CanvasShapeType::Region => "region",
+    }
+}
+
+impl From<&CanvasObject> for ObjectMetadata {
+    fn from(object: &CanvasObject) -> Self {
+        match object {
+            CanvasObject::Stroke(stroke) => Self::Stroke {
+                color: color_hex(stroke.color),
+                base_width: stroke.base_width,
+                points: stroke.points.iter().copied().map(point).collect(),
+                widths: stroke.widths.clone(),
+                forces: stroke.forces.clone(),
+                input: match stroke.input_type {
+                    PointerInputType::Pen => "pen",
+                    PointerInputType::Finger => "finger",
+                },
+            },
+            CanvasObject::Image(img) => Self::Image {
+                position: point(img.pos),
+                size: [img.size.x, img.size.y],
+                corner_radius: img.corner_radius,
+                border_width: img.border_width,
+                border_color: color_hex(img.border_color),
+            },
+            CanvasObject::Text(text) => Self::Text {
+                position: point(text.pos),
+                text: text.text.clone(),
+                color: color_hex(text.color),
+                font_size: text.font_size,
+            },
+            CanvasObject::Shape(shape) => Self::Shape {
+                shape: shape_name(shape.shape_type),
+                position: point(shape.pos),
+                size: shape.size,
+                color: color_hex(shape.color),
+                rotation: shape.rotation,
+                stroke_width: shape.stroke_width,
+                fill: shape.fill.map(color_hex),
+                height: shape.rect_size().y,
+                corner_radius: shape.corner_radius,
+                outline: match shape.shape_type {
+                    CanvasShapeType::Region => crate::utils::AppUtils::shape_outline(shape)
+                        .into_iter()
+                        .next()
+                        .map(|mut polyline| {
+                            polyline.pop();
+                            polyline.into_iter().map(point).collect()
+                        })
+                        .unwrap_or_default(),
+                    _ => Vec::new(),
+                },
+            },
+        }
+    }
+}
+
+impl BoardMetadata {
+    pub fn new(objects: &[CanvasObject], canvas_size: egui::Vec2, background: Color32) -> Self {
+        Self {
+            version: METADATA_VERSION,
+            canvas: CanvasMetadata {
+                width: canvas_size.x,
+                height: canvas_size.y,
+                background: color_hex(background),
+            },
+            objects: objects.iter().map(ObjectMetadata::from).collect(),
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+}
@@ -0,0 +1,81 @@
+// 手写识别（可选功能，需启用 `ocr` feature）
+// 将笔画栅格化为图片后调用系统中安装的 tesseract 命令行工具进行识别，
+// 因此基础构建不引入任何额外依赖
+
+use egui::{Pos2, Rect};
+
+use crate::state::CanvasStroke;
+
+// 栅格化时在内容周围留出的空白（像素）
+const RASTER_PADDING: f32 = 20.0;
+
+// 将笔画绘制为白底黑字的灰度图片
+fn rasterize_strokes(strokes: &[&CanvasStroke], bounds: Rect) -> image::GrayImage {
+    let origin = bounds.min - egui::vec2(RASTER_PADDING, RASTER_PADDING);
+    let width = (bounds.width() + RASTER_PADDING * 2.0).ceil().max(1.0) as u32;
+    let height = (bounds.height() + RASTER_PADDING * 2.0).ceil().max(1.0) as u32;
+    let mut image = image::GrayImage::from_pixel(width, height, image::Luma([255]));
+
+    // 以圆形笔刷沿线段逐点盖印
+    let mut stamp = |center: Pos2, radius: f32| {
+        let center = center - origin.to_vec2();
+        let min_x = (center.x - radius).floor().max(0.0) as u32;
+        let min_y = (center.y - radius).floor().max(0.0) as u32;
+        let max_x = ((center.x + radius).ceil().max(0.0) as u32).min(width - 1);
+        let max_y = ((center.y + radius).ceil().max(0.0) as u32).min(height - 1);
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                if Pos2::new(x as f32, y as f32).distance(center) <= radius {
+                    image.put_pixel(x, y, image::Luma([0]));
+                }
+            }
+        }
+    };
+
+    for stroke in strokes {
+        for (i, point) in stroke.points.iter().enumerate() {
+            let radius = stroke.widths.get(i).copied().unwrap_or(stroke.base_width) / 2.0;
+            let radius = radius.max(1.0);
+            stamp(*point, radius);
+
+            if let Some(next) = stroke.points.get(i + 1) {
+                let steps = (point.distance(*next) / radius).ceil() as usize;
+                for step in 1..steps {
+                    let t = step as f32 / steps as f32;
+                    stamp(point.lerp(*next, t), radius);
+                }
+            }
+        }
+    }
+
+    image
+}
+
+// 识别笔画中的手写文字，返回识别出的文本
+pub fn recognize_strokes(strokes: &[&CanvasStroke], bounds: Rect) -> Result<String, String> {
+    let image = rasterize_strokes(strokes, bounds);
+
+    let image_path =
+        std::env::temp_dir().join(format!("smartboard-ocr-{}.png", std::process::id()));
+    image
+        .save(&image_path)
+        .map_err(|err| format!("无法保存临时图片: {err}"))?;
+
+    let output = std::process::Command::new("tesseract")
+        .arg(&image_path)
+        .arg("stdout")
+        .args(["-l", "chi_sim+eng"])
+        .output();
+    _ = std::fs::remove_file(&image_path);
+
+    let output = output.map_err(|err| format!("无法运行 tesseract: {err}"))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_owned());
+    }
+
+    let text = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+    if text.is_empty() {
+        return Err("未识别到文字".to_owned());
+    }
+    Ok(text)
+}
@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+
+use egui::{Color32, ViewportCommand};
+use serde::{Deserialize, Serialize};
+use wgpu::PresentMode;
+
+use crate::state::{
+    AccessibilitySettings, AppState, CanvasTool, CustomTheme, RenderUpdateMode, ThemeMode,
+    ToolSettings, ToolbarPosition, WindowGeometry,
+};
+
+// 偏好设置：窗口位置与大小、上次使用的工具及各工具设置、主题与自定义主题颜色、快捷颜色与最近使用的颜色、
+// 无障碍、工具栏位置、渲染与网格设置、垂直同步模式，通过 eframe 的持久化存储在退出时保存、启动时恢复
+// 画笔颜色与宽度随画笔工具的设置保存；画布对象由画板文件保存，不包含在这里；新增字段在旧的存储中缺失时使用默认值
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preferences {
+    pub window: WindowGeometry,                           // 窗口位置与大小
+    pub last_tool: CanvasTool,                            // 上次使用的工具
+    pub tool_settings: HashMap<CanvasTool, ToolSettings>, // 各工具的次要设置（包括画笔颜色与宽度）
+    pub min_brush_width: f32,                             // 画笔宽度滑块的下限
+    pub max_brush_width: f32,                             // 画笔宽度滑块的上限
+    pub theme_mode: ThemeMode,                            // 主题模式
+    pub custom_theme: CustomTheme,                        // 自定义主题颜色
+    pub accessibility: AccessibilitySettings,             // 无障碍设置
+    pub toolbar_position: ToolbarPosition,                // 工具栏位置
+    pub quick_colors: Vec<Color32>,                       // 快捷颜色
+    pub recent_colors: Vec<Color32>,                      // 最近使用的颜色
+    pub render_update_mode: RenderUpdateMode,             // 界面刷新方式
+    pub grid_enabled: bool,                               // 显示背景网格
+    pub grid_size: f32,                                   // 网格间距（像素）
+    pub grid_snap_strokes: bool,                          // 笔画起点吸附到网格交点
+    #[serde(with = "PresentModeDef")]
+    pub present_mode: PresentMode, // 垂直同步模式（创建窗口时使用）
+}
+
+// wgpu 的 PresentMode 未启用 serde，按变体名称序列化
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "PresentMode")]
+enum PresentModeDef {
+    AutoVsync,
+    AutoNoVsync,
+    Fifo,
+    FifoRelaxed,
+    Immediate,
+    Mailbox,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self::from_state(&AppState::default())
+    }
+}
+
+impl Preferences {
+    pub fn from_state(state: &AppState) -> Self {
+        let mut tool_settings = state.tool_settings.clone();
+        if let Some(settings) = ToolSettings::capture(state.current_tool, state) {
+            tool_settings.insert(state.current_tool, settings);
+        }
+        if let Some(settings) = ToolSettings::capture(CanvasTool::Brush, state) {
+            tool_settings.insert(CanvasTool::Brush, settings);
+        }
+        Self {
+            window: state.window_geometry,
+            last_tool: state.current_tool,
+            tool_settings,
+            min_brush_width: state.min_brush_width,
+            max_brush_width: state.max_brush_width,
+            theme_mode: state.theme_mode,
+            custom_theme: state.custom_theme,
+            accessibility: state.accessibility,
+            toolbar_position: state.toolbar_position,
+            quick_colors: state.quick_colors.clone(),
+            recent_colors: state.recent_colors.clone(),
+            render_update_mode: state.render_update_mode,
+            grid_enabled: state.grid_enabled,
+            grid_size: state.grid_size,
+            grid_snap_strokes: state.grid_snap_strokes,
+            present_mode: state.present_mode,
+        }
+    }
+
+    pub fn apply(&self, ctx: &egui::Context, state: &mut AppState) {
+        if let Some([x, y]) = self.window.pos {
+            ctx.send_viewport_cmd(ViewportCommand::OuterPosition(egui::pos2(x, y)));
+        }
+        if let Some([width, height]) = self.window.size {
+            ctx.send_viewport_cmd(ViewportCommand::InnerSize(egui::vec2(width, height)));
+        }
+        state.window_geometry = self.window;
+
+        state.min_brush_width = self.min_brush_width;
+        state.max_brush_width = self.max_brush_width.max(self.min_brush_width);
+        state.tool_settings.clone_from(&self.tool_settings);
+        state.current_tool = self.last_tool;
+        // 画笔设置在其他工具下也会用到（颜色选择等），先恢复画笔，再恢复当前工具
+        for tool in [CanvasTool::Brush, self.last_tool] {
+            if let Some(settings) = self.tool_settings.get(&tool).copied() {
+                settings.apply(tool, state);
+            }
+        }
+
+        state.theme_mode = self.theme_mode;
+        state.custom_theme = self.custom_theme;
+        state.accessibility = self.accessibility;
+        state.toolbar_position = self.toolbar_position;
+        state.quick_colors.clone_from(&self.quick_colors);
+        state.recent_colors.clone_from(&self.recent_colors);
+        state.render_update_mode = self.render_update_mode;
+        state.grid_enabled = self.grid_enabled;
+        state.grid_size = self.grid_size;
+        state.grid_snap_strokes = self.grid_snap_strokes;
+    }
+
+    // 创建窗口前读取上次选择的垂直同步模式（窗口创建后 eframe 无法再更改）
+    // 此时 eframe 尚未打开存储，直接读取其存储文件（键值均为 RON 字符串）
+    // 除 Fifo 与自动模式外，其他模式不一定被驱动支持，不支持时无法创建窗口：
+    // 先在存储中改回默认模式，正常退出时再写回所选模式，避免每次启动都失败
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn startup_present_mode(app_name: &str) -> PresentMode {
+        let Some(path) = eframe::storage_dir(app_name).map(|dir| dir.join("app.ron")) else {
+            return PresentMode::default();
+        };
+        let Some(mut storage) = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|ron| ron::from_str::<HashMap<String, String>>(&ron).ok())
+        else {
+            return PresentMode::default();
+        };
+        let Some(mut preferences) = storage
+            .get(eframe::APP_KEY)
+            .and_then(|value| ron::from_str::<Self>(value).ok())
+        else {
+            return PresentMode::default();
+        };
+
+        let present_mode = preferences.present_mode;
+        if !matches!(
+            present_mode,
+            PresentMode::AutoVsync | PresentMode::AutoNoVsync | PresentMode::Fifo
+        ) {
+            preferences.present_mode = PresentMode::default();
+            let result = ron::to_string(&preferences)
+                .and_then(|value| {
+                    storage.insert(eframe::APP_KEY.to_owned(), value);
+                    ron::ser::to_string_pretty(&storage, ron::ser::PrettyConfig::default())
+                })
+                .map_err(|err| err.to_string())
+                .and_then(|ron| std::fs::write(&path, ron).map_err(|err| err.to_string()));
+            if let Err(err) = result {
+                log::error!("Failed to reset the stored present mode: {err}");
+            }
+        }
+        present_mode
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn brush_settings_survive_another_last_tool() {
+        let state = AppState {
+            brush_width: 7.0,
+            brush_color: Color32::RED,
+            current_tool: CanvasTool::Select,
+            ..AppState::default()
+        };
+        let preferences = Preferences::from_state(&state);
+
+        let mut restored = AppState::default();
+        preferences.apply(&egui::Context::default(), &mut restored);
+        assert!(
+            restored.current_tool == CanvasTool::Select,
+            "last tool should be restored"
+        );
+        assert!(
+            (restored.brush_width - 7.0).abs() < f32::EPSILON,
+            "brush width should be restored from the brush settings"
+        );
+        assert_eq!(
+            restored.brush_color,
+            Color32::RED,
+            "brush color should be restored"
+        );
+    }
+
+    #[test]
+    fn preferences_round_trip_through_ron() {
+        let state = AppState {
+            present_mode: PresentMode::Immediate,
+            ..AppState::default()
+        };
+        let ron = ron::to_string(&Preferences::from_state(&state)).unwrap_or_default();
+        let preferences = ron::from_str::<Preferences>(&ron).ok();
+        assert!(
+            preferences
+                .is_some_and(|preferences| preferences.present_mode == PresentMode::Immediate),
+            "preferences should deserialize from their own RON"
+        );
+    }
+}
@@ -0,0 +1,204 @@
+// 软件光栅化：将 egui 细分得到的三角形网格绘制到图片上，使画板可以在没有窗口和 GPU 的情况下导出为图片
+// - 顶点颜色与纹理颜色均为预乘透明度的 sRGB，与 egui 的渲染后端一样直接在 sRGB 空间混合
+// - 像素中心位于三角形内部时绘制该像素（共享边按左上规则只绘制一次），抗锯齿由 egui 的羽化顶点提供
+// - 纹理使用双线性采样，缺少的纹理按纯白处理
+
+use std::collections::HashMap;
+
+use egui::epaint::{ClippedPrimitive, Primitive, Vertex};
+use egui::{Color32, ColorImage, Pos2, Rect, TextureId};
+
+// 预乘透明度的颜色（0..1）
+type Rgba = [f32; 4];
+
+pub fn rasterize(
+    primitives: &[ClippedPrimitive],
+    textures: &HashMap<TextureId, ColorImage>,
+    size: [usize; 2],
+    pixels_per_point: f32,
+    background: Color32,
+) -> ColorImage {
+    let [width, height] = size;
+    let mut canvas = vec![to_rgba(background); width * height];
+    let bounds = Rect::from_min_size(Pos2::ZERO, egui::vec2(width as f32, height as f32));
+
+    for clipped in primitives {
+        let Primitive::Mesh(mesh) = &clipped.primitive else {
+            continue;
+        };
+        let clip = (clipped.clip_rect * pixels_per_point).intersect(bounds);
+        let texture = textures.get(&mesh.texture_id);
+        for triangle in mesh.indices.chunks_exact(3) {
+            let vertices = triangle
+                .iter()
+                .map(|&index| mesh.vertices.get(index as usize))
+                .collect::<Option<Vec<_>>>();
+            if let Some([a, b, c]) = vertices.as_deref() {
+                draw_triangle(
+                    &mut canvas,
+                    width,
+                    [a, b, c],
+                    pixels_per_point,
+                    clip,
+                    texture,
+                );
+            }
+        }
+    }
+
+    ColorImage::new(size, canvas.into_iter().map(to_color32).collect())
+}
+
+fn draw_triangle(
+    canvas: &mut [Rgba],
+    width: usize,
+    vertices: [&Vertex; 3],
+    pixels_per_point: f32,
+    clip: Rect,
+    texture: Option<&ColorImage>,
+) {
+    let [mut a, mut b, c] = vertices;
+    let mut area = edge(a.pos, b.pos, c.pos);
+    if area.abs() <= f32::EPSILON {
+        return;
+    }
+    // 统一为同一绕向，边函数在三角形内部均为正
+    if area < 0.0 {
+        std::mem::swap(&mut a, &mut b);
+        area = -area;
+    }
+
+    let [pa, pb, pc] = [a.pos, b.pos, c.pos].map(|pos| pos * pixels_per_point);
+    let min = pa.min(pb).min(pc).max(clip.min);
+    let max = pa.max(pb).max(pc).min(clip.max);
+    if min.x >= max.x || min.y >= max.y {
+        return;
+    }
+    let area = area * pixels_per_point * pixels_per_point;
+
+    let colors = [a.color, b.color, c.color].map(to_rgba);
+    for y in (min.y.floor() as usize)..(max.y.ceil() as usize) {
+        for x in (min.x.floor() as usize)..(max.x.ceil() as usize) {
+            let p = Pos2::new(x as f32 + 0.5, y as f32 + 0.5);
+            let (Some(wa), Some(wb), Some(wc)) = (
+                barycentric(pb, pc, p, area),
+                barycentric(pc, pa, p, area),
+                barycentric(pa, pb, p, area),
+            ) else {
+                continue;
+            };
+
+            let mut color = [0.0; 4];
+            for (vertex_color, weight) in colors.iter().zip([wa, wb, wc]) {
+                for (value, channel) in color.iter_mut().zip(vertex_color) {
+                    *value += channel * weight;
+                }
+            }
+            if let Some(texture) = texture {
+                let uv =
+                    (a.uv.to_vec2() * wa + b.uv.to_vec2() * wb + c.uv.to_vec2() * wc).to_pos2();
+                let texel = sample(texture, uv);
+                for (value, t) in color.iter_mut().zip(texel) {
+                    *value *= t;
+                }
+            }
+
+            if let Some(dst) = canvas.get_mut(y * width + x) {
+                let keep = 1.0 - color[3];
+                for (d, s) in dst.iter_mut().zip(color) {
+                    *d = s + *d * keep;
+                }
+            }
+        }
+    }
+}
+
+// 边函数：p 位于有向边 a→b 左侧时为正，数值为三角形 abp 面积的两倍
+fn edge(a: Pos2, b: Pos2, p: Pos2) -> f32 {
+    (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+
+// 像素中心相对边 a→b 的重心坐标，位于三角形外部时返回 None
+// 恰好落在边上时只有上边与左边包含该像素，避免相邻三角形重复混合
+fn barycentric(a: Pos2, b: Pos2, p: Pos2, area: f32) -> Option<f32> {
+    let value = edge(a, b, p);
+    let top_left = (a.y == b.y && b.x > a.x) || b.y < a.y;
+    (value > 0.0 || (value == 0.0 && top_left)).then_some(value / area)
+}
+
+// 双线性采样纹理（纹理坐标超出范围时取边缘像素）
+fn sample(texture: &ColorImage, uv: Pos2) -> Rgba {
+    let [width, height] = texture.size;
+    if width == 0 || height == 0 {
+        return [1.0; 4];
+    }
+    let x = (uv.x * width as f32 - 0.5).clamp(0.0, (width - 1) as f32);
+    let y = (uv.y * height as f32 - 0.5).clamp(0.0, (height - 1) as f32);
+    let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+    let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+    let (fx, fy) = (x.fract(), y.fract());
+
+    let texel = |x: usize, y: usize| {
+        texture
+            .pixels
+            .get(y * width + x)
+            .copied()
+            .map_or([1.0; 4], to_rgba)
+    };
+    let top = lerp(texel(x0, y0), texel(x1, y0), fx);
+    let bottom = lerp(texel(x0, y1), texel(x1, y1), fx);
+    lerp(top, bottom, fy)
+}
+
+fn lerp(a: Rgba, b: Rgba, t: f32) -> Rgba {
+    let mut color = a;
+    for (value, target) in color.iter_mut().zip(b) {
+        *value += (target - *value) * t;
+    }
+    color
+}
+
+fn to_rgba(color: Color32) -> Rgba {
+    color.to_array().map(|c| f32::from(c) / 255.0)
+}
+
+fn to_color32(color: Rgba) -> Color32 {
+    let [r, g, b, a] = color.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+    Color32::from_rgba_premultiplied(r, g, b, a)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::epaint::Mesh;
+
+    #[test]
+    fn shared_edges_are_blended_once() {
+        // 两个三角形组成的半透明矩形：共享的对角线只绘制一次，颜色应当均匀
+        let mut mesh = Mesh::default();
+        mesh.add_colored_rect(
+            Rect::from_min_size(Pos2::ZERO, egui::vec2(4.0, 4.0)),
+            Color32::from_black_alpha(128),
+        );
+        let primitives = [ClippedPrimitive {
+            clip_rect: Rect::EVERYTHING,
+            primitive: Primitive::Mesh(mesh),
+        }];
+        let mut textures = HashMap::new();
+        textures.insert(
+            TextureId::default(),
+            ColorImage::new([1, 1], vec![Color32::WHITE]),
+        );
+
+        let image = rasterize(&primitives, &textures, [4, 4], 1.0, Color32::WHITE);
+        let first = image.pixels.first().copied();
+        assert!(
+            first.is_some_and(|pixel| pixel != Color32::WHITE),
+            "the rectangle should be drawn"
+        );
+        assert!(
+            image.pixels.iter().all(|pixel| Some(*pixel) == first),
+            "every pixel of the rectangle should have the same color"
+        );
+    }
+}
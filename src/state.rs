@@ -1,10 +1,15 @@
 use egui::Color32;
 use egui::Pos2;
 use egui::Stroke;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
 use std::time::Instant;
 use wgpu::PresentMode;
 
+use crate::board::{Board, BoardSnapshot, ObjectId};
+
 // 窗口模式
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum WindowMode {
@@ -16,30 +21,374 @@ pub enum WindowMode {
 // 动态画笔模式
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum DynamicBrushWidthMode {
-    Disabled,   // 禁用
-    BrushTip,   // 模拟笔锋
-    SpeedBased, // 基于速度
+    Disabled,      // 禁用
+    BrushTip,      // 模拟笔锋
+    SpeedBased,    // 基于速度
+    PressureBased, // 基于压感
 }
 
 // 主题模式
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ThemeMode {
     System, // 跟随系统
     Light,  // 浅色模式
     Dark,   // 深色模式
+    Custom, // 自定义
 }
 
-// 工具类型
+// 自定义主题颜色
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CustomTheme {
+    pub accent_color: Color32, // 强调色
+    pub panel_color: Color32,  // 面板颜色
+    pub text_color: Color32,   // 文字颜色
+}
+
+impl Default for CustomTheme {
+    fn default() -> Self {
+        Self {
+            accent_color: Color32::from_rgb(0, 120, 215),
+            panel_color: Color32::from_rgb(245, 245, 245),
+            text_color: Color32::from_rgb(30, 30, 30),
+        }
+    }
+}
+
+impl CustomTheme {
+    // 根据自定义颜色构建界面样式，面板较暗时以深色样式为基础
+    pub fn visuals(&self) -> egui::Visuals {
+        let [r, g, b, _] = self.panel_color.to_array();
+        let luminance = 0.299 * f32::from(r) + 0.587 * f32::from(g) + 0.114 * f32::from(b);
+        let mut visuals = if luminance < 128.0 {
+            egui::Visuals::dark()
+        } else {
+            egui::Visuals::light()
+        };
+
+        visuals.override_text_color = Some(self.text_color);
+        visuals.panel_fill = self.panel_color;
+        visuals.window_fill = self.panel_color;
+        visuals.hyperlink_color = self.accent_color;
+        visuals.selection.bg_fill = self.accent_color;
+        visuals.widgets.active.bg_fill = self.accent_color;
+        visuals.widgets.active.weak_bg_fill = self.accent_color;
+        visuals.widgets.hovered.bg_stroke.color = self.accent_color;
+        visuals
+    }
+}
+
+// 窗口位置与大小（逻辑像素），每帧记录，保存在偏好设置中
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    pub pos: Option<[f32; 2]>,  // 窗口位置
+    pub size: Option<[f32; 2]>, // 窗口内部大小
+}
+
+impl WindowGeometry {
+    // 记录当前窗口几何信息（全屏或最大化时保留之前的窗口几何信息）
+    pub fn capture(&mut self, ctx: &egui::Context) {
+        ctx.input(|i| {
+            let viewport = i.viewport();
+            if viewport.fullscreen == Some(true) || viewport.maximized == Some(true) {
+                return;
+            }
+            if let Some(outer_rect) = viewport.outer_rect {
+                self.pos = Some([outer_rect.min.x, outer_rect.min.y]);
+            }
+            if let Some(inner_rect) = viewport.inner_rect {
+                self.size = Some([inner_rect.width(), inner_rect.height()]);
+            }
+        });
+    }
+}
+
+// 无障碍设置：界面缩放、高对比度与更大的点击区域，只影响界面控件，不影响画布内容
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccessibilitySettings {
+    pub ui_scale: f32,           // 界面缩放比例
+    pub high_contrast: bool,     // 高对比度模式
+    pub large_hit_targets: bool, // 增大按钮等控件的点击区域
+}
+
+impl Default for AccessibilitySettings {
+    fn default() -> Self {
+        Self {
+            ui_scale: 1.0,
+            high_contrast: false,
+            large_hit_targets: false,
+        }
+    }
+}
+
+impl AccessibilitySettings {
+    // 增大点击区域时控件尺寸与间距的放大倍数
+    const LARGE_HIT_TARGET_FACTOR: f32 = 1.5;
+
+    // 在已应用主题的样式上叠加无障碍设置
+    pub fn apply(&self, style: &mut egui::Style) {
+        for font_id in style.text_styles.values_mut() {
+            font_id.size *= self.ui_scale;
+        }
+
+        let spacing_scale = if self.large_hit_targets {
+            self.ui_scale * Self::LARGE_HIT_TARGET_FACTOR
+        } else {
+            self.ui_scale
+        };
+        let spacing = &mut style.spacing;
+        spacing.item_spacing *= spacing_scale;
+        spacing.button_padding *= spacing_scale;
+        spacing.interact_size *= spacing_scale;
+        spacing.icon_width *= spacing_scale;
+        spacing.icon_width_inner *= spacing_scale;
+        spacing.icon_spacing *= spacing_scale;
+        spacing.slider_width *= spacing_scale;
+        spacing.combo_width *= spacing_scale;
+        spacing.indent *= spacing_scale;
+
+        if self.high_contrast {
+            Self::apply_high_contrast(&mut style.visuals);
+        }
+    }
+
+    // 前景与背景使用纯黑/纯白，控件统一加粗描边
+    fn apply_high_contrast(visuals: &mut egui::Visuals) {
+        let (foreground, background) = if visuals.dark_mode {
+            (Color32::WHITE, Color32::BLACK)
+        } else {
+            (Color32::BLACK, Color32::WHITE)
+        };
+        let outline = Stroke::new(2.0, foreground);
+
+        visuals.override_text_color = Some(foreground);
+        visuals.panel_fill = background;
+        visuals.window_fill = background;
+        visuals.extreme_bg_color = background;
+        visuals.faint_bg_color = background;
+        visuals.window_stroke = outline;
+        visuals.selection.bg_fill = Color32::from_rgb(0, 90, 200);
+        visuals.selection.stroke = Stroke::new(2.0, Color32::WHITE);
+
+        let widgets = &mut visuals.widgets;
+        for widget in [
+            &mut widgets.noninteractive,
+            &mut widgets.inactive,
+            &mut widgets.hovered,
+            &mut widgets.active,
+            &mut widgets.open,
+        ] {
+            widget.fg_stroke = outline;
+            widget.bg_stroke = outline;
+        }
+        widgets.noninteractive.bg_fill = background;
+        widgets.noninteractive.weak_bg_fill = background;
+        widgets.inactive.bg_fill = background;
+        widgets.inactive.weak_bg_fill = background;
+        widgets.hovered.bg_stroke.width = 3.0;
+        widgets.active.bg_stroke.width = 3.0;
+    }
+}
+
+// 工具栏位置
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ToolbarPosition {
+    Floating, // 浮动
+    Top,      // 停靠顶部
+    Bottom,   // 停靠底部
+    Left,     // 停靠左侧
+    Right,    // 停靠右侧
+}
+
+// 画布尺寸变化（窗口缩放、分辨率变化）时内容的调整方式
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CanvasResizeBehavior {
+    Keep,    // 不调整
+    Center,  // 保持居中
+    TopLeft, // 保持相对左上角位置
+}
+
+// 将画布复制到剪贴板或导出为图片时的范围
 #[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CanvasExportArea {
+    FullCanvas,    // 整个画布
+    ContentBounds, // 仅内容区域
+}
+
+// 切换工具时未完成笔画的处理方式
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ToolSwitchStrokeBehavior {
+    Commit,  // 提交
+    Discard, // 丢弃
+}
+
+// 快速切换的目标工具
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum QuickToggleMode {
+    Recent, // 在最近使用的两个工具之间切换
+    Custom, // 在用户指定的两个工具之间切换
+}
+
+// 新笔迹在对象列表中的插入位置
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum StrokeInsertPosition {
+    Top,          // 顶层
+    CurrentLayer, // 当前层（最近选中对象的上方）
+    Bottom,       // 底层
+}
+
+// 大小预览的样式
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SizePreviewStyle {
+    Brush(Color32), // 画笔：以画笔颜色填充
+    Eraser,         // 橡皮擦：半透明圆圈与十字准星
+    Neutral,        // 其他工具：白色填充黑色边框
+}
+
+// 工具类型
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum CanvasTool {
     Select,       // 选择
     Brush,        // 画笔
+    Polyline,     // 折线（逐点放置顶点）
     ObjectEraser, // 对象橡皮擦
     PixelEraser,  // 像素橡皮擦
+    StrokeWidth,  // 笔迹粗细调整
+    Fill,         // 填充封闭区域
+    AreaMeasure,  // 面积测量
+    Distance,     // 距离测量
+    Protractor,   // 量角器
     Insert,       // 插入
     Settings,     // 设置
 }
 
+impl CanvasTool {
+    // 工具栏中显示的所有工具（按显示顺序）
+    pub const ALL: [Self; 12] = [
+        Self::Select,
+        Self::Brush,
+        Self::Polyline,
+        Self::ObjectEraser,
+        Self::PixelEraser,
+        Self::StrokeWidth,
+        Self::Fill,
+        Self::AreaMeasure,
+        Self::Distance,
+        Self::Protractor,
+        Self::Insert,
+        Self::Settings,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Select => "选择",
+            Self::Brush => "画笔",
+            Self::Polyline => "折线",
+            Self::ObjectEraser => "对象橡皮擦",
+            Self::PixelEraser => "像素橡皮擦",
+            Self::StrokeWidth => "粗细调整",
+            Self::Fill => "填充",
+            Self::AreaMeasure => "面积测量",
+            Self::Distance => "距离测量",
+            Self::Protractor => "量角器",
+            Self::Insert => "插入",
+            Self::Settings => "设置",
+        }
+    }
+}
+
+// 点击放置形状后的尺寸输入
+#[derive(Clone)]
+pub struct ShapeSizeEntry {
+    pub object_index: usize, // 刚放置的形状在对象列表中的索引
+    pub center: Pos2,        // 形状中心（调整尺寸时保持不变）
+    pub text: String,        // 正在输入的尺寸文本
+}
+
+// 单个工具的次要设置，切换工具时保存并在切换回来时恢复
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ToolSettings {
+    pub size: f32,              // 画笔宽度 / 橡皮擦大小 / 粗细调整工具大小
+    pub color: Option<Color32>, // 画笔颜色（其他工具为 None）
+}
+
+impl ToolSettings {
+    // 读取工具当前使用的设置，没有次要设置的工具返回 None
+    pub fn capture(tool: CanvasTool, state: &AppState) -> Option<Self> {
+        match tool {
+            CanvasTool::Brush => Some(Self {
+                size: state.brush_width,
+                color: Some(state.brush_color),
+            }),
+            CanvasTool::ObjectEraser | CanvasTool::PixelEraser => Some(Self {
+                size: state.eraser_size,
+                color: None,
+            }),
+            CanvasTool::StrokeWidth => Some(Self {
+                size: state.stroke_width_tool_size,
+                color: None,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn apply(&self, tool: CanvasTool, state: &mut AppState) {
+        match tool {
+            CanvasTool::Brush => {
+                state.brush_width = self
+                    .size
+                    .clamp(state.min_brush_width, state.max_brush_width);
+                if let Some(color) = self.color {
+                    state.brush_color = color;
+                }
+            }
+            CanvasTool::ObjectEraser | CanvasTool::PixelEraser => state.eraser_size = self.size,
+            CanvasTool::StrokeWidth => state.stroke_width_tool_size = self.size,
+            _ => {}
+        }
+    }
+}
+
+// 面积测量结果
+#[derive(Clone, Copy)]
+pub struct AreaMeasurement {
+    pub object_index: usize, // 被测量的对象索引
+    pub area: Option<f32>,   // 面积（平方像素），对象未闭合时为 None
+}
+
+// 量角器测量：依次点击放置量角器中心与两条射线经过的点
+#[derive(Clone, Copy)]
+pub struct ProtractorMeasurement {
+    pub center: Pos2,         // 量角器中心（角的顶点）
+    pub first: Option<Pos2>,  // 第一条射线经过的点
+    pub second: Option<Pos2>, // 第二条射线经过的点
+}
+
+// 径向菜单项
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RadialMenuItem {
+    Tool(CanvasTool), // 切换工具
+    Color(Color32),   // 切换画笔颜色
+}
+
+// 长按检测状态
+#[derive(Clone, Copy)]
+pub struct HoldState {
+    pub origin: Pos2,    // 按下位置
+    pub start: Instant,  // 按下时间
+    pub cancelled: bool, // 是否因移动过远而取消
+}
+
+// 笔按钮画圈切换颜色的手势状态
+#[derive(Clone, Copy)]
+pub struct ColorGestureState {
+    pub origin: Pos2,            // 按下笔按钮的位置（色轮中心）
+    pub last_angle: Option<f32>, // 上一帧指针相对中心的角度
+    pub accumulated_angle: f32,  // 累计转过的角度（顺时针为正）
+    pub start_index: usize,      // 手势开始时的快捷颜色索引
+}
+
 // 可绘制对象的 trait
 pub trait Draw {
     fn draw(&self, painter: &egui::Painter, selected: bool);
@@ -53,23 +402,182 @@ pub struct CanvasImage {
     pub size: egui::Vec2,
     pub aspect_ratio: f32,
     pub marked_for_deletion: bool, // deferred deletion to avoid panic
+    pub corner_radius: f32,        // 圆角半径
+    pub border_width: f32,         // 边框宽度（0 表示无边框）
+    pub border_color: Color32,     // 边框颜色
+    pub source: Arc<image::RgbaImage>, // 原始图片数据（纹理无法读回，保存画板时使用）
+    pub crop: Option<[u32; 4]>,    // 裁剪区域（原图像素坐标 x, y, 宽, 高），None 表示未裁剪
+    pub flip_x: bool,              // 水平翻转（通过纹理坐标实现）
+    pub flip_y: bool,              // 垂直翻转（通过纹理坐标实现）
+}
+
+impl CanvasImage {
+    // 当前显示的区域（原图像素坐标 x, y, 宽, 高）
+    pub fn crop_region(&self) -> [u32; 4] {
+        self.crop.unwrap_or_else(|| {
+            let (width, height) = self.source.dimensions();
+            [0, 0, width, height]
+        })
+    }
+
+    // 当前显示的像素（裁剪后的图片）
+    pub fn visible_pixels(&self) -> image::RgbaImage {
+        let [x, y, width, height] = self.crop_region();
+        image::imageops::crop_imm(self.source.as_ref(), x, y, width, height).to_image()
+    }
+
+    // 画布上显示的像素（裁剪并翻转后的图片，用于导出）
+    pub fn displayed_pixels(&self) -> image::RgbaImage {
+        let mut pixels = self.visible_pixels();
+        if self.flip_x {
+            image::imageops::flip_horizontal_in_place(&mut pixels);
+        }
+        if self.flip_y {
+            image::imageops::flip_vertical_in_place(&mut pixels);
+        }
+        pixels
+    }
+
+    // 绘制时使用的纹理坐标（翻转时交换对应方向的两端）
+    pub fn uv_rect(&self) -> egui::Rect {
+        let (left, right) = if self.flip_x { (1.0, 0.0) } else { (0.0, 1.0) };
+        let (top, bottom) = if self.flip_y { (1.0, 0.0) } else { (0.0, 1.0) };
+        egui::Rect::from_min_max(Pos2::new(left, top), Pos2::new(right, bottom))
+    }
+
+    // 将图片上的相对位置（0..1）换算为裁剪区域内的相对位置（考虑翻转）
+    pub fn mirror_uv(&self, uv: egui::Vec2) -> egui::Vec2 {
+        egui::vec2(
+            if self.flip_x { 1.0 - uv.x } else { uv.x },
+            if self.flip_y { 1.0 - uv.y } else { uv.y },
+        )
+    }
+
+    // 顺时针旋转 90°：旋转原图像素并重建纹理，宽高互换，中心位置不变
+    // 水平翻转在旋转后等效于垂直翻转，因此交换两个翻转标记以保持画面一致
+    pub fn rotate_clockwise(&mut self, ctx: &egui::Context) {
+        let (_, full_height) = self.source.dimensions();
+        let crop = self
+            .crop
+            .map(|[x, y, width, height]| [full_height - y - height, x, height, width]);
+        self.source = Arc::new(image::imageops::rotate90(self.source.as_ref()));
+        std::mem::swap(&mut self.flip_x, &mut self.flip_y);
+
+        let center = self.pos + self.size / 2.0;
+        self.size = egui::vec2(self.size.y, self.size.x);
+        self.pos = center - self.size / 2.0;
+        self.set_crop(ctx, crop);
+    }
+
+    // 设置裁剪区域，并按裁剪后的像素重建纹理；原图始终保留，可以重新裁剪或还原
+    pub fn set_crop(&mut self, ctx: &egui::Context, crop: Option<[u32; 4]>) {
+        self.crop = crop;
+        let pixels = self.visible_pixels();
+        let (width, height) = pixels.dimensions();
+        self.texture = ctx.load_texture(
+            "inserted_image",
+            egui::ColorImage::from_rgba_unmultiplied([width as usize, height as usize], &pixels),
+            egui::TextureOptions::LINEAR,
+        );
+        self.aspect_ratio = width as f32 / height.max(1) as f32;
+    }
+
+    // 按画布上的裁剪框裁剪图片：换算为原图像素坐标，图片缩小为裁剪框的大小与位置
+    pub fn crop_to(&mut self, ctx: &egui::Context, rect: egui::Rect) {
+        let image_rect = egui::Rect::from_min_size(self.pos, self.size);
+        let rect = rect.intersect(image_rect);
+        if rect.width() < 1.0 || rect.height() < 1.0 {
+            return;
+        }
+
+        let [x, y, width, height] = self.crop_region();
+        let scale = egui::vec2(width as f32, height as f32);
+        let a = self.mirror_uv((rect.min - image_rect.min) / self.size);
+        let b = self.mirror_uv((rect.max - image_rect.min) / self.size);
+        let min = a.min(b) * scale;
+        let max = a.max(b) * scale;
+        let left = (min.x.floor() as u32).min(width - 1);
+        let top = (min.y.floor() as u32).min(height - 1);
+        let right = (max.x.ceil() as u32).clamp(left + 1, width);
+        let bottom = (max.y.ceil() as u32).clamp(top + 1, height);
+
+        self.set_crop(ctx, Some([x + left, y + top, right - left, bottom - top]));
+        self.pos = rect.min;
+        self.size = rect.size();
+    }
+
+    // 还原为未裁剪的原图，保留的部分位置与缩放不变
+    pub fn reset_crop(&mut self, ctx: &egui::Context) {
+        let [x, y, width, height] = self.crop_region();
+        let (full_width, full_height) = self.source.dimensions();
+        let scale = self.size / egui::vec2(width as f32, height as f32);
+        // 翻转时裁掉的部分位于另一侧
+        let left = if self.flip_x {
+            full_width - x - width
+        } else {
+            x
+        };
+        let top = if self.flip_y {
+            full_height - y - height
+        } else {
+            y
+        };
+        self.pos -= egui::vec2(left as f32, top as f32) * scale;
+        self.size = egui::vec2(full_width as f32, full_height as f32) * scale;
+        self.set_crop(ctx, None);
+    }
+}
+
+// 图片裁剪：在选中的图片上拖出要保留的区域，确认后应用
+#[derive(Clone, Copy)]
+pub struct CropOperation {
+    pub index: usize,             // 正在裁剪的图片索引
+    pub start: Option<Pos2>,      // 裁剪框拖动的起点
+    pub rect: Option<egui::Rect>, // 裁剪框（画布坐标）
+}
+
+// 直尺的拖动方式
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RulerDrag {
+    Move,   // 拖动尺身移动直尺
+    Rotate, // 拖动旋转手柄
+}
+
+// 图片属性面板中的操作
+#[derive(Clone, Copy)]
+pub enum ImageAction {
+    StartCrop,       // 开始裁剪
+    ApplyCrop,       // 应用裁剪框
+    CancelCrop,      // 取消裁剪
+    ResetCrop,       // 还原为原图
+    FlipHorizontal,  // 水平翻转
+    FlipVertical,    // 垂直翻转
+    RotateClockwise, // 顺时针旋转 90°
 }
 
 impl Draw for CanvasImage {
     fn draw(&self, painter: &egui::Painter, selected: bool) {
         let img_rect = egui::Rect::from_min_size(self.pos, self.size);
-        painter.image(
-            self.texture.id(),
-            img_rect,
-            egui::Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0)),
-            Color32::WHITE,
+        // painter.image 只能绘制直角矩形，使用带纹理的圆角矩形以支持圆角
+        painter.add(
+            egui::epaint::RectShape::filled(img_rect, self.corner_radius, Color32::WHITE)
+                .with_texture(self.texture.id(), self.uv_rect()),
         );
 
+        if self.border_width > 0.0 {
+            painter.rect_stroke(
+                img_rect,
+                self.corner_radius,
+                Stroke::new(self.border_width, self.border_color),
+                egui::StrokeKind::Inside,
+            );
+        }
+
         // 如果被选中，绘制边框
         if selected {
             painter.rect_stroke(
                 img_rect,
-                0.0,
+                self.corner_radius,
                 Stroke::new(2.0, Color32::BLUE),
                 egui::StrokeKind::Outside,
             );
@@ -78,24 +586,113 @@ impl Draw for CanvasImage {
 }
 
 // 插入的文本数据结构
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct CanvasText {
     pub text: String,
     pub pos: Pos2,
     pub color: Color32,
     pub font_size: f32,
+    #[serde(default)]
+    pub max_width: Option<f32>, // 段落宽度（超出时自动换行），None 表示不换行
+    #[serde(default)]
+    pub align: TextAlign, // 各行的对齐方式
+    #[serde(default)]
+    pub font: TextFont, // 字体
+    #[serde(default)]
+    pub bold: bool, // 粗体
+    #[serde(default)]
+    pub italic: bool, // 斜体
 }
 
-impl Draw for CanvasText {
-    fn draw(&self, painter: &egui::Painter, selected: bool) {
-        // Draw text using egui's text rendering
-        let text_galley = painter.layout_no_wrap(
+// 画布文本可用的字体；等宽与默认字体在缺少字形时回退到中文字体（见 App::new）
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextFont {
+    #[default]
+    Cjk, // 中文字体
+    Monospace, // 等宽字体
+    Default,   // egui 默认字体
+}
+
+impl TextFont {
+    pub const MONOSPACE_FAMILY: &str = "canvas_monospace";
+    pub const DEFAULT_FAMILY: &str = "canvas_default";
+
+    pub fn family(self) -> egui::FontFamily {
+        match self {
+            Self::Cjk => egui::FontFamily::Proportional,
+            Self::Monospace => egui::FontFamily::Name(Self::MONOSPACE_FAMILY.into()),
+            Self::Default => egui::FontFamily::Name(Self::DEFAULT_FAMILY.into()),
+        }
+    }
+}
+
+// 文本对齐方式
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextAlign {
+    #[default]
+    Left, // 左对齐
+    Center, // 居中
+    Right,  // 右对齐
+}
+
+impl CanvasText {
+    // 排版文本：设置了段落宽度时自动换行，各行按对齐方式排列
+    pub fn galley(&self, painter: &egui::Painter) -> Arc<egui::Galley> {
+        let mut job = egui::text::LayoutJob::single_section(
             self.text.clone(),
-            egui::FontId::proportional(self.font_size),
-            self.color,
+            egui::TextFormat {
+                font_id: egui::FontId::new(self.font_size, self.font.family()),
+                color: self.color,
+                italics: self.italic,
+                ..Default::default()
+            },
         );
+        job.wrap.max_width = self
+            .max_width
+            .map_or(f32::INFINITY, |width| width - self.bold_offset());
+        job.halign = match self.align {
+            TextAlign::Left => egui::Align::LEFT,
+            TextAlign::Center => egui::Align::Center,
+            TextAlign::Right => egui::Align::RIGHT,
+        };
+        painter.layout_job(job)
+    }
+
+    // 文本框的大小：设置了段落宽度时宽度固定为段落宽度
+    pub fn box_size(&self, galley: &egui::Galley) -> egui::Vec2 {
+        egui::vec2(
+            self.max_width
+                .unwrap_or(galley.size().x + self.bold_offset()),
+            galley.size().y,
+        )
+    }
+
+    // 字体没有粗体字形，粗体通过将文本向右错开少许重复绘制来模拟
+    fn bold_offset(&self) -> f32 {
+        if self.bold {
+            (self.font_size / 24.0).max(0.5)
+        } else {
+            0.0
+        }
+    }
+
+    // 绘制排版结果的位置：居中或右对齐时各行以文本框的中线或右边为基准
+    fn galley_pos(&self, galley: &egui::Galley) -> Pos2 {
+        let width = self.box_size(galley).x - self.bold_offset();
+        let offset = match self.align {
+            TextAlign::Left => 0.0,
+            TextAlign::Center => width / 2.0,
+            TextAlign::Right => width,
+        };
+        self.pos + egui::vec2(offset, 0.0)
+    }
+}
+
+impl Draw for CanvasText {
+    fn draw(&self, painter: &egui::Painter, selected: bool) {
+        let text_galley = self.galley(painter);
         let text_shape = egui::epaint::TextShape {
-            pos: self.pos,
+            pos: self.galley_pos(&text_galley),
             galley: text_galley.clone(),
             underline: egui::Stroke::NONE,
             override_text_color: None,
@@ -103,11 +700,15 @@ impl Draw for CanvasText {
             fallback_color: self.color,
             opacity_factor: 1.0,
         };
+        if self.bold {
+            let mut bold_shape = text_shape.clone();
+            bold_shape.pos.x += self.bold_offset();
+            painter.add(bold_shape);
+        }
         painter.add(text_shape);
 
         if selected {
-            let text_size = text_galley.size();
-            let text_rect = egui::Rect::from_min_size(self.pos, text_size);
+            let text_rect = egui::Rect::from_min_size(self.pos, self.box_size(&text_galley));
             painter.rect_stroke(
                 text_rect,
                 0.0,
@@ -119,75 +720,151 @@ impl Draw for CanvasText {
 }
 
 // 插入的形状数据结构
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum CanvasShapeType {
     Line,
     Arrow,
     Rectangle,
     Triangle,
     Circle,
+    Polygon { sides: u32 }, // 正多边形：pos 为中心，size 为外接圆半径
+    Star { points: u32 },   // 星形：pos 为中心，size 为外侧顶点所在圆的半径
+    Region,                 // 填充区域：轮廓保存在 outline 中，pos 为范围的左上角
 }
 
-#[derive(Clone)]
+// 箭头的箭头位置
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArrowHeads {
+    #[default]
+    End, // 仅终点
+    Both, // 起点与终点
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct CanvasShape {
     pub shape_type: CanvasShapeType,
     pub pos: Pos2,
     pub size: f32,
     pub color: Color32,
     pub rotation: f32,
+    #[serde(default = "default_shape_stroke_width")]
+    pub stroke_width: f32, // 轮廓宽度
+    #[serde(default)]
+    pub fill: Option<Color32>, // 填充颜色（直线与箭头忽略）
+    #[serde(default)]
+    pub arrow_heads: ArrowHeads, // 箭头位置（仅箭头使用）
+    #[serde(default)]
+    pub height: Option<f32>, // 高度（矩形与椭圆，size 为宽度），None 表示与宽度相同
+    #[serde(default)]
+    pub corner_radius: f32, // 圆角半径（仅矩形）
+    #[serde(default)]
+    pub outline: Vec<egui::Vec2>, // 填充区域的轮廓顶点（相对范围的比例坐标，0.0 到 1.0）
 }
 
-impl Draw for CanvasShape {
-    fn draw(&self, painter: &egui::Painter, selected: bool) {
-        // 绘制形状本身
+fn default_shape_stroke_width() -> f32 {
+    2.0
+}
+
+impl CanvasShape {
+    // 矩形与椭圆的宽高（圆形的宽高相同）
+    pub fn rect_size(&self) -> egui::Vec2 {
+        egui::vec2(self.size, self.height.unwrap_or(self.size))
+    }
+
+    // 矩形、椭圆与填充区域自身（未旋转）的范围：椭圆的 pos 为中心，其他为左上角
+    pub fn frame(&self) -> Option<egui::Rect> {
         match self.shape_type {
-            CanvasShapeType::Line => {
-                let end_point = Pos2::new(self.pos.x + self.size, self.pos.y);
-                painter.line_segment([self.pos, end_point], Stroke::new(2.0, self.color));
+            CanvasShapeType::Rectangle | CanvasShapeType::Region => {
+                Some(egui::Rect::from_min_size(self.pos, self.rect_size()))
             }
-            CanvasShapeType::Arrow => {
-                let end_point = Pos2::new(self.pos.x + self.size, self.pos.y);
-                painter.line_segment([self.pos, end_point], Stroke::new(2.0, self.color));
-
-                // 绘制箭头头部
-                let arrow_size = self.size * 0.1;
-                let arrow_angle = std::f32::consts::PI / 6.0; // 30度
-                let arrow_point1 = Pos2::new(
-                    end_point.x - arrow_size * arrow_angle.cos(),
-                    end_point.y - arrow_size * arrow_angle.sin(),
-                );
-                let arrow_point2 = Pos2::new(
-                    end_point.x - arrow_size * arrow_angle.cos(),
-                    end_point.y + arrow_size * arrow_angle.sin(),
-                );
+            CanvasShapeType::Circle => {
+                Some(egui::Rect::from_center_size(self.pos, self.rect_size()))
+            }
+            _ => None,
+        }
+    }
 
-                painter.line_segment([end_point, arrow_point1], Stroke::new(2.0, self.color));
-                painter.line_segment([end_point, arrow_point2], Stroke::new(2.0, self.color));
+    // 按范围设置矩形、椭圆与填充区域的位置与宽高
+    pub fn set_frame(&mut self, rect: egui::Rect) {
+        self.pos = match self.shape_type {
+            CanvasShapeType::Circle => rect.center(),
+            _ => rect.min,
+        };
+        self.size = rect.width();
+        self.height = Some(rect.height());
+    }
+}
+
+impl Draw for CanvasShape {
+    fn draw(&self, painter: &egui::Painter, selected: bool) {
+        let stroke = Stroke::new(self.stroke_width, self.color);
+        let fill = self.fill.unwrap_or(Color32::TRANSPARENT);
+
+        // 绘制形状本身（形状绕自身中心旋转）
+        match self.shape_type {
+            CanvasShapeType::Line | CanvasShapeType::Arrow => {
+                for polyline in crate::utils::AppUtils::shape_outline(self) {
+                    painter.add(egui::Shape::line(polyline, stroke));
+                }
             }
             CanvasShapeType::Rectangle => {
-                let rect = egui::Rect::from_min_size(self.pos, egui::vec2(self.size, self.size));
-                painter.rect_stroke(
-                    rect,
-                    0.0,
-                    Stroke::new(2.0, self.color),
-                    egui::StrokeKind::Outside,
-                );
+                if self.rotation == 0.0 {
+                    let rect = egui::Rect::from_min_size(self.pos, self.rect_size());
+                    painter.rect(
+                        rect,
+                        self.corner_radius,
+                        fill,
+                        stroke,
+                        egui::StrokeKind::Outside,
+                    );
+                } else {
+                    for mut polyline in crate::utils::AppUtils::shape_outline(self) {
+                        polyline.pop();
+                        painter.add(egui::Shape::convex_polygon(polyline, fill, stroke));
+                    }
+                }
             }
             CanvasShapeType::Triangle => {
-                let half_size = self.size / 2.0;
-                let points = [
-                    self.pos,
-                    Pos2::new(self.pos.x + self.size, self.pos.y),
-                    Pos2::new(self.pos.x + half_size, self.pos.y + half_size),
-                ];
-                painter.add(egui::Shape::convex_polygon(
-                    points.to_vec(),
-                    self.color,
-                    Stroke::new(2.0, self.color),
-                ));
+                for mut points in crate::utils::AppUtils::shape_outline(self) {
+                    points.pop();
+                    painter.add(egui::Shape::convex_polygon(points, fill, stroke));
+                }
             }
             CanvasShapeType::Circle => {
-                painter.circle_stroke(self.pos, self.size / 2.0, Stroke::new(2.0, self.color));
+                let size = self.rect_size();
+                if size.x == size.y {
+                    painter.circle(self.pos, self.size / 2.0, fill, stroke);
+                } else {
+                    // egui 没有椭圆轮廓，使用细分的轮廓多边形绘制
+                    for mut points in crate::utils::AppUtils::shape_outline(self) {
+                        points.pop();
+                        painter.add(egui::Shape::convex_polygon(points, fill, stroke));
+                    }
+                }
+            }
+            CanvasShapeType::Polygon { .. } | CanvasShapeType::Star { .. } => {
+                // 星形不是凸多边形，填充以中心为顶点的扇形三角网格
+                for mut points in crate::utils::AppUtils::shape_outline(self) {
+                    points.pop();
+                    if self.fill.is_some() {
+                        painter.add(crate::utils::AppUtils::fan_mesh(
+                            crate::utils::AppUtils::shape_center(self),
+                            &points,
+                            fill,
+                        ));
+                    }
+                    painter.add(egui::Shape::closed_line(points, stroke));
+                }
+            }
+            CanvasShapeType::Region => {
+                // 区域通常不是凸多边形，三角化后填充
+                for mut points in crate::utils::AppUtils::shape_outline(self) {
+                    points.pop();
+                    if self.fill.is_some() {
+                        painter.add(crate::utils::AppUtils::polygon_mesh(&points, fill));
+                    }
+                    painter.add(egui::Shape::closed_line(points, stroke));
+                }
             }
         }
 
@@ -223,6 +900,199 @@ impl CanvasObject {
             CanvasObject::Shape(shape) => shape.draw(painter, selected),
         }
     }
+
+    // 可被批量替换的颜色（笔画与形状；修正笔笔迹跟随背景颜色，不参与替换）
+    pub fn replaceable_color(&self) -> Option<Color32> {
+        match self {
+            Self::Stroke(stroke) if !stroke.follow_background => Some(stroke.color),
+            Self::Shape(shape) => Some(shape.color),
+            _ => None,
+        }
+    }
+
+    pub fn replaceable_color_mut(&mut self) -> Option<&mut Color32> {
+        match self {
+            Self::Stroke(stroke) if !stroke.follow_background => Some(&mut stroke.color),
+            Self::Shape(shape) => Some(&mut shape.color),
+            _ => None,
+        }
+    }
+
+    // 计算影响绘制结果的内容的摘要，内容改变时摘要随之改变（用于缓存页面缩略图）
+    pub fn hash_appearance(&self, state: &mut impl Hasher) {
+        fn floats(state: &mut impl Hasher, values: impl IntoIterator<Item = f32>) {
+            for value in values {
+                value.to_bits().hash(state);
+            }
+        }
+
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Self::Stroke(stroke) => {
+                stroke.points.len().hash(state);
+                floats(state, stroke.points.iter().flat_map(|p| [p.x, p.y]));
+                floats(state, stroke.widths.iter().copied());
+                floats(state, [stroke.base_width]);
+                stroke.color.hash(state);
+                stroke.highlighter.hash(state);
+            }
+            Self::Image(img) => {
+                img.texture.id().hash(state);
+                floats(
+                    state,
+                    [
+                        img.pos.x,
+                        img.pos.y,
+                        img.size.x,
+                        img.size.y,
+                        img.corner_radius,
+                        img.border_width,
+                    ],
+                );
+                img.border_color.hash(state);
+                (img.flip_x, img.flip_y).hash(state);
+            }
+            Self::Text(text) => {
+                text.text.hash(state);
+                floats(state, [text.pos.x, text.pos.y, text.font_size]);
+                text.max_width.map(f32::to_bits).hash(state);
+                text.color.hash(state);
+                std::mem::discriminant(&text.align).hash(state);
+                std::mem::discriminant(&text.font).hash(state);
+                (text.bold, text.italic).hash(state);
+            }
+            Self::Shape(shape) => {
+                std::mem::discriminant(&shape.shape_type).hash(state);
+                if let CanvasShapeType::Polygon { sides: count }
+                | CanvasShapeType::Star { points: count } = shape.shape_type
+                {
+                    count.hash(state);
+                }
+                floats(
+                    state,
+                    [
+                        shape.pos.x,
+                        shape.pos.y,
+                        shape.size,
+                        shape.rotation,
+                        shape.stroke_width,
+                        shape.corner_radius,
+                    ],
+                );
+                shape.height.map(f32::to_bits).hash(state);
+                (shape.color, shape.fill).hash(state);
+                std::mem::discriminant(&shape.arrow_heads).hash(state);
+                floats(state, shape.outline.iter().flat_map(|p| [p.x, p.y]));
+            }
+        }
+    }
+
+    // 平移对象
+    pub fn translate(&mut self, delta: egui::Vec2) {
+        match self {
+            Self::Image(img) => {
+                img.pos += delta;
+            }
+            Self::Stroke(stroke) => {
+                for p in &mut stroke.points {
+                    *p += delta;
+                }
+                stroke.invalidate_cache();
+            }
+            Self::Text(text) => {
+                text.pos += delta;
+            }
+            Self::Shape(shape) => {
+                shape.pos += delta;
+            }
+        }
+    }
+}
+
+// 图章：保存在图章库中可重复使用的对象
+#[derive(Clone)]
+pub struct Stamp {
+    pub name: String,
+    pub object: CanvasObject,
+}
+
+// 描摹底图：用于描摹练习的参考图片，以及预先计算的边缘图
+pub struct TraceReference {
+    pub texture: egui::TextureHandle,
+    pub rect: egui::Rect,   // 底图在画布上的显示区域
+    pub edge_width: usize,  // 边缘图宽度（像素）
+    pub edge_height: usize, // 边缘图高度（像素）
+    pub edges: Vec<bool>,   // 按行存储的边缘像素标记
+}
+
+impl TraceReference {
+    // 在半径范围内查找离给定位置最近的边缘像素，返回其画布坐标
+    pub fn nearest_edge(&self, pos: Pos2, radius: f32) -> Option<Pos2> {
+        if self.edge_width == 0 || self.rect.width() <= 0.0 || self.rect.height() <= 0.0 {
+            return None;
+        }
+
+        let scale_x = self.edge_width as f32 / self.rect.width();
+        let scale_y = self.edge_height as f32 / self.rect.height();
+        let center_x = (pos.x - self.rect.min.x) * scale_x;
+        let center_y = (pos.y - self.rect.min.y) * scale_y;
+
+        let min_x = ((center_x - radius * scale_x).floor().max(0.0)) as usize;
+        let min_y = ((center_y - radius * scale_y).floor().max(0.0)) as usize;
+        let max_x = ((center_x + radius * scale_x).ceil().max(0.0) as usize).min(self.edge_width);
+        let max_y = ((center_y + radius * scale_y).ceil().max(0.0) as usize).min(self.edge_height);
+
+        let mut nearest: Option<(Pos2, f32)> = None;
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                if !self
+                    .edges
+                    .get(y * self.edge_width + x)
+                    .copied()
+                    .unwrap_or(false)
+                {
+                    continue;
+                }
+                let candidate = Pos2::new(
+                    self.rect.min.x + (x as f32 + 0.5) / scale_x,
+                    self.rect.min.y + (y as f32 + 0.5) / scale_y,
+                );
+                let distance = candidate.distance(pos);
+                if distance <= radius && nearest.is_none_or(|(_, best)| distance < best) {
+                    nearest = Some((candidate, distance));
+                }
+            }
+        }
+        nearest.map(|(candidate, _)| candidate)
+    }
+}
+
+// 画布内容统计（用于状态栏）
+#[derive(Clone, Copy, Default)]
+pub struct CanvasStats {
+    pub strokes: usize, // 笔画数量
+    pub shapes: usize,  // 形状数量
+    pub texts: usize,   // 文本数量
+    pub images: usize,  // 图片数量
+    pub points: usize,  // 笔画点总数
+}
+
+impl CanvasStats {
+    pub fn from_objects(objects: &[CanvasObject]) -> Self {
+        let mut stats = Self::default();
+        for object in objects {
+            match object {
+                CanvasObject::Stroke(stroke) => {
+                    stats.strokes += 1;
+                    stats.points += stroke.points.len();
+                }
+                CanvasObject::Shape(_) => stats.shapes += 1,
+                CanvasObject::Text(_) => stats.texts += 1,
+                CanvasObject::Image(_) => stats.images += 1,
+            }
+        }
+        stats
+    }
 }
 
 // 调整大小锚点类型
@@ -238,6 +1108,13 @@ pub enum ResizeAnchor {
     BottomRight,
 }
 
+// 直线与箭头的端点
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ShapeEndpoint {
+    Start,
+    End,
+}
+
 // 调整大小操作
 #[derive(Clone, Copy)]
 pub struct ResizeOperation {
@@ -245,6 +1122,19 @@ pub struct ResizeOperation {
     pub start_pos: Pos2,
     pub start_size: egui::Vec2,
     pub start_object_pos: Pos2,
+    pub start_shape_rect: Option<egui::Rect>, // 矩形与椭圆自身（未旋转）的范围，用于分别调整宽高
+}
+
+// 旋转中心
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RotationPivot {
+    Center,      // 中心
+    Origin,      // 起点（线条与箭头的起点、矩形与三角形的左上角）
+    TopLeft,     // 左上角
+    TopRight,    // 右上角
+    BottomLeft,  // 左下角
+    BottomRight, // 右下角
+    Custom,      // 自定义（边界框内的相对位置）
 }
 
 // 旋转操作
@@ -252,88 +1142,234 @@ pub struct ResizeOperation {
 pub struct RotationOperation {
     pub start_pos: Pos2,
     pub start_angle: f32,
-    pub center: Pos2,
+    pub pivot: Pos2,            // 旋转中心
+    pub start_object_pos: Pos2, // 旋转开始时对象的位置
+}
+
+// 绘制笔画的输入方式
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PointerInputType {
+    Pen,    // 触控笔或鼠标
+    Finger, // 手指
 }
 
 // 绘图数据结构
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct CanvasStroke {
     pub points: Vec<Pos2>,
     pub widths: Vec<f32>, // 每个点的宽度（用于动态画笔）
+    #[serde(default)]
+    pub forces: Vec<f32>, // 每个点的压力（输入未报告压力时为空）
     pub color: Color32,
     pub base_width: f32,
+    pub follow_background: bool,      // 修正笔笔迹：颜色始终跟随背景颜色
+    pub input_type: PointerInputType, // 绘制该笔画的输入方式
+    #[serde(default)]
+    pub highlighter: bool, // 荧光笔笔迹
+    #[serde(skip)]
+    pub fade: Option<StrokeFade>, // 渐隐笔迹的计时（普通笔迹为 None，不保存）
+    #[serde(skip)]
+    pub cache: StrokeCache, // 派生数据缓存（不保存）
+}
+
+// 渐隐笔迹：提交后保持显示一段时间，然后逐渐变透明，完全消失后从画布移除
+#[derive(Clone, Copy)]
+pub struct StrokeFade {
+    pub created_at: Instant, // 提交时间
+    pub hold: f32,           // 完全显示的时长（秒）
+}
+
+impl StrokeFade {
+    // 渐隐阶段的时长（秒）
+    const FADE_OUT: f32 = 1.0;
+
+    // 当前的不透明度（1.0 到 0.0）
+    pub fn opacity(&self) -> f32 {
+        let fading = self.created_at.elapsed().as_secs_f32() - self.hold;
+        (1.0 - fading / Self::FADE_OUT).clamp(0.0, 1.0)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.opacity() <= 0.0
+    }
+}
+
+// 笔画的派生数据缓存，首次使用时计算；修改笔画的点或宽度后需调用 CanvasStroke::invalidate_cache
+#[derive(Default)]
+pub struct StrokeCache {
+    bounds: std::sync::OnceLock<egui::Rect>, // 包围盒（包含笔画宽度）
+    mesh: std::sync::Mutex<Option<StrokeMesh>>, // 三角化后的网格
+}
+
+#[derive(Clone)]
+struct StrokeMesh {
+    color: Color32,        // 生成网格时的笔画颜色
+    pixels_per_point: f32, // 生成网格时的缩放比例（影响抗锯齿羽化宽度）
+    mesh: Arc<egui::Mesh>,
+}
+
+// 网格以 Arc 共享，复制笔画（如撤销快照）时不会复制网格数据
+impl Clone for StrokeCache {
+    fn clone(&self) -> Self {
+        Self {
+            bounds: self.bounds.clone(),
+            mesh: std::sync::Mutex::new(self.mesh.lock().ok().and_then(|mesh| mesh.clone())),
+        }
+    }
 }
 
 impl Draw for CanvasStroke {
     fn draw(&self, painter: &egui::Painter, selected: bool) {
-        if self.points.len() < 2 {
-            return;
+        let opacity = self.fade.map_or(1.0, |fade| fade.opacity());
+        if selected {
+            // 选中时着色为蓝色并保留笔迹原有的不透明度；高亮只在少数笔画上出现，直接绘制而不缓存
+            let tint = Color32::from_rgba_unmultiplied(0, 0, 255, self.color.a());
+            painter.extend(self.shapes(tint.gamma_multiply(opacity)));
+        } else if opacity < 1.0 {
+            // 渐隐中的笔画每帧颜色都不同，不使用缓存的网格
+            painter.extend(self.shapes(self.color.gamma_multiply(opacity)));
+        } else {
+            painter.add(egui::Shape::Mesh(self.mesh(painter)));
         }
+    }
+}
 
-        let color = if selected { Color32::BLUE } else { self.color };
+impl CanvasStroke {
+    // 笔画的包围盒（包含笔画宽度）
+    pub fn bounds(&self) -> egui::Rect {
+        *self.cache.bounds.get_or_init(|| {
+            let max_width = self.widths.iter().copied().fold(0.0, f32::max);
+            egui::Rect::from_points(&self.points).expand(max_width / 2.0)
+        })
+    }
 
-        // 如果所有宽度相同，使用简单路径
-        let all_same_width = self.widths.windows(2).all(|w| (w[0] - w[1]).abs() < 0.01);
+    pub fn invalidate_cache(&mut self) {
+        self.cache = StrokeCache::default();
+    }
 
-        if all_same_width && self.points.len() == 2 {
-            // 只有两个点且宽度相同，直接画线段
-            painter.line_segment(
-                [self.points[0], self.points[1]],
-                Stroke::new(self.widths[0], color),
-            );
-        } else if all_same_width {
-            // 多个点但宽度相同，使用路径
-            let path = egui::epaint::PathShape::line(
-                self.points.clone(),
-                Stroke::new(self.widths[0], color),
-            );
-            painter.add(egui::Shape::Path(path));
-        } else {
-            // 宽度不同，分段绘制
-            for i in 0..self.points.len() - 1 {
-                let avg_width = (self.widths[i] + self.widths[i + 1]) / 2.0;
-                painter.line_segment(
-                    [self.points[i], self.points[i + 1]],
-                    Stroke::new(avg_width, color),
-                );
+    // 组成笔画的图形（缺少宽度数据时使用基础宽度，宽度数量与点数不一致时按比例重采样）
+    fn shapes(&self, color: Color32) -> Vec<egui::Shape> {
+        let width = self.widths.first().copied().unwrap_or(self.base_width);
+        match self.points.as_slice() {
+            [] => Vec::new(),
+            // 单点笔画（点击产生的圆点）
+            [point] => vec![egui::Shape::circle_filled(*point, width / 2.0, color)],
+            _ if width <= crate::utils::AppUtils::WIDE_STROKE_WIDTH
+                && self
+                    .widths
+                    .windows(2)
+                    .all(|w| matches!(w, [a, b] if (a - b).abs() < 0.01)) =>
+            {
+                // 宽度相同：两个点直接画线段，多个点使用路径
+                match self.points.as_slice() {
+                    &[a, b] => vec![egui::Shape::line_segment([a, b], Stroke::new(width, color))],
+                    points => vec![egui::Shape::line(
+                        points.to_vec(),
+                        Stroke::new(width, color),
+                    )],
+                }
+            }
+            points => {
+                // 宽度不同或笔画较宽，绘制为带圆形笔帽与转折的填充网格
+                let widths = if self.widths.len() == points.len() {
+                    std::borrow::Cow::Borrowed(&self.widths)
+                } else {
+                    std::borrow::Cow::Owned(crate::utils::AppUtils::resample_widths(
+                        &self.widths,
+                        points.len(),
+                    ))
+                };
+                vec![egui::Shape::mesh(
+                    crate::utils::AppUtils::build_stroke_mesh(points, &widths, color),
+                )]
             }
         }
     }
+
+    // 三角化后的笔画网格：首次绘制时生成，颜色或缩放比例变化时重新生成
+    fn mesh(&self, painter: &egui::Painter) -> Arc<egui::Mesh> {
+        let pixels_per_point = painter.pixels_per_point();
+        let mut cached = self
+            .cache
+            .mesh
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        if let Some(cached) = cached.as_ref()
+            && cached.color == self.color
+            && cached.pixels_per_point.to_bits() == pixels_per_point.to_bits()
+        {
+            return Arc::clone(&cached.mesh);
+        }
+
+        let options = painter.ctx().tessellation_options(|options| *options);
+        let mut tessellator =
+            egui::epaint::Tessellator::new(pixels_per_point, options, [1, 1], Vec::new());
+        let mut mesh = egui::Mesh::default();
+        for shape in self.shapes(self.color) {
+            tessellator.tessellate_shape(shape, &mut mesh);
+        }
+        let mesh = Arc::new(mesh);
+        *cached = Some(StrokeMesh {
+            color: self.color,
+            pixels_per_point,
+            mesh: Arc::clone(&mesh),
+        });
+        mesh
+    }
 }
 
-// FPS 计数器
+// FPS 计数器：按最近若干帧的平均帧时间计算，显示的数值不会逐帧跳动
 pub struct FpsCounter {
-    pub frame_count: u32,
-    pub last_time: Instant,
-    pub current_fps: f32,
+    pub frame_times: VecDeque<f32>, // 最近各帧的时长（秒）
+    pub last_time: Instant,         // 上一帧的时间
+    pub current_fps: f32,           // 平均帧率
+    pub frame_time_ms: f32,         // 上一帧的时长（毫秒）
 }
 
 impl FpsCounter {
+    // 参与平均的帧数
+    const WINDOW: usize = 60;
+    // 超过该时长（秒）未更新时（关闭 FPS 显示或界面空闲）重新开始统计
+    const RESET_GAP: f32 = 1.0;
+
     pub fn new() -> Self {
         Self {
-            frame_count: 0,
+            frame_times: VecDeque::with_capacity(Self::WINDOW),
             last_time: Instant::now(),
             current_fps: 0.0,
+            frame_time_ms: 0.0,
         }
     }
 
     pub fn update(&mut self) -> f32 {
-        self.frame_count += 1;
-
         let now = Instant::now();
         let elapsed = now.duration_since(self.last_time).as_secs_f32();
+        self.last_time = now;
+        self.record_frame_time(elapsed)
+    }
+
+    // 记录一帧的时长（秒），返回更新后的平均帧率
+    pub fn record_frame_time(&mut self, seconds: f32) -> f32 {
+        if seconds > Self::RESET_GAP {
+            self.frame_times.clear();
+            return self.current_fps;
+        }
 
-        if elapsed >= 0.05 {
-            self.current_fps = self.frame_count as f32 / elapsed;
-            self.frame_count = 0;
-            self.last_time = now;
+        if self.frame_times.len() == Self::WINDOW {
+            self.frame_times.pop_front();
         }
+        self.frame_times.push_back(seconds);
+        self.frame_time_ms = seconds * 1000.0;
 
+        let total: f32 = self.frame_times.iter().sum();
+        if total > 0.0 {
+            self.current_fps = self.frame_times.len() as f32 / total;
+        }
         self.current_fps
     }
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum RenderUpdateMode {
     /// This is the default for the demo.
     ///
@@ -376,74 +1412,328 @@ impl Default for RenderUpdateMode {
 // 单个正在绘制的笔画数据
 pub struct ActiveStroke {
     pub points: Vec<Pos2>,
-    pub widths: Vec<f32>,    // 每个点的宽度（用于动态画笔）
-    pub times: Vec<f64>,     // 每个点的时间戳（用于速度计算）
-    pub start_time: Instant, // 笔画开始时间
+    pub widths: Vec<f32>,             // 每个点的宽度（用于动态画笔）
+    pub times: Vec<f64>,              // 每个点的时间戳（用于速度计算）
+    pub forces: Vec<f32>,             // 每个点的压力（0.0-1.0，输入未报告压力时为空）
+    pub start_time: Instant,          // 笔画开始时间
+    pub input_type: PointerInputType, // 输入方式
 }
 
 // 应用程序状态
 pub struct AppState {
-    pub canvas_objects: Vec<CanvasObject>,          // 所有画布对象
+    pub board: Board,                  // 画板模型（当前页的所有画布对象）
+    pub pages: Vec<Vec<CanvasObject>>, // 所有页面，当前页存于 board 中
+    pub current_page: usize,           // 当前页索引
+    pub show_page_overview: bool,      // 是否显示页面总览
+    pub page_thumbnails: HashMap<u64, egui::TextureHandle>, // 页面总览的缩略图（按页面内容与尺寸的摘要缓存）
+    pub canvas_locked: bool,    // 锁定画布（只读），禁止一切修改内容的操作
+    pub pan_offset: egui::Vec2, // 视图平移（屏幕坐标 = 画布坐标 × 缩放 + 平移）
+    pub zoom: f32,              // 视图缩放比例
+    pub undo_stack: Vec<BoardSnapshot>, // 撤销历史（每一项为修改前的对象快照）
+    pub redo_stack: Vec<BoardSnapshot>, // 重做历史
+    pub max_undo_steps: usize,  // 最多保留的撤销步数
     pub active_strokes: HashMap<u64, ActiveStroke>, // 多点触控笔画，存储触控 ID 到正在绘制的笔画
-    pub is_drawing: bool,                           // 是否正在绘制
-    pub brush_color: Color32,                       // 画笔颜色
-    pub brush_width: f32,                           // 画笔大小
+    pub is_drawing: bool,       // 是否正在绘制
+    pub brush_color: Color32,   // 画笔颜色
+    pub eyedropper_active: bool, // 吸管：下一次单击画布时吸取颜色
+    pub brush_width: f32,       // 画笔大小
+    pub min_brush_width: f32,   // 画笔宽度滑块的下限
+    pub max_brush_width: f32,   // 画笔宽度滑块的上限（快捷宽度按比例缩放）
+    pub brush_opacity: f32,     // 画笔不透明度
+    pub highlighter: bool,      // 荧光笔：固定宽度的宽笔头，半透明叠加
+    pub fading_ink: bool,       // 渐隐笔迹：提交的笔画显示一段时间后自动消失
+    pub fading_ink_hold: f32,   // 渐隐笔迹完全显示的时长（秒）
+    pub correction_pen: bool,   // 修正笔：使用背景颜色绘制，用于涂改
+    pub integer_widths: bool,   // 提交笔画时将宽度取整
     pub dynamic_brush_width_mode: DynamicBrushWidthMode, // 动态画笔大小微调
-    pub stroke_smoothing: bool,                     // 笔画平滑选项
-    pub interpolation_frequency: f32,               // 插值频率
-    pub current_tool: CanvasTool,                   // 当前工具
-    pub eraser_size: f32,                           // 橡皮擦大小
-    pub background_color: Color32,                  // 背景颜色
-    pub selected_object: Option<usize>,             // 选中的对象索引
-    pub drag_start_pos: Option<Pos2>,               //
-    pub show_size_preview: bool,                    //
-    pub show_text_dialog: bool,                     //
-    pub new_text_content: String,                   //
-    pub show_shape_dialog: bool,                    //
-    pub show_fps: bool,                             // 是否显示 FPS
-    pub fps_counter: FpsCounter,                    // FPS 计数器
-    pub touch_points: HashMap<u64, Pos2>,           // 多点触控点，存储触控 ID 到位置的映射
-    pub window_mode: WindowMode,                    // 窗口模式
+    pub show_dynamic_width_legend: bool, // 在工具栏中显示动态画笔当前计算出的宽度
+    pub separate_finger_style: bool, // 区分触控笔与手指：手指使用固定宽度的荧光笔样式
+    pub finger_brush_width: f32, // 手指笔画的固定宽度
+    pub finger_brush_opacity: f32, // 手指笔画的不透明度
+    pub last_pointer_input: PointerInputType, // 最近一次按下指针的输入方式
+    pub stroke_smoothing: bool, // 笔画平滑选项
+    pub smoothing_iterations: usize, // 平滑（Chaikin 切角）迭代次数
+    pub show_smoothing_preview: bool, // 是否预览选中笔画重新平滑后的效果
+    pub live_stroke_smoothing: bool, // 绘制中笔画末端的显示平滑（不影响记录的数据）
+    pub live_smoothing_window: usize, // 显示平滑作用的末端点数
+    pub auto_straighten: bool,  // 自动拉直接近直线的笔画
+    pub straighten_threshold: f32, // 自动拉直阈值（像素）
+    pub recognize_shapes: bool, // 识别形状：将接近直线、圆形、矩形或三角形的笔画替换为形状
+    pub interpolation_frequency: f32, // 插值频率
+    pub simplify_epsilon: f32,  // 笔画简化容差（像素），0 表示不简化
+    pub palm_rejection: bool,   // 手掌防误触：触控笔按下期间忽略手指与手掌的触点
+    pub pen_touch_ids: HashSet<u64>, // 当前按下的触控笔触点（报告压力的触点）
+    pub min_stroke_length: f32, // 最短笔画长度（像素），更短的笔画提交时丢弃
+    pub min_stroke_duration: f32, // 最短笔画时长（秒），更短的笔画提交时丢弃
+    pub current_tool: CanvasTool, // 当前工具
+    pub tool_settings: HashMap<CanvasTool, ToolSettings>, // 各工具上次使用的次要设置
+    pub recent_tools: Vec<CanvasTool>, // 最近使用过的工具（最后一个为上一个工具）
+    pub quick_toggle_mode: QuickToggleMode, // 快速切换的目标工具
+    pub quick_toggle_pair: [CanvasTool; 2], // 自定义快速切换的两个工具
+    pub tool_switch_stroke_behavior: ToolSwitchStrokeBehavior, // 切换工具时未完成笔画的处理方式
+    pub commit_on_pointer_leave: bool, // 指针离开窗口时立即提交未完成的笔画
+    pub stroke_insert_position: StrokeInsertPosition, // 新笔迹置于
+    pub eraser_size: f32,       // 橡皮擦大小
+    pub dynamic_eraser_size: bool, // 是否根据移动速度调整橡皮擦大小
+    pub dynamic_eraser_min_scale: f32, // 动态橡皮擦最小缩放比例
+    pub dynamic_eraser_max_scale: f32, // 动态橡皮擦最大缩放比例
+    pub eraser_last_sample: Option<(Pos2, Instant)>, // 橡皮擦上一次的位置与时间（用于速度计算）
+    pub eraser_speed: f32,      // 平滑后的橡皮擦移动速度
+    pub soft_eraser: bool,      // 像素橡皮擦使用柔和擦除（逐次降低笔画不透明度）
+    pub soft_eraser_strength: f32, // 柔和擦除每次经过降低的不透明度比例
+    pub soft_erased_strokes: Vec<ObjectId>, // 本次擦除经过的笔画（每次擦除只降低一次）
+    pub object_eraser_split_strokes: bool, // 对象橡皮擦只擦除笔画经过的部分，而不是整条笔画
+    pub stroke_width_tool_size: f32, // 粗细调整工具大小
+    pub stroke_width_tool_strength: f32, // 粗细调整强度（每拖动一个工具直径的缩放比例）
+    pub area_measurement: Option<AreaMeasurement>, // 最近一次面积测量结果
+    pub pen_points: Vec<Pos2>,  // 折线工具已放置的顶点
+    pub pen_smooth: bool,       // 完成时将折线平滑为曲线
+    pub area_measure_place_label: bool, // 测量后是否在画布上放置面积标签
+    pub protractor: Option<ProtractorMeasurement>, // 量角器位置与正在测量的角
+    pub protractor_place_shapes: bool, // 测量后是否在画布上放置角的两条边与度数标签
+    pub distance_measurement: Option<(Pos2, Pos2)>, // 距离测量的起点与终点
+    pub distance_place_annotation: bool, // 松开后是否在画布上放置尺寸标注
+    pub pixels_per_cm: Option<f32>, // 长度校准：每厘米的像素数，None 时以像素显示长度
+    pub background_color: Color32, // 背景颜色
+    pub replace_color_from: Color32, // 替换颜色：要被替换的颜色
+    pub replace_color_to: Color32, // 替换颜色：替换后的颜色
+    pub replace_color_tolerance: f32, // 替换颜色：允许的颜色差异（RGB 欧氏距离）
+    pub synced_background_color: Color32, // 修正笔笔迹当前使用的背景颜色
+    pub stamps: Vec<Stamp>,     // 图章库
+    pub new_stamp_name: String, // 新图章名称
+    pub active_stamp: Option<usize>, // 当前用于盖章的图章索引
+    pub show_axes: bool,        // 是否显示坐标轴
+    pub axes_origin: egui::Vec2, // 坐标原点（相对画布，0.0 到 1.0）
+    pub axes_unit_spacing: f32, // 坐标轴单位长度（像素）
+    pub grid_enabled: bool,     // 显示背景网格，并将新插入与移动的对象吸附到网格交点
+    pub grid_size: f32,         // 网格间距（像素）
+    pub grid_snap_strokes: bool, // 网格吸附时笔画的起点也吸附到网格交点
+    pub trace_reference: Option<TraceReference>, // 描摹底图
+    pub trace_snap: bool,       // 描摹吸附：画笔点向底图边缘靠拢
+    pub trace_snap_strength: f32, // 描摹吸附强度（0 到 1）
+    pub trace_snap_radius: f32, // 描摹吸附搜索半径（像素）
+    pub ruler: Option<(Pos2, f32)>, // 直尺（边缘中点、角度），显示时画笔靠近边缘的点吸附到边缘上
+    pub ruler_drag: Option<RulerDrag>, // 正在进行的直尺拖动
+    pub selected_object: Option<usize>, // 选中的对象索引
+    pub selected_objects: Vec<usize>, // 框选的多个对象索引（多选时 selected_object 为空）
+    pub marquee_start: Option<Pos2>, // 框选的起点
+    pub clipboard: Option<CanvasObject>, // 复制或剪切的对象（用于粘贴）
+    pub drag_start_pos: Option<Pos2>, //
+    pub snap_to_canvas: bool,   // 移动对象时吸附画布中心与边缘
+    pub snap_to_objects: bool,  // 移动对象时吸附其他对象的边缘与中心
+    pub keep_image_aspect_ratio: bool, // 调整图片大小时保持宽高比（按住 Shift 临时切换）
+    pub keep_objects_on_canvas: bool, // 移动对象时保证对象至少有一部分留在画布内
+    pub anchor_size: f32,       // 调整大小与旋转锚点的显示半径
+    pub anchor_hit_radius: f32, // 锚点的触控判定半径
+    pub move_snap_offset: egui::Vec2, // 上一帧因吸附产生的偏移
+    pub snap_guides: Vec<[Pos2; 2]>, // 当前显示的对齐参考线
+    pub object_guides: Vec<[Pos2; 2]>, // 当前显示的对象间对齐参考线
+    pub show_size_preview: bool, //
+    pub show_text_dialog: bool, //
+    pub new_text_content: String, //
+    pub new_text_font: TextFont, // 插入文本的字体
+    pub new_text_bold: bool,    // 插入文本是否为粗体
+    pub new_text_italic: bool,  // 插入文本是否为斜体
+    pub editing_text: Option<usize>, // 正在编辑的文本对象索引
+    pub editing_text_draft: Option<CanvasText>, // 编辑中的文本内容与样式，确认后写回对象
+    pub show_shape_dialog: bool, //
+    pub tap_place_shapes: bool, // 选择形状后点击画布放置，并输入精确尺寸
+    pub shape_stroke_width: f32, // 插入形状的轮廓宽度
+    pub shape_filled: bool,     // 插入的形状是否填充
+    pub shape_fill_color: Color32, // 插入形状的填充颜色
+    pub shape_polygon_sides: u32, // 插入正多边形的边数
+    pub shape_star_points: u32, // 插入星形的角数
+    pub arrow_heads: ArrowHeads, // 插入箭头的箭头位置
+    pub pending_shape_placement: Option<CanvasShapeType>, // 等待点击放置的形状
+    pub shape_size_entry: Option<ShapeSizeEntry>, // 正在输入尺寸的形状
+    pub show_text_search: bool, // 是否显示查找文本窗口
+    pub text_search_query: String, // 查找文本的关键词
+    pub text_search_cursor: usize, // 下一个要跳转的匹配序号
+    pub show_fps: bool,         // 是否显示 FPS
+    pub fps_counter: FpsCounter, // FPS 计数器
+    pub drawn_object_count: usize, // 上一帧实际绘制的对象数量（可见区域外的对象被跳过）
+    pub touch_points: HashMap<u64, Pos2>, // 多点触控点，存储触控 ID 到位置的映射
+    pub window_mode: WindowMode, // 窗口模式
     // pub window_mode_changed: bool,                  // 窗口模式是否已更改
     pub keep_insertion_window_open: bool, // 是否保持插入对象窗口开启
     pub resize_anchor_hovered: Option<ResizeAnchor>, // 当前悬停的调整大小锚点
     pub rotation_anchor_hovered: bool,    // 是否悬停在旋转锚点上
     pub resize_operation: Option<ResizeOperation>, // 当前正在进行的调整大小操作
     pub rotation_operation: Option<RotationOperation>, // 当前正在进行的旋转操作
+    pub endpoint_hovered: Option<ShapeEndpoint>, // 当前悬停的直线或箭头端点手柄
+    pub endpoint_operation: Option<ShapeEndpoint>, // 正在拖动的直线或箭头端点
+    pub crop_operation: Option<CropOperation>, // 正在进行的图片裁剪
+    pub rotation_pivot: RotationPivot,    // 旋转中心设置
+    pub custom_rotation_pivot: egui::Vec2, // 自定义旋转中心（相对边界框，0.0 到 1.0）
     // pub available_video_modes: Vec<winit::monitor::VideoModeHandle>, // 可用的视频模式
     // pub selected_video_mode_index: Option<usize>,   // 选中的视频模式索引
     pub quick_colors: Vec<Color32>,    // 快捷颜色列表
+    pub recent_colors: Vec<Color32>,   // 最近用于提交笔迹的颜色（最近的在前，不重复）
     pub show_quick_color_editor: bool, // 是否显示快捷颜色编辑器
+    pub quick_widths: Vec<f32>,        // 快捷宽度列表
+    pub pending_config_import: Option<crate::config::SharedConfig>, // 待确认导入的配置
     pub new_quick_color: Color32,      // 新快捷颜色，用于添加
     pub show_touch_points: bool,       // 是否显示触控点，用于调试
-    pub present_mode: PresentMode,     // 垂直同步模式
-    pub present_mode_changed: bool,    // 垂直同步模式是否已更改
+    pub show_status_bar: bool,         // 是否显示状态栏（对象与点数统计）
+    pub show_stroke_width_heatmap: bool, // 是否以热力图显示笔画各点宽度，用于调试
+    pub radial_menu_enabled: bool,     // 是否启用长按径向菜单
+    pub radial_menu_hold_duration: f32, // 打开径向菜单所需的长按时间（秒）
+    pub hold_state: Option<HoldState>, // 当前长按检测状态
+    pub radial_menu_center: Option<Pos2>, // 已打开的径向菜单中心
+    pub pen_color_gesture_enabled: bool, // 是否启用按住笔按钮画圈切换颜色
+    pub color_gesture: Option<ColorGestureState>, // 当前颜色切换手势状态
+    pub tap_ripple_enabled: bool,      // 点击画布时是否显示波纹动画（演示辅助）
+    pub tap_ripple_color: Color32,     // 波纹颜色
+    pub tap_ripples: Vec<(Pos2, Instant)>, // 正在播放的波纹（位置与开始时间）
+    pub present_mode: PresentMode,     // 选择的垂直同步模式（下次启动时生效）
+    pub active_present_mode: PresentMode, // 窗口实际使用的垂直同步模式
+    pub present_mode_changed: bool,    // 垂直同步模式是否已更改（显示重启提示）
     pub theme_mode: ThemeMode,         // 主题模式
+    pub custom_theme: CustomTheme,     // 自定义主题颜色
+    pub accessibility: AccessibilitySettings, // 无障碍设置
+    pub window_geometry: WindowGeometry, // 窗口位置与大小
+    pub toolbar_position: ToolbarPosition, // 工具栏位置
+    pub canvas_resize_behavior: CanvasResizeBehavior, // 画布尺寸变化时内容的调整方式
+    pub last_canvas_rect: Option<egui::Rect>, // 上一帧的画布区域
+    pub clipboard_export_area: CanvasExportArea, // 复制或导出画布图片的范围
+    pub toast: Option<(String, Instant)>, // 提示消息及其显示时间
     pub render_update_mode: RenderUpdateMode,
 }
 
 impl Default for AppState {
     fn default() -> Self {
         Self {
-            canvas_objects: Vec::new(),
+            board: Board::default(),
+            pages: vec![Vec::new()],
+            current_page: 0,
+            show_page_overview: false,
+            page_thumbnails: HashMap::new(),
+            canvas_locked: false,
+            pan_offset: egui::Vec2::ZERO,
+            zoom: 1.0,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            max_undo_steps: 50,
             active_strokes: HashMap::new(),
             is_drawing: false,
             brush_color: Color32::WHITE,
             brush_width: 3.0,
+            min_brush_width: 1.0,
+            max_brush_width: 20.0,
+            brush_opacity: 1.0,
+            eyedropper_active: false,
+            highlighter: false,
+            fading_ink: false,
+            fading_ink_hold: 5.0,
+            correction_pen: false,
+            integer_widths: false,
             dynamic_brush_width_mode: DynamicBrushWidthMode::Disabled,
+            show_dynamic_width_legend: false,
+            separate_finger_style: false,
+            finger_brush_width: 24.0,
+            finger_brush_opacity: 0.4,
+            last_pointer_input: PointerInputType::Pen,
             stroke_smoothing: true,
+            smoothing_iterations: 2,
+            show_smoothing_preview: false,
+            live_stroke_smoothing: false,
+            live_smoothing_window: 4,
+            auto_straighten: false,
+            straighten_threshold: 8.0,
+            recognize_shapes: false,
             interpolation_frequency: 0.3,
+            simplify_epsilon: 0.5,
+            palm_rejection: false,
+            pen_touch_ids: HashSet::new(),
+            min_stroke_length: 0.0,
+            min_stroke_duration: 0.0,
             current_tool: CanvasTool::Brush,
+            tool_settings: HashMap::new(),
+            recent_tools: Vec::new(),
+            quick_toggle_mode: QuickToggleMode::Recent,
+            quick_toggle_pair: [CanvasTool::Brush, CanvasTool::ObjectEraser],
+            tool_switch_stroke_behavior: ToolSwitchStrokeBehavior::Commit,
+            commit_on_pointer_leave: false,
+            stroke_insert_position: StrokeInsertPosition::Top,
             eraser_size: 10.0,
+            dynamic_eraser_size: false,
+            dynamic_eraser_min_scale: 0.5,
+            dynamic_eraser_max_scale: 3.0,
+            eraser_last_sample: None,
+            eraser_speed: 0.0,
+            soft_eraser: false,
+            soft_eraser_strength: 0.3,
+            object_eraser_split_strokes: false,
+            soft_erased_strokes: Vec::new(),
+            stroke_width_tool_size: 30.0,
+            stroke_width_tool_strength: 0.05,
+            area_measurement: None,
+            pen_points: Vec::new(),
+            pen_smooth: false,
+            area_measure_place_label: false,
+            protractor: None,
+            protractor_place_shapes: false,
+            distance_measurement: None,
+            distance_place_annotation: false,
+            pixels_per_cm: None,
             background_color: Color32::from_rgb(0, 50, 35),
+            replace_color_from: Color32::BLACK,
+            replace_color_to: Color32::RED,
+            replace_color_tolerance: 30.0,
+            synced_background_color: Color32::from_rgb(0, 50, 35),
+            stamps: Vec::new(),
+            new_stamp_name: String::new(),
+            active_stamp: None,
+            show_axes: false,
+            axes_origin: egui::vec2(0.5, 0.5),
+            axes_unit_spacing: 50.0,
+            grid_enabled: false,
+            grid_size: 40.0,
+            grid_snap_strokes: false,
+            trace_reference: None,
+            trace_snap: false,
+            trace_snap_strength: 0.5,
+            trace_snap_radius: 12.0,
+            ruler: None,
+            ruler_drag: None,
             selected_object: None,
+            selected_objects: Vec::new(),
+            marquee_start: None,
+            clipboard: None,
             drag_start_pos: None,
+            snap_to_canvas: false,
+            snap_to_objects: true,
+            keep_image_aspect_ratio: true,
+            keep_objects_on_canvas: true,
+            anchor_size: 10.0,
+            anchor_hit_radius: 15.0,
+            move_snap_offset: egui::Vec2::ZERO,
+            snap_guides: Vec::new(),
+            object_guides: Vec::new(),
             show_size_preview: false,
             show_fps: true,
             fps_counter: FpsCounter::new(),
+            drawn_object_count: 0,
             show_text_dialog: false,
             new_text_content: String::from(""),
+            new_text_font: TextFont::Cjk,
+            new_text_bold: false,
+            new_text_italic: false,
+            editing_text: None,
+            editing_text_draft: None,
             show_shape_dialog: false,
+            tap_place_shapes: false,
+            shape_stroke_width: 2.0,
+            shape_filled: false,
+            arrow_heads: ArrowHeads::End,
+            shape_fill_color: Color32::from_rgba_unmultiplied(255, 255, 255, 64),
+            shape_polygon_sides: 6,
+            shape_star_points: 5,
+            pending_shape_placement: None,
+            shape_size_entry: None,
+            show_text_search: false,
+            text_search_query: String::new(),
+            text_search_cursor: 0,
             touch_points: HashMap::new(),
             window_mode: WindowMode::BorderlessFullscreen,
             // window_mode_changed: false,
@@ -452,6 +1742,11 @@ impl Default for AppState {
             rotation_anchor_hovered: false,
             resize_operation: None,
             rotation_operation: None,
+            endpoint_hovered: None,
+            endpoint_operation: None,
+            crop_operation: None,
+            rotation_pivot: RotationPivot::Center,
+            custom_rotation_pivot: egui::vec2(0.5, 0.5),
             // available_video_modes: Vec::new(),
             // selected_video_mode_index: None,
             quick_colors: vec![
@@ -461,13 +1756,219 @@ impl Default for AppState {
                 Color32::from_rgb(0, 0, 0),       // 黑色
                 Color32::from_rgb(255, 255, 255), // 白色
             ],
+            recent_colors: Vec::new(),
             show_quick_color_editor: false,
+            quick_widths: vec![1.0, 3.0, 5.0],
+            pending_config_import: None,
             new_quick_color: Color32::WHITE,
             show_touch_points: false,
-            present_mode: PresentMode::AAutoVsync,
+            show_status_bar: true,
+            show_stroke_width_heatmap: false,
+            radial_menu_enabled: false,
+            radial_menu_hold_duration: 0.6,
+            hold_state: None,
+            radial_menu_center: None,
+            pen_color_gesture_enabled: false,
+            color_gesture: None,
+            tap_ripple_enabled: false,
+            tap_ripple_color: Color32::from_rgb(255, 140, 0),
+            tap_ripples: Vec::new(),
+            present_mode: PresentMode::AutoVsync,
+            active_present_mode: PresentMode::AutoVsync,
             present_mode_changed: false,
             theme_mode: ThemeMode::System,
+            custom_theme: CustomTheme::default(),
+            accessibility: AccessibilitySettings::default(),
+            window_geometry: WindowGeometry::default(),
+            toolbar_position: ToolbarPosition::Floating,
+            canvas_resize_behavior: CanvasResizeBehavior::Keep,
+            last_canvas_rect: None,
+            clipboard_export_area: CanvasExportArea::FullCanvas,
+            toast: None,
             render_update_mode: RenderUpdateMode::default(),
         }
     }
 }
+
+impl AppState {
+    // 画布坐标到屏幕坐标的视图变换
+    pub fn view_transform(&self) -> egui::emath::TSTransform {
+        egui::emath::TSTransform::new(self.pan_offset, self.zoom)
+    }
+
+    // 记录最近使用的颜色：移到最前，超出上限时丢弃最早的颜色
+    pub fn record_recent_color(&mut self, color: Color32) {
+        const MAX_RECENT_COLORS: usize = 8;
+
+        self.recent_colors.retain(|recent| *recent != color);
+        self.recent_colors.insert(0, color);
+        self.recent_colors.truncate(MAX_RECENT_COLORS);
+    }
+
+    // 在修改画布内容之前调用：保存当前对象的快照，超出上限时丢弃最早的记录，并清空重做历史
+    pub fn push_undo_snapshot(&mut self) {
+        self.undo_stack.push(self.board.snapshot());
+        self.trim_undo_stack();
+        self.redo_stack.clear();
+    }
+
+    // 丢弃超出上限的最早撤销记录
+    pub fn trim_undo_stack(&mut self) {
+        let excess = self.undo_stack.len().saturating_sub(self.max_undo_steps);
+        self.undo_stack.drain(..excess);
+    }
+
+    // 恢复到上一次修改之前，返回是否有可撤销的记录
+    pub fn undo(&mut self) -> bool {
+        let Some(snapshot) = self.undo_stack.pop() else {
+            return false;
+        };
+        let current = self.board.restore(snapshot);
+        self.redo_stack.push(current);
+        true
+    }
+
+    // 重新应用上一次撤销的修改，返回是否有可重做的记录
+    pub fn redo(&mut self) -> bool {
+        let Some(snapshot) = self.redo_stack.pop() else {
+            return false;
+        };
+        let current = self.board.restore(snapshot);
+        self.undo_stack.push(current);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stroke(points: &[Pos2], widths: &[f32]) -> CanvasStroke {
+        CanvasStroke {
+            points: points.to_vec(),
+            widths: widths.to_vec(),
+            forces: Vec::new(),
+            color: Color32::BLACK,
+            base_width: 4.0,
+            follow_background: false,
+            input_type: PointerInputType::Pen,
+            highlighter: false,
+            fade: None,
+            cache: StrokeCache::default(),
+        }
+    }
+
+    #[test]
+    fn empty_stroke_has_no_shapes() {
+        assert!(
+            stroke(&[], &[]).shapes(Color32::BLACK).is_empty(),
+            "an empty stroke should draw nothing"
+        );
+        assert!(
+            stroke(&[], &[3.0]).shapes(Color32::BLACK).is_empty(),
+            "widths without points should draw nothing"
+        );
+    }
+
+    #[test]
+    fn single_point_stroke_is_a_dot() {
+        let center = Pos2::new(5.0, 5.0);
+        let with_width = stroke(&[center], &[6.0]).shapes(Color32::BLACK);
+        assert!(
+            matches!(
+                with_width.as_slice(),
+                [egui::Shape::Circle(circle)] if circle.center == center && circle.radius == 3.0
+            ),
+            "a single point should draw a dot of its width"
+        );
+
+        // 缺少宽度数据时使用基础宽度
+        let without_width = stroke(&[center], &[]).shapes(Color32::BLACK);
+        assert!(
+            matches!(
+                without_width.as_slice(),
+                [egui::Shape::Circle(circle)] if circle.radius == 2.0
+            ),
+            "a dot without widths should use the base width"
+        );
+    }
+
+    #[test]
+    fn stroke_with_mismatched_widths_does_not_panic() {
+        let points = [
+            Pos2::new(0.0, 0.0),
+            Pos2::new(10.0, 0.0),
+            Pos2::new(20.0, 0.0),
+        ];
+        assert_eq!(
+            stroke(&points, &[]).shapes(Color32::BLACK).len(),
+            1,
+            "a stroke without widths should draw with the base width"
+        );
+        assert_eq!(
+            stroke(&points, &[1.0, 3.0]).shapes(Color32::BLACK).len(),
+            1,
+            "fewer widths than points should be resampled"
+        );
+    }
+
+    #[test]
+    fn fps_counter_averages_over_window() {
+        let mut counter = FpsCounter::new();
+        counter.record_frame_time(0.01);
+        let fps = counter.record_frame_time(0.03);
+        assert!(
+            (fps - 50.0).abs() < 0.01,
+            "average of 10ms and 30ms frames should be 50 fps"
+        );
+        assert!(
+            (counter.frame_time_ms - 30.0).abs() < 0.01,
+            "frame time should show the latest frame"
+        );
+    }
+
+    #[test]
+    fn fps_counter_evicts_oldest_frames() {
+        let mut counter = FpsCounter::new();
+        // 一帧很慢的帧之后接满窗口的快帧，慢帧应被移出窗口
+        counter.record_frame_time(0.5);
+        for _ in 0..FpsCounter::WINDOW - 1 {
+            counter.record_frame_time(0.01);
+        }
+        assert_eq!(
+            counter.frame_times.len(),
+            FpsCounter::WINDOW,
+            "window should be full"
+        );
+        assert!(
+            counter.current_fps < 90.0,
+            "slow frame should still count while in the window"
+        );
+
+        let fps = counter.record_frame_time(0.01);
+        assert_eq!(
+            counter.frame_times.len(),
+            FpsCounter::WINDOW,
+            "window should not grow past its size"
+        );
+        assert!(
+            (fps - 100.0).abs() < 0.01,
+            "slow frame should be evicted from the average"
+        );
+    }
+
+    #[test]
+    fn fps_counter_restarts_after_gap() {
+        let mut counter = FpsCounter::new();
+        counter.record_frame_time(0.02);
+        let fps = counter.record_frame_time(FpsCounter::RESET_GAP + 1.0);
+        assert!(
+            counter.frame_times.is_empty(),
+            "a long gap should clear the window"
+        );
+        assert!(
+            (fps - 50.0).abs() < 0.01,
+            "a long gap should keep the last reading"
+        );
+    }
+}
@@ -0,0 +1,298 @@
+// SVG 导出：将画布对象转换为矢量图，便于存档或在 Inkscape 等软件中继续编辑
+// - 笔画导出为 <polyline>，线宽取各点宽度的平均值
+// - 形状导出为 <line>/<polygon>/<ellipse>，旋转通过 transform 保留（多边形、星形与填充区域的顶点已包含旋转）
+// - 图片以 base64 编码的 PNG 数据内嵌
+// 颜色的不透明度写入 stroke-opacity / fill-opacity，画布背景不包含在导出内容中
+
+use std::fmt::Write as _;
+
+use egui::{Color32, Pos2, Rect};
+
+use crate::state::{
+    CanvasImage, CanvasObject, CanvasShape, CanvasShapeType, CanvasStroke, CanvasText, TextAlign,
+    TextFont,
+};
+use crate::utils::AppUtils;
+
+// 导出内容周围留出的空白（像素）
+const SVG_PADDING: f32 = 10.0;
+
+// 文本行高相对字号的比例
+const TEXT_LINE_HEIGHT: f32 = 1.2;
+
+pub fn to_svg(objects: &[CanvasObject]) -> String {
+    let bounds = objects
+        .iter()
+        .map(object_bounds)
+        .reduce(Rect::union)
+        .unwrap_or(Rect::from_min_size(Pos2::ZERO, egui::vec2(1.0, 1.0)))
+        .expand(SVG_PADDING);
+
+    let mut svg = String::new();
+    _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}" width="{}" height="{}">"#,
+        bounds.min.x,
+        bounds.min.y,
+        bounds.width(),
+        bounds.height(),
+        bounds.width(),
+        bounds.height()
+    );
+    for (index, object) in objects.iter().enumerate() {
+        match object {
+            CanvasObject::Stroke(stroke) => write_stroke(&mut svg, stroke),
+            CanvasObject::Shape(shape) => write_shape(&mut svg, shape),
+            CanvasObject::Text(text) => write_text(&mut svg, text),
+            CanvasObject::Image(img) => write_image(&mut svg, img, index),
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+// 对象的大致边界（文本按字号估算，用于确定 SVG 画布范围）
+fn object_bounds(object: &CanvasObject) -> Rect {
+    match object {
+        CanvasObject::Stroke(stroke) => {
+            AppUtils::calculate_stroke_bounding_box(stroke).expand(stroke.base_width / 2.0)
+        }
+        CanvasObject::Shape(shape) => AppUtils::calculate_shape_bounding_box(shape),
+        CanvasObject::Image(img) => Rect::from_min_size(img.pos, img.size),
+        CanvasObject::Text(text) => {
+            let rows = text.text.lines().count().max(1);
+            Rect::from_min_size(
+                text.pos,
+                egui::vec2(
+                    text_width(text),
+                    rows as f32 * text.font_size * TEXT_LINE_HEIGHT,
+                ),
+            )
+        }
+    }
+}
+
+// 文本框宽度：设置了段落宽度时使用该宽度，否则按最长一行的字数估算
+fn text_width(text: &CanvasText) -> f32 {
+    text.max_width.unwrap_or_else(|| {
+        let columns = text.text.lines().map(|line| line.chars().count()).max();
+        columns.unwrap_or(0) as f32 * text.font_size
+    })
+}
+
+// 颜色的十六进制 RGB 值与不透明度
+fn color_attrs(color: Color32) -> (String, f32) {
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    (format!("#{r:02x}{g:02x}{b:02x}"), f32::from(a) / 255.0)
+}
+
+fn stroke_attrs(color: Color32, width: f32) -> String {
+    let (hex, opacity) = color_attrs(color);
+    format!(r#"stroke="{hex}" stroke-opacity="{opacity}" stroke-width="{width}""#)
+}
+
+fn fill_attrs(color: Color32) -> String {
+    let (hex, opacity) = color_attrs(color);
+    format!(r#"fill="{hex}" fill-opacity="{opacity}""#)
+}
+
+fn points_attr(points: &[Pos2]) -> String {
+    points
+        .iter()
+        .map(|p| format!("{},{}", p.x, p.y))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn write_stroke(svg: &mut String, stroke: &CanvasStroke) {
+    let width = if stroke.widths.is_empty() {
+        stroke.base_width
+    } else {
+        stroke.widths.iter().sum::<f32>() / stroke.widths.len() as f32
+    };
+
+    // 单点笔画绘制为圆点
+    if let [point] = stroke.points.as_slice() {
+        _ = writeln!(
+            svg,
+            r#"  <circle cx="{}" cy="{}" r="{}" {}/>"#,
+            point.x,
+            point.y,
+            width / 2.0,
+            fill_attrs(stroke.color)
+        );
+        return;
+    }
+
+    _ = writeln!(
+        svg,
+        r#"  <polyline points="{}" fill="none" {} stroke-linecap="round" stroke-linejoin="round"/>"#,
+        points_attr(&stroke.points),
+        stroke_attrs(stroke.color, width)
+    );
+}
+
+fn write_shape(svg: &mut String, shape: &CanvasShape) {
+    let transform = if shape.rotation == 0.0 {
+        String::new()
+    } else {
+        let center = AppUtils::shape_center(shape);
+        format!(
+            r#" transform="rotate({} {} {})""#,
+            shape.rotation.to_degrees(),
+            center.x,
+            center.y
+        )
+    };
+    let stroke = stroke_attrs(shape.color, shape.stroke_width);
+    let fill = shape
+        .fill
+        .map_or_else(|| String::from(r#"fill="none""#), fill_attrs);
+    let (x, y, size) = (shape.pos.x, shape.pos.y, shape.size);
+
+    match shape.shape_type {
+        CanvasShapeType::Line => {
+            _ = writeln!(
+                svg,
+                r#"  <line x1="{x}" y1="{y}" x2="{}" y2="{y}" {stroke}{transform}/>"#,
+                x + size
+            );
+        }
+        CanvasShapeType::Arrow => {
+            // 箭头使用与画布相同的轮廓（已包含旋转）
+            _ = writeln!(svg, "  <g>");
+            for polyline in AppUtils::shape_outline(shape) {
+                _ = writeln!(
+                    svg,
+                    r#"    <polyline points="{}" fill="none" {stroke}/>"#,
+                    points_attr(&polyline)
+                );
+            }
+            _ = writeln!(svg, "  </g>");
+        }
+        CanvasShapeType::Rectangle => {
+            let [width, height] = shape.rect_size().into();
+            let radius = shape.corner_radius.clamp(0.0, width.min(height) / 2.0);
+            _ = writeln!(
+                svg,
+                r#"  <rect x="{x}" y="{y}" width="{width}" height="{height}" rx="{radius}" {fill} {stroke}{transform}/>"#
+            );
+        }
+        CanvasShapeType::Triangle => {
+            let corners = [
+                Pos2::new(x, y),
+                Pos2::new(x + size, y),
+                Pos2::new(x + size / 2.0, y + size / 2.0),
+            ];
+            _ = writeln!(
+                svg,
+                r#"  <polygon points="{}" {fill} {stroke}{transform}/>"#,
+                points_attr(&corners)
+            );
+        }
+        CanvasShapeType::Circle => {
+            let [rx, ry] = (shape.rect_size() / 2.0).into();
+            _ = writeln!(
+                svg,
+                r#"  <ellipse cx="{x}" cy="{y}" rx="{rx}" ry="{ry}" {fill} {stroke}{transform}/>"#
+            );
+        }
+        CanvasShapeType::Polygon { .. }
+        | CanvasShapeType::Star { .. }
+        | CanvasShapeType::Region => {
+            // 顶点使用与画布相同的轮廓（已包含旋转）
+            for mut polyline in AppUtils::shape_outline(shape) {
+                polyline.pop();
+                _ = writeln!(
+                    svg,
+                    r#"  <polygon points="{}" {fill} {stroke}/>"#,
+                    points_attr(&polyline)
+                );
+            }
+        }
+    }
+}
+
+// SVG 文本不会自动换行，只保留原有的分行与对齐方式
+fn write_text(svg: &mut String, text: &CanvasText) {
+    let (anchor, x) = match text.align {
+        TextAlign::Left => ("start", text.pos.x),
+        TextAlign::Center => ("middle", text.pos.x + text_width(text) / 2.0),
+        TextAlign::Right => ("end", text.pos.x + text_width(text)),
+    };
+    let family = match text.font {
+        TextFont::Monospace => "monospace",
+        TextFont::Cjk | TextFont::Default => "sans-serif",
+    };
+    let weight = if text.bold { "bold" } else { "normal" };
+    let style = if text.italic { "italic" } else { "normal" };
+    _ = write!(
+        svg,
+        r#"  <text x="{x}" y="{}" font-size="{}" font-family="{family}" font-weight="{weight}" font-style="{style}" text-anchor="{anchor}" dominant-baseline="hanging" {}>"#,
+        text.pos.y,
+        text.font_size,
+        fill_attrs(text.color)
+    );
+    for (i, line) in text.text.lines().enumerate() {
+        let dy = if i == 0 {
+            0.0
+        } else {
+            text.font_size * TEXT_LINE_HEIGHT
+        };
+        _ = write!(
+            svg,
+            r#"<tspan x="{x}" dy="{dy}">{}</tspan>"#,
+            escape_xml(line)
+        );
+    }
+    svg.push_str("</text>\n");
+}
+
+fn write_image(svg: &mut String, img: &CanvasImage, index: usize) {
+    let data = match AppUtils::png_base64(&img.displayed_pixels()) {
+        Ok(data) => data,
+        Err(err) => {
+            log::warn!("Skipping image that could not be encoded: {err}");
+            return;
+        }
+    };
+
+    let (x, y, width, height) = (img.pos.x, img.pos.y, img.size.x, img.size.y);
+    let radius = img.corner_radius;
+    let clip = if radius > 0.0 {
+        let id = format!("image-clip-{index}");
+        _ = writeln!(
+            svg,
+            r#"  <clipPath id="{id}"><rect x="{x}" y="{y}" width="{width}" height="{height}" rx="{radius}"/></clipPath>"#
+        );
+        format!(r#" clip-path="url(#{id})""#)
+    } else {
+        String::new()
+    };
+    _ = writeln!(
+        svg,
+        r#"  <image x="{x}" y="{y}" width="{width}" height="{height}" preserveAspectRatio="none" href="data:image/png;base64,{data}"{clip}/>"#
+    );
+
+    if img.border_width > 0.0 {
+        // 边框绘制在图片内侧，与画布上的显示一致
+        let half = img.border_width / 2.0;
+        _ = writeln!(
+            svg,
+            r#"  <rect x="{}" y="{}" width="{}" height="{}" rx="{}" fill="none" {}/>"#,
+            x + half,
+            y + half,
+            (width - img.border_width).max(0.0),
+            (height - img.border_width).max(0.0),
+            (radius - half).max(0.0),
+            stroke_attrs(img.border_color, img.border_width)
+        );
+    }
+}
@@ -1,6 +1,8 @@
-use egui::{Color32, Painter, Pos2, Stroke};
+use base64::prelude::{BASE64_STANDARD, Engine as _};
+use egui::{Color32, Painter, Pos2, Rect, Stroke, Vec2};
 
-use crate::state::ResizeAnchor;
+use crate::board::TextMeasure as _;
+use crate::state::{CanvasTool, RadialMenuItem, ResizeAnchor};
 
 pub struct AppUtils;
 
@@ -12,53 +14,294 @@ impl AppUtils {
         eraser_size: f32,
     ) -> bool {
         let eraser_radius = eraser_size / 2.0;
-        for i in 0..stroke.points.len() - 1 {
-            let p1 = stroke.points[i];
-            let p2 = stroke.points[i + 1];
-            let stroke_width = if i < stroke.widths.len() {
-                stroke.widths[i].max(
-                    stroke
-                        .widths
-                        .get(i + 1)
-                        .copied()
-                        .unwrap_or(stroke.widths[i]),
-                )
-            } else {
-                stroke.widths[0]
-            };
 
-            // 计算点到线段的距离
-            let dist = Self::point_to_line_segment_distance(pos, p1, p2);
-            if dist <= eraser_radius + stroke_width / 2.0 {
-                return true;
+        match stroke.points.as_slice() {
+            [] => false,
+            // 单点笔画（圆点）
+            [point] => {
+                point.distance(pos) <= eraser_radius + Self::stroke_width_at(stroke, 0) / 2.0
+            }
+            points => points
+                .windows(2)
+                .enumerate()
+                .any(|(i, segment)| match segment {
+                    &[p1, p2] => Self::segment_hit(
+                        pos,
+                        eraser_radius,
+                        (p1, Self::stroke_width_at(stroke, i)),
+                        (p2, Self::stroke_width_at(stroke, i + 1)),
+                    ),
+                    _ => false,
+                }),
+        }
+    }
+
+    // 笔画第 i 个点处的宽度（缺少宽度数据时使用最后一个宽度或基础宽度）
+    pub fn stroke_width_at(stroke: &crate::state::CanvasStroke, i: usize) -> f32 {
+        stroke
+            .widths
+            .get(i)
+            .or(stroke.widths.last())
+            .copied()
+            .unwrap_or(stroke.base_width)
+    }
+
+    // 判断半径为 radius 的圆是否与一段笔画相交，两端宽度不同时沿线段线性插值宽度
+    pub fn segment_hit(pos: Pos2, radius: f32, start: (Pos2, f32), end: (Pos2, f32)) -> bool {
+        let ((a, width_a), (b, width_b)) = (start, end);
+        let t = Self::segment_projection(pos, a, b);
+        let width = width_a + (width_b - width_a) * t;
+        pos.distance(a.lerp(b, t)) <= radius + width / 2.0
+    }
+
+    // 按工具范围调整笔画的局部宽度（用于粗细调整工具），返回是否有点被修改
+    // factor > 1.0 加粗，factor < 1.0 变细，越靠近工具中心影响越大；调整后的宽度限制在 min_width..=max_width
+    pub fn adjust_stroke_widths(
+        stroke: &mut crate::state::CanvasStroke,
+        pos: Pos2,
+        tool_size: f32,
+        factor: f32,
+        min_width: f32,
+        max_width: f32,
+    ) -> bool {
+        let max_width = max_width.max(min_width);
+        let radius = tool_size / 2.0;
+        if radius <= 0.0 {
+            return false;
+        }
+
+        let mut changed = false;
+        for (point, width) in stroke.points.iter().zip(stroke.widths.iter_mut()) {
+            let dist = point.distance(pos);
+            if dist > radius {
+                continue;
+            }
+
+            let falloff = 1.0 - dist / radius;
+            let scale = 1.0 + (factor - 1.0) * falloff;
+            let new_width = (*width * scale).clamp(min_width, max_width);
+            if (new_width - *width).abs() > f32::EPSILON {
+                *width = new_width;
+                changed = true;
             }
         }
-        false
+        if changed {
+            stroke.invalidate_cache();
+        }
+        changed
     }
 
     // 计算点到线段的最短距离
     pub fn point_to_line_segment_distance(p: Pos2, a: Pos2, b: Pos2) -> f32 {
-        let ab = Pos2::new(b.x - a.x, b.y - a.y);
-        let ap = Pos2::new(p.x - a.x, p.y - a.y);
-        let ab_sq = ab.x * ab.x + ab.y * ab.y;
+        p.distance(a.lerp(b, Self::segment_projection(p, a, b)))
+    }
 
+    // 点在线段上的投影位置（0.0 为起点，1.0 为终点）
+    fn segment_projection(p: Pos2, a: Pos2, b: Pos2) -> f32 {
+        let ab = b - a;
+        let ab_sq = ab.length_sq();
         if ab_sq < 0.0001 {
             // a 和 b 几乎重合
-            return (p.x - a.x).hypot(p.y - a.y);
+            return 0.0;
+        }
+        ((p - a).dot(ab) / ab_sq).clamp(0.0, 1.0)
+    }
+
+    // 折线各点偏离首尾连线不超过 threshold 时，返回拉直后的首尾两点
+    // 阈值不是固定容差，而是画笔设置中“自动拉直”旁的阈值滑块（AppState::straighten_threshold）
+    pub fn maybe_straighten(points: &[Pos2], threshold: f32) -> Option<[Pos2; 2]> {
+        match points {
+            [first, .., last] if Self::max_deviation_from_chord(points) <= threshold => {
+                Some([*first, *last])
+            }
+            _ => None,
+        }
+    }
+
+    // 计算折线上各点到首尾连线的最大垂直距离（用于判断笔画是否接近直线）
+    pub fn max_deviation_from_chord(points: &[Pos2]) -> f32 {
+        let (Some(first), Some(last)) = (points.first(), points.last()) else {
+            return 0.0;
+        };
+
+        points
+            .iter()
+            .map(|p| Self::point_to_line_segment_distance(*p, *first, *last))
+            .fold(0.0, f32::max)
+    }
+
+    // 使用鞋带公式计算多边形面积（首尾无需重复）
+    pub fn polygon_area(points: &[Pos2]) -> f32 {
+        let Some(&last) = points.last() else {
+            return 0.0;
+        };
+
+        let mut prev = last;
+        let mut twice_area = 0.0;
+        for &p in points {
+            twice_area += prev.x * p.y - p.x * prev.y;
+            prev = p;
+        }
+        twice_area.abs() / 2.0
+    }
+
+    // 射线法判断点是否在多边形内
+    pub fn point_in_polygon(pos: Pos2, points: &[Pos2]) -> bool {
+        let Some(&last) = points.last() else {
+            return false;
+        };
+
+        let mut inside = false;
+        let mut prev = last;
+        for &p in points {
+            if (p.y > pos.y) != (prev.y > pos.y)
+                && pos.x < (prev.x - p.x) * (pos.y - p.y) / (prev.y - p.y) + p.x
+            {
+                inside = !inside;
+            }
+            prev = p;
+        }
+        inside
+    }
+
+    // 获取对象围成的封闭多边形：闭合的笔画（首尾足够接近时自动闭合）或封闭形状
+    pub fn closed_object_polygon(object: &crate::state::CanvasObject) -> Option<Vec<Pos2>> {
+        match object {
+            crate::state::CanvasObject::Stroke(stroke) => {
+                let (first, last) = (stroke.points.first()?, stroke.points.last()?);
+                let length = Self::calculate_path_length(&stroke.points);
+                // 首尾距离不超过 20 像素或总长度的 10% 时视为闭合
+                let close_threshold = (length * 0.1).max(20.0);
+                (stroke.points.len() >= 3 && first.distance(*last) <= close_threshold)
+                    .then(|| stroke.points.clone())
+            }
+            crate::state::CanvasObject::Shape(shape) => match shape.shape_type {
+                crate::state::CanvasShapeType::Rectangle
+                | crate::state::CanvasShapeType::Triangle
+                | crate::state::CanvasShapeType::Circle
+                | crate::state::CanvasShapeType::Polygon { .. }
+                | crate::state::CanvasShapeType::Star { .. }
+                | crate::state::CanvasShapeType::Region => {
+                    Self::shape_outline(shape).into_iter().next()
+                }
+                crate::state::CanvasShapeType::Line | crate::state::CanvasShapeType::Arrow => None,
+            },
+            crate::state::CanvasObject::Image(_) | crate::state::CanvasObject::Text(_) => None,
+        }
+    }
+
+    // 识别手绘笔画对应的形状，无法可靠识别时返回 None
+    // - 未闭合的笔画偏离首尾连线不超过总长度的 5% 时为直线
+    // - 闭合的笔画圆度（4πA/P²）足够高时为圆形，否则按简化后折线的拐角数判断三角形或矩形
+    pub fn recognize_shape(points: &[Pos2]) -> Option<crate::state::CanvasShapeType> {
+        use crate::state::CanvasShapeType;
+        const MIN_LENGTH: f32 = 30.0;
+        const LINE_DEVIATION_RATIO: f32 = 0.05;
+        const MIN_CIRCULARITY: f32 = 0.85;
+        const SIMPLIFY_RATIO: f32 = 0.04;
+        const CORNER_ANGLE: f32 = 35.0_f32.to_radians();
+
+        let (&first, &last) = (points.first()?, points.last()?);
+        let length = Self::calculate_path_length(points);
+        if points.len() < 3 || length < MIN_LENGTH {
+            return None;
+        }
+
+        let gap = first.distance(last);
+        if gap > (length * 0.15).max(20.0) {
+            return (Self::max_deviation_from_chord(points) <= length * LINE_DEVIATION_RATIO)
+                .then_some(CanvasShapeType::Line);
         }
 
-        let t = ((ap.x * ab.x + ap.y * ab.y) / ab_sq).max(0.0).min(1.0);
-        let closest = Pos2::new(a.x + t * ab.x, a.y + t * ab.y);
-        (p.x - closest.x).hypot(p.y - closest.y)
+        let perimeter = length + gap;
+        let circularity =
+            4.0 * std::f32::consts::PI * Self::polygon_area(points) / (perimeter * perimeter);
+        if circularity >= MIN_CIRCULARITY {
+            return Some(CanvasShapeType::Circle);
+        }
+
+        // 简化闭合后的折线，去掉与起点重合的终点，统计转角足够大的顶点
+        let mut closed = points.to_vec();
+        closed.push(first);
+        let mut vertices: Vec<Pos2> = Self::simplify_stroke(&closed, perimeter * SIMPLIFY_RATIO)
+            .into_iter()
+            .filter_map(|i| closed.get(i).copied())
+            .collect();
+        vertices.pop();
+        if vertices.len() < 3 {
+            return None;
+        }
+        let corners = vertices
+            .iter()
+            .zip(vertices.iter().cycle().skip(vertices.len() - 1))
+            .zip(vertices.iter().cycle().skip(1))
+            .filter(|((vertex, prev), next)| {
+                let incoming = (**vertex - **prev).normalized();
+                let outgoing = (**next - **vertex).normalized();
+                incoming.dot(outgoing).clamp(-1.0, 1.0).acos() >= CORNER_ANGLE
+            })
+            .count();
+
+        match corners {
+            3 => Some(CanvasShapeType::Triangle),
+            4 => Some(CanvasShapeType::Rectangle),
+            _ => None,
+        }
+    }
+
+    // Ramer-Douglas-Peucker 折线简化：返回保留的点的索引（始终保留首尾两点），
+    // 被删除的点偏离简化后的线段不超过 epsilon；epsilon 为 0 时不简化，保留所有点
+    pub fn simplify_stroke(points: &[Pos2], epsilon: f32) -> Vec<usize> {
+        if points.len() < 3 || epsilon <= 0.0 {
+            return (0..points.len()).collect();
+        }
+
+        let last = points.len() - 1;
+        let mut keep = vec![false; points.len()];
+        let mut ranges = vec![(0, last)];
+        while let Some((start, end)) = ranges.pop() {
+            let (Some(&a), Some(&b)) = (points.get(start), points.get(end)) else {
+                continue;
+            };
+            let farthest = points
+                .iter()
+                .enumerate()
+                .take(end)
+                .skip(start + 1)
+                .map(|(i, p)| (i, Self::point_to_line_segment_distance(*p, a, b)))
+                .max_by(|x, y| x.1.total_cmp(&y.1));
+            if let Some((index, distance)) = farthest
+                && distance > epsilon
+            {
+                ranges.push((start, index));
+                ranges.push((index, end));
+            } else {
+                for i in [start, end] {
+                    if let Some(flag) = keep.get_mut(i) {
+                        *flag = true;
+                    }
+                }
+            }
+        }
+
+        keep.iter()
+            .enumerate()
+            .filter(|(_, kept)| **kept)
+            .map(|(i, _)| i)
+            .collect()
     }
 
     // 计算动态画笔宽度
+    // 动态画笔宽度相对基础宽度的变化范围（笔锋与最轻的压力最小 40%，速度模式最大 150%）
+    pub const DYNAMIC_WIDTH_RANGE: std::ops::RangeInclusive<f32> = 0.4..=1.5;
+
     pub fn calculate_dynamic_width(
         base_width: f32,
         mode: crate::state::DynamicBrushWidthMode,
         point_index: usize,
         total_points: usize,
         speed: Option<f32>,
+        force: Option<f32>,
     ) -> f32 {
         match mode {
             crate::state::DynamicBrushWidthMode::Disabled => base_width,
@@ -87,15 +330,38 @@ impl AppUtils {
                     base_width
                 }
             }
+
+            crate::state::DynamicBrushWidthMode::PressureBased => {
+                // 基于压感：压力越大越粗，从 40% 到 100%；输入未报告压力时使用基础宽度
+                let min_scale = *Self::DYNAMIC_WIDTH_RANGE.start();
+                force.map_or(base_width, |force| {
+                    base_width * (min_scale + (1.0 - min_scale) * force.clamp(0.0, 1.0))
+                })
+            }
         }
     }
 
+    // 计算动态橡皮擦大小：移动越快越大，越慢越小
+    pub fn calculate_dynamic_eraser_size(
+        base_size: f32,
+        speed: f32,
+        min_scale: f32,
+        max_scale: f32,
+    ) -> f32 {
+        // 速度范围假设：0-1500 像素/秒
+        let normalized_speed = (speed / 1500.0).clamp(0.0, 1.0);
+        base_size * (min_scale + (max_scale - min_scale) * normalized_speed)
+    }
+
     // 插值算法 - 在点之间插入中间点
     pub fn apply_point_interpolation(
         points: &[Pos2],
         widths: &[f32],
         frequency: f32,
     ) -> (Vec<Pos2>, Vec<f32>) {
+        let Some(&last_width) = widths.last() else {
+            return (points.to_vec(), widths.to_vec());
+        };
         if points.len() < 2 || frequency <= 0.0 {
             return (points.to_vec(), widths.to_vec());
         }
@@ -103,19 +369,12 @@ impl AppUtils {
         let mut interpolated_points = Vec::new();
         let mut interpolated_widths = Vec::new();
 
-        for i in 0..points.len() - 1 {
-            let p1 = points[i];
-            let p2 = points[i + 1];
-            let width1 = if i < widths.len() {
-                widths[i]
-            } else {
-                widths[0]
-            };
-            let width2 = if i + 1 < widths.len() {
-                widths[i + 1]
-            } else {
-                widths[widths.len() - 1]
+        for (i, segment) in points.windows(2).enumerate() {
+            let &[p1, p2] = segment else {
+                continue;
             };
+            let width1 = widths.get(i).copied().unwrap_or(last_width);
+            let width2 = widths.get(i + 1).copied().unwrap_or(last_width);
 
             // 添加第一个点
             interpolated_points.push(p1);
@@ -149,7 +408,7 @@ impl AppUtils {
     }
 
     // 笔画平滑算法 - 使用移动平均和曲线拟合来减少抖动
-    pub fn apply_stroke_smoothing(points: &[Pos2]) -> Vec<Pos2> {
+    pub fn apply_stroke_smoothing(points: &[Pos2], iterations: usize) -> Vec<Pos2> {
         if points.len() < 3 {
             return points.to_vec();
         }
@@ -187,8 +446,7 @@ impl AppUtils {
         // --------------------------------
         let mut smoothed = resampled;
 
-        let iterations = 2; // 2–3 recommended for real-time strokes
-
+        // 2–3 iterations recommended for real-time strokes
         for _ in 0..iterations {
             let mut next = Vec::with_capacity(smoothed.len() * 2);
             next.push(smoothed[0]);
@@ -229,123 +487,1814 @@ impl AppUtils {
         final_points
     }
 
+    // 将变宽笔画构建为填充的带状网格：每段按两端的半宽偏移中心线得到梯形，
+    // 转折处的外侧与笔画两端以圆弧填补，使笔锋的粗细变化连续、没有接缝；边缘羽化一个像素以抗锯齿
+    pub fn build_stroke_mesh(points: &[Pos2], widths: &[f32], color: Color32) -> egui::Mesh {
+        use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+        let mut mesh = egui::Mesh::default();
+
+        // 去掉重合的点，避免出现长度为零的线段
+        let mut nodes: Vec<(Pos2, f32)> = Vec::with_capacity(points.len());
+        for (point, width) in points.iter().zip(widths) {
+            if nodes
+                .last()
+                .is_none_or(|(last, _)| last.distance(*point) > 1e-3)
+            {
+                nodes.push((*point, width / 2.0));
+            }
+        }
+
+        let directions: Vec<f32> = nodes
+            .windows(2)
+            .filter_map(|segment| match segment {
+                [(a, _), (b, _)] => Some((*b - *a).angle()),
+                _ => None,
+            })
+            .collect();
+
+        let (Some(&(first, first_radius)), Some(&(last, last_radius))) =
+            (nodes.first(), nodes.last())
+        else {
+            return mesh;
+        };
+        let (Some(&first_angle), Some(&last_angle)) = (directions.first(), directions.last())
+        else {
+            // 只有一个点：绘制为圆点
+            Self::add_stroke_arc(&mut mesh, first, first_radius, 0.0, TAU, color);
+            return mesh;
+        };
+
+        // 两端的半圆笔帽
+        Self::add_stroke_arc(
+            &mut mesh,
+            first,
+            first_radius,
+            first_angle + FRAC_PI_2,
+            PI,
+            color,
+        );
+        Self::add_stroke_arc(
+            &mut mesh,
+            last,
+            last_radius,
+            last_angle - FRAC_PI_2,
+            PI,
+            color,
+        );
+
+        for (segment, angle) in nodes.windows(2).zip(&directions) {
+            if let [(a, start_radius), (b, end_radius)] = segment {
+                Self::add_stroke_quad(
+                    &mut mesh,
+                    (*a, *start_radius),
+                    (*b, *end_radius),
+                    Vec2::angled(angle + FRAC_PI_2),
+                    color,
+                );
+            }
+        }
+
+        // 转折处：在外侧补一段圆弧（内侧由相邻两段的重叠覆盖）
+        for (turn, &(center, radius)) in directions.windows(2).zip(nodes.iter().skip(1)) {
+            if let [incoming, outgoing] = turn {
+                let sweep = (outgoing - incoming + PI).rem_euclid(TAU) - PI;
+                if sweep.abs() > 1e-3 {
+                    let start = incoming - sweep.signum() * FRAC_PI_2;
+                    Self::add_stroke_arc(&mut mesh, center, radius, start, sweep, color);
+                }
+            }
+        }
+
+        mesh
+    }
+
+    // 笔画网格边缘的羽化宽度
+    const STROKE_FEATHER: f32 = 1.0;
+
+    // 宽度超过该值的等宽笔画也绘制为网格：egui 的折线两端为平头，宽线在急转处会出现尖角
+    pub const WIDE_STROKE_WIDTH: f32 = 8.0;
+
+    // 向网格添加一个顶点，返回其索引
+    fn add_stroke_vertex(mesh: &mut egui::Mesh, pos: Pos2, color: Color32) -> u32 {
+        let index = u32::try_from(mesh.vertices.len()).unwrap_or(u32::MAX);
+        mesh.colored_vertex(pos, color);
+        index
+    }
+
+    // 笔画的一段：中心线两侧各偏移对应端点的半宽，外缘羽化
+    fn add_stroke_quad(
+        mesh: &mut egui::Mesh,
+        (start, start_radius): (Pos2, f32),
+        (end, end_radius): (Pos2, f32),
+        normal: Vec2,
+        color: Color32,
+    ) {
+        let half_feather = Self::STROKE_FEATHER / 2.0;
+        let mut cross_section = |center: Pos2, radius: f32| {
+            let inner = (radius - half_feather).max(0.0);
+            let outer = radius + half_feather;
+            [
+                Self::add_stroke_vertex(mesh, center + normal * outer, Color32::TRANSPARENT),
+                Self::add_stroke_vertex(mesh, center + normal * inner, color),
+                Self::add_stroke_vertex(mesh, center - normal * inner, color),
+                Self::add_stroke_vertex(mesh, center - normal * outer, Color32::TRANSPARENT),
+            ]
+        };
+        let from = cross_section(start, start_radius);
+        let to = cross_section(end, end_radius);
+        for (a, b) in from.windows(2).zip(to.windows(2)) {
+            if let ([a0, a1], [b0, b1]) = (a, b) {
+                mesh.add_triangle(*a0, *a1, *b1);
+                mesh.add_triangle(*a0, *b1, *b0);
+            }
+        }
+    }
+
+    // 以 center 为圆心、从 start 角度扫过 sweep 的扇形（用于笔帽与转折处），外缘羽化
+    fn add_stroke_arc(
+        mesh: &mut egui::Mesh,
+        center: Pos2,
+        radius: f32,
+        start: f32,
+        sweep: f32,
+        color: Color32,
+    ) {
+        // 相邻顶点之间的最大角度，以及圆弧与弦之间允许的最大偏差（像素），半径越大分段越多
+        const MAX_ARC_STEP: f32 = std::f32::consts::PI / 8.0;
+        const ARC_TOLERANCE: f32 = 0.25;
+
+        let half_feather = Self::STROKE_FEATHER / 2.0;
+        let inner = (radius - half_feather).max(0.0);
+        let outer = radius + half_feather;
+        let arc_step = if outer > ARC_TOLERANCE {
+            (2.0 * (1.0 - ARC_TOLERANCE / outer).acos()).min(MAX_ARC_STEP)
+        } else {
+            MAX_ARC_STEP
+        };
+        let steps = (sweep.abs() / arc_step).ceil().max(1.0) as usize;
+
+        let center_index = Self::add_stroke_vertex(mesh, center, color);
+        let mut previous: Option<(u32, u32)> = None;
+        for step in 0..=steps {
+            let direction = Vec2::angled(start + sweep * step as f32 / steps as f32);
+            let inner_index = Self::add_stroke_vertex(mesh, center + direction * inner, color);
+            let outer_index =
+                Self::add_stroke_vertex(mesh, center + direction * outer, Color32::TRANSPARENT);
+            if let Some((previous_inner, previous_outer)) = previous {
+                mesh.add_triangle(center_index, previous_inner, inner_index);
+                mesh.add_triangle(previous_inner, previous_outer, outer_index);
+                mesh.add_triangle(previous_inner, outer_index, inner_index);
+            }
+            previous = Some((inner_index, outer_index));
+        }
+    }
+
+    // 仅对末尾若干个点做三点平均，用于绘制中笔画的实时显示；首尾点保持不变，点数不变
+    pub fn smooth_trailing_points(points: &[Pos2], window: usize) -> Vec<Pos2> {
+        let mut smoothed = points.to_vec();
+        if points.len() < 3 {
+            return smoothed;
+        }
+
+        let start = points.len().saturating_sub(window + 1).max(1);
+        for i in start..points.len() - 1 {
+            if let (Some(&prev), Some(&curr), Some(&next)) =
+                (smoothed.get(i - 1), points.get(i), points.get(i + 1))
+                && let Some(point) = smoothed.get_mut(i)
+            {
+                *point = Pos2::new(
+                    (prev.x + curr.x + next.x) / 3.0,
+                    (prev.y + curr.y + next.y) / 3.0,
+                );
+            }
+        }
+        smoothed
+    }
+
+    // 一次性删除多个索引处的元素：只移动一遍剩余元素，避免逐个 Vec::remove 反复搬移
+    pub fn remove_indices<T>(items: &mut Vec<T>, indices: &[usize]) {
+        let mut marked = vec![false; items.len()];
+        for &index in indices {
+            if let Some(flag) = marked.get_mut(index) {
+                *flag = true;
+            }
+        }
+        let mut marked = marked.into_iter();
+        items.retain(|_| !marked.next().unwrap_or(false));
+    }
+
+    // 压力测试数据：1000 条各 100 个点的斜线笔画，按 20 × 15 的网格排布（调试设置与基准测试共用）
+    pub fn stress_test_strokes() -> Vec<crate::state::CanvasStroke> {
+        const STROKE_COUNT: usize = 1000;
+        const POINTS_PER_STROKE: usize = 100;
+        const WIDTH: f32 = 3.0;
+
+        (0..STROKE_COUNT)
+            .map(|i| {
+                let start_x = (i % 20) as f32 * 50.0;
+                let start_y = (i / 20 % 15) as f32 * 50.0;
+                let points = (0..POINTS_PER_STROKE)
+                    .map(|j| Pos2::new(start_x + j as f32 * 10.0, start_y + j as f32 * 5.0))
+                    .collect();
+
+                crate::state::CanvasStroke {
+                    points,
+                    widths: vec![WIDTH; POINTS_PER_STROKE],
+                    forces: Vec::new(),
+                    color: Color32::from_rgb(255, 0, 0),
+                    base_width: WIDTH,
+                    follow_background: false,
+                    input_type: crate::state::PointerInputType::Pen,
+                    highlighter: false,
+                    fade: None,
+                    cache: Default::default(),
+                }
+            })
+            .collect()
+    }
+
+    // 将宽度序列按比例重采样到指定长度（用于点数变化后的笔画）
+    pub fn resample_widths(widths: &[f32], len: usize) -> Vec<f32> {
+        let Some(&last) = widths.last() else {
+            return Vec::new();
+        };
+        if len < 2 {
+            return vec![last; len];
+        }
+
+        let scale = (widths.len() - 1) as f32 / (len - 1) as f32;
+        (0..len)
+            .map(|i| {
+                let index = (i as f32 * scale).round() as usize;
+                widths.get(index).copied().unwrap_or(last)
+            })
+            .collect()
+    }
+
     // 计算形状的边界框（用于选择和碰撞检测）
     pub fn calculate_shape_bounding_box(shape: &crate::state::CanvasShape) -> egui::Rect {
+        // 边距随轮廓宽度增加，避免粗轮廓超出包围盒
+        let padding = shape.stroke_width / 2.0 + 4.0;
+        if shape.rotation != 0.0
+            && !matches!(
+                shape.shape_type,
+                crate::state::CanvasShapeType::Circle
+                    | crate::state::CanvasShapeType::Polygon { .. }
+                    | crate::state::CanvasShapeType::Star { .. }
+            )
+        {
+            let points: Vec<Pos2> = Self::shape_outline(shape).into_iter().flatten().collect();
+            return Rect::from_points(&points).expand(padding);
+        }
+
         match shape.shape_type {
             crate::state::CanvasShapeType::Line => {
                 let end_point = Pos2::new(shape.pos.x + shape.size, shape.pos.y);
-                let min_x = shape.pos.x.min(end_point.x) - 5.0;
-                let max_x = shape.pos.x.max(end_point.x) + 5.0;
-                let min_y = shape.pos.y.min(end_point.y) - 5.0;
-                let max_y = shape.pos.y.max(end_point.y) + 5.0;
+                let min_x = shape.pos.x.min(end_point.x) - padding;
+                let max_x = shape.pos.x.max(end_point.x) + padding;
+                let min_y = shape.pos.y.min(end_point.y) - padding;
+                let max_y = shape.pos.y.max(end_point.y) + padding;
                 egui::Rect::from_min_max(Pos2::new(min_x, min_y), Pos2::new(max_x, max_y))
             }
             crate::state::CanvasShapeType::Arrow => {
-                let end_point = Pos2::new(shape.pos.x + shape.size, shape.pos.y);
-                let min_x = shape.pos.x.min(end_point.x) - 5.0;
-                let max_x = shape.pos.x.max(end_point.x) + 5.0;
-                let min_y = shape.pos.y.min(end_point.y) - 15.0;
-                let max_y = shape.pos.y.max(end_point.y) + 15.0;
-                egui::Rect::from_min_max(Pos2::new(min_x, min_y), Pos2::new(max_x, max_y))
+                let points: Vec<Pos2> = Self::shape_outline(shape).into_iter().flatten().collect();
+                Rect::from_points(&points).expand(padding)
             }
             crate::state::CanvasShapeType::Rectangle => {
-                egui::Rect::from_min_size(shape.pos, egui::vec2(shape.size, shape.size))
+                // 矩形轮廓绘制在边框外侧
+                egui::Rect::from_min_size(shape.pos, shape.rect_size()).expand(shape.stroke_width)
+            }
+            crate::state::CanvasShapeType::Region => {
+                egui::Rect::from_min_size(shape.pos, shape.rect_size()).expand(padding)
             }
             crate::state::CanvasShapeType::Triangle => {
                 let half_size = shape.size / 2.0;
-                let min_x = shape.pos.x - 5.0;
-                let max_x = shape.pos.x + shape.size + 5.0;
-                let min_y = shape.pos.y - 5.0;
-                let max_y = shape.pos.y + half_size + 5.0;
+                let min_x = shape.pos.x - padding;
+                let max_x = shape.pos.x + shape.size + padding;
+                let min_y = shape.pos.y - padding;
+                let max_y = shape.pos.y + half_size + padding;
                 egui::Rect::from_min_max(Pos2::new(min_x, min_y), Pos2::new(max_x, max_y))
             }
             crate::state::CanvasShapeType::Circle => {
-                let radius = shape.size / 2.0;
-                egui::Rect::from_min_max(
-                    Pos2::new(shape.pos.x - radius - 5.0, shape.pos.y - radius - 5.0),
-                    Pos2::new(shape.pos.x + radius + 5.0, shape.pos.y + radius + 5.0),
-                )
+                // 旋转后椭圆的外接矩形：各方向的半宽为两条半轴在该方向投影的合成
+                let radii = shape.rect_size() / 2.0;
+                let (sin, cos) = shape.rotation.sin_cos();
+                let half_extent = egui::vec2(
+                    (radii.x * cos).hypot(radii.y * sin),
+                    (radii.x * sin).hypot(radii.y * cos),
+                );
+                Rect::from_center_size(shape.pos, half_extent * 2.0).expand(padding)
+            }
+            // 使用外接圆的范围，旋转时包围盒保持不变
+            crate::state::CanvasShapeType::Polygon { .. }
+            | crate::state::CanvasShapeType::Star { .. } => {
+                Rect::from_center_size(shape.pos, Vec2::splat(shape.size * 2.0)).expand(padding)
             }
         }
     }
 
-    pub fn draw_size_preview(painter: &Painter, pos: Pos2, size: f32) -> () {
-        const SIZE_PREVIEW_BORDER_WIDTH: f32 = 2.0;
-        let radius = size / SIZE_PREVIEW_BORDER_WIDTH;
-        painter.circle_filled(pos, radius, Color32::WHITE);
-        painter.circle_stroke(
-            pos,
-            radius,
-            Stroke::new(SIZE_PREVIEW_BORDER_WIDTH, Color32::BLACK),
+    // 以热力图形式绘制笔画各段宽度（相对基础宽度：蓝色较细，绿色相同，红色较粗）
+    pub fn draw_stroke_width_heatmap(painter: &Painter, stroke: &crate::state::CanvasStroke) {
+        let heat_color = |width: f32| {
+            let ratio = width / stroke.base_width.max(0.01);
+            // 将 0.4..1.6 映射到 -1..1
+            let t = ((ratio - 1.0) / 0.6).clamp(-1.0, 1.0);
+            if t < 0.0 {
+                Color32::from_rgb(0, 200, 0).lerp_to_gamma(Color32::BLUE, -t)
+            } else {
+                Color32::from_rgb(0, 200, 0).lerp_to_gamma(Color32::RED, t)
+            }
+        };
+
+        if let ([point], [width]) = (stroke.points.as_slice(), stroke.widths.as_slice()) {
+            painter.circle_filled(*point, width / 2.0, heat_color(*width));
+            return;
+        }
+
+        for (points, widths) in stroke.points.windows(2).zip(stroke.widths.windows(2)) {
+            if let ([p1, p2], [w1, w2]) = (points, widths) {
+                let width = f32::midpoint(*w1, *w2);
+                painter.line_segment([*p1, *p2], Stroke::new(width, heat_color(width)));
+            }
+        }
+    }
+
+    // 使用 Sobel 算子计算灰度图的边缘图，梯度幅值超过阈值的像素记为边缘
+    pub fn compute_edge_map(image: &image::GrayImage, threshold: f32) -> Vec<bool> {
+        let (width, height) = image.dimensions();
+        let mut edges = vec![false; (width * height) as usize];
+        if width < 3 || height < 3 {
+            return edges;
+        }
+
+        let luma = |x: u32, y: u32| f32::from(image.get_pixel(x, y).0[0]);
+        for y in 1..height - 1 {
+            for x in 1..width - 1 {
+                let gx = luma(x + 1, y - 1) + 2.0 * luma(x + 1, y) + luma(x + 1, y + 1)
+                    - luma(x - 1, y - 1)
+                    - 2.0 * luma(x - 1, y)
+                    - luma(x - 1, y + 1);
+                let gy = luma(x - 1, y + 1) + 2.0 * luma(x, y + 1) + luma(x + 1, y + 1)
+                    - luma(x - 1, y - 1)
+                    - 2.0 * luma(x, y - 1)
+                    - luma(x + 1, y - 1);
+                if let Some(edge) = edges.get_mut((y * width + x) as usize) {
+                    *edge = gx.hypot(gy) > threshold;
+                }
+            }
+        }
+        edges
+    }
+
+    // 两个颜色在 RGB 空间中的欧氏距离（忽略不透明度）
+    pub fn color_distance(a: Color32, b: Color32) -> f32 {
+        let [ar, ag, ab, _] = a.to_srgba_unmultiplied();
+        let [br, bg, bb, _] = b.to_srgba_unmultiplied();
+        let dr = f32::from(ar) - f32::from(br);
+        let dg = f32::from(ag) - f32::from(bg);
+        let db = f32::from(ab) - f32::from(bb);
+        (dr * dr + dg * dg + db * db).sqrt()
+    }
+
+    // 将图片编码为 PNG 并转换为 base64 字符串（用于保存画板与导出 SVG）
+    pub fn png_base64(image: &image::RgbaImage) -> Result<String, String> {
+        let mut png = std::io::Cursor::new(Vec::new());
+        image
+            .write_to(&mut png, image::ImageFormat::Png)
+            .map_err(|err| err.to_string())?;
+        Ok(BASE64_STANDARD.encode(png.get_ref()))
+    }
+
+    // 根据背景亮度选择对比明显的前景颜色
+    pub fn contrast_color(background: Color32) -> Color32 {
+        let [r, g, b, _] = background.to_array();
+        let luminance = 0.299 * f32::from(r) + 0.587 * f32::from(g) + 0.114 * f32::from(b);
+        if luminance < 128.0 {
+            Color32::from_gray(220)
+        } else {
+            Color32::from_gray(60)
+        }
+    }
+
+    // 将位置吸附到最近的网格交点
+    pub fn snap_to_grid(pos: Pos2, grid_size: f32) -> Pos2 {
+        if grid_size <= 0.0 {
+            return pos;
+        }
+        Pos2::new(
+            (pos.x / grid_size).round() * grid_size,
+            (pos.y / grid_size).round() * grid_size,
+        )
+    }
+
+    // 绘制覆盖 rect 的网格线（rect 与 grid_size 为画布坐标，zoom 为视图缩放，线宽在屏幕上保持 1 像素）
+    // 缩小视图使网格在屏幕上过密时不绘制
+    pub fn draw_grid(painter: &Painter, rect: Rect, grid_size: f32, zoom: f32, color: Color32) {
+        const MIN_SCREEN_SPACING: f32 = 4.0;
+
+        if grid_size <= 0.0 || grid_size * zoom < MIN_SCREEN_SPACING {
+            return;
+        }
+
+        let stroke = Stroke::new(1.0 / zoom, color);
+        let mut x = (rect.min.x / grid_size).ceil() * grid_size;
+        while x <= rect.max.x {
+            painter.vline(x, rect.y_range(), stroke);
+            x += grid_size;
+        }
+        let mut y = (rect.min.y / grid_size).ceil() * grid_size;
+        while y <= rect.max.y {
+            painter.hline(rect.x_range(), y, stroke);
+            y += grid_size;
+        }
+    }
+
+    // 绘制过原点的 x/y 坐标轴，带刻度与数值标签（y 轴向上为正）
+    // origin 为画布坐标下的原点，rect 为可见区域，只用于限定轴线与刻度的绘制范围
+    pub fn draw_axes(painter: &Painter, rect: Rect, origin: Pos2, unit: f32, color: Color32) {
+        const TICK_SIZE: f32 = 4.0;
+
+        if unit <= 0.0 {
+            return;
+        }
+
+        let axis_stroke = Stroke::new(1.5, color);
+        let font_id = egui::FontId::proportional(12.0);
+
+        painter.hline(rect.x_range(), origin.y, axis_stroke);
+        painter.vline(origin.x, rect.y_range(), axis_stroke);
+
+        // x 轴刻度
+        let first = ((rect.min.x - origin.x) / unit).ceil() as i32;
+        let last = ((rect.max.x - origin.x) / unit).floor() as i32;
+        for i in first..=last {
+            if i == 0 {
+                continue;
+            }
+            let x = origin.x + i as f32 * unit;
+            painter.vline(
+                x,
+                (origin.y - TICK_SIZE)..=(origin.y + TICK_SIZE),
+                axis_stroke,
+            );
+            painter.text(
+                Pos2::new(x, origin.y + TICK_SIZE + 2.0),
+                egui::Align2::CENTER_TOP,
+                i.to_string(),
+                font_id.clone(),
+                color,
+            );
+        }
+
+        // y 轴刻度
+        let first = ((origin.y - rect.max.y) / unit).ceil() as i32;
+        let last = ((origin.y - rect.min.y) / unit).floor() as i32;
+        for i in first..=last {
+            if i == 0 {
+                continue;
+            }
+            let y = origin.y - i as f32 * unit;
+            painter.hline(
+                (origin.x - TICK_SIZE)..=(origin.x + TICK_SIZE),
+                y,
+                axis_stroke,
+            );
+            painter.text(
+                Pos2::new(origin.x - TICK_SIZE - 2.0, y),
+                egui::Align2::RIGHT_CENTER,
+                i.to_string(),
+                font_id.clone(),
+                color,
+            );
+        }
+
+        painter.text(
+            Pos2::new(origin.x - 2.0, origin.y + 2.0),
+            egui::Align2::RIGHT_TOP,
+            "O",
+            font_id.clone(),
+            color,
+        );
+        painter.text(
+            Pos2::new(rect.max.x - 4.0, origin.y - 4.0),
+            egui::Align2::RIGHT_BOTTOM,
+            "x",
+            font_id.clone(),
+            color,
+        );
+        painter.text(
+            Pos2::new(origin.x + 6.0, rect.min.y + 4.0),
+            egui::Align2::LEFT_TOP,
+            "y",
+            font_id,
+            color,
         );
     }
 
-    pub fn draw_resize_and_rotation_anchors(
-        painter: &egui::Painter,
-        object_rect: egui::Rect,
-        resize_anchor_hovered: Option<ResizeAnchor>,
-        rotation_anchor_hovered: bool,
-    ) {
-        const ANCHOR_SIZE: f32 = 10.0;
-        const ROTATION_ANCHOR_DISTANCE: f32 = 30.0;
+    // 长度的显示文本：设置了校准时以厘米显示，否则以像素显示
+    pub fn format_length(length: f32, pixels_per_cm: Option<f32>) -> String {
+        match pixels_per_cm {
+            Some(pixels_per_cm) if pixels_per_cm > 0.0 => {
+                format!("{:.2} cm", length / pixels_per_cm)
+            }
+            _ => format!("{length:.1} px"),
+        }
+    }
 
-        // 绘制调整大小锚点
-        let anchors = [
-            (ResizeAnchor::TopLeft, object_rect.left_top()),
-            (ResizeAnchor::TopRight, object_rect.right_top()),
-            (ResizeAnchor::BottomLeft, object_rect.left_bottom()),
-            (ResizeAnchor::BottomRight, object_rect.right_bottom()),
-            (
-                ResizeAnchor::Top,
-                Pos2::new(object_rect.center().x, object_rect.min.y),
-            ),
-            (
-                ResizeAnchor::Bottom,
-                Pos2::new(object_rect.center().x, object_rect.max.y),
-            ),
-            (
-                ResizeAnchor::Left,
-                Pos2::new(object_rect.min.x, object_rect.center().y),
-            ),
-            (
-                ResizeAnchor::Right,
-                Pos2::new(object_rect.max.x, object_rect.center().y),
-            ),
-        ];
+    // 量角器半径（画布坐标）
+    pub const PROTRACTOR_RADIUS: f32 = 200.0;
 
-        for (anchor_type, pos) in anchors {
-            // 绘制锚点
-            let anchor_color = if let Some(hovered_anchor) = resize_anchor_hovered {
-                if hovered_anchor == anchor_type {
-                    Color32::YELLOW
-                } else {
-                    Color32::WHITE
-                }
-            } else {
-                Color32::WHITE
+    // 从 center 指向 a 的射线转到指向 b 的射线：返回起始方向角与有向夹角（-π 到 π，取较小的一侧）
+    pub fn ray_sweep(center: Pos2, a: Pos2, b: Pos2) -> (f32, f32) {
+        use std::f32::consts::{PI, TAU};
+
+        let start = (a - center).angle();
+        let end = (b - center).angle();
+        (start, (end - start + PI).rem_euclid(TAU) - PI)
+    }
+
+    // 绘制半圆形量角器：中心位于底边中点，刻度从右侧的 0° 沿上半圆增加到左侧的 180°
+    pub fn draw_protractor(painter: &Painter, center: Pos2, zoom: f32) {
+        const ARC_SEGMENTS: u32 = 90;
+
+        let radius = Self::PROTRACTOR_RADIUS;
+        let ink = Color32::from_rgb(20, 70, 120);
+        let stroke = Stroke::new(1.0 / zoom, ink);
+        // 画布的 y 轴向下，上半圆对应负角度
+        let direction = |degrees: f32| Vec2::angled(-degrees.to_radians());
+
+        let outline: Vec<Pos2> = (0..=ARC_SEGMENTS)
+            .map(|i| center + direction(180.0 * i as f32 / ARC_SEGMENTS as f32) * radius)
+            .collect();
+        painter.add(egui::Shape::convex_polygon(
+            outline,
+            Color32::from_rgba_unmultiplied(160, 210, 255, 110),
+            stroke,
+        ));
+
+        let font_id = egui::FontId::proportional(11.0);
+        for degree in 0..=180_u32 {
+            let direction = direction(degree as f32);
+            let length = match degree {
+                _ if degree % 10 == 0 => 16.0,
+                _ if degree % 5 == 0 => 10.0,
+                _ => 5.0,
             };
+            let outer = center + direction * radius;
+            painter.line_segment([outer, outer - direction * length], stroke);
+            if degree % 10 == 0 {
+                painter.text(
+                    center + direction * (radius - length - 10.0),
+                    egui::Align2::CENTER_CENTER,
+                    degree.to_string(),
+                    font_id.clone(),
+                    ink,
+                );
+            }
+        }
+        painter.circle_filled(center, 3.0 / zoom, ink);
+    }
+
+    // 绘制量角器上测量的角：两条射线延伸到量角器边缘之外，两条射线都确定后标出夹角与度数
+    pub fn draw_angle_measurement(
+        painter: &Painter,
+        center: Pos2,
+        rays: [Option<Pos2>; 2],
+        zoom: f32,
+    ) {
+        const ARC_RADIUS: f32 = 40.0;
+        const ARC_SEGMENTS: u32 = 32;
 
-            painter.circle_filled(pos, ANCHOR_SIZE, anchor_color);
-            painter.circle_stroke(pos, ANCHOR_SIZE, Stroke::new(2.0, Color32::BLACK));
+        let color = Color32::from_rgb(255, 120, 0);
+        let stroke = Stroke::new(2.0 / zoom, color);
+        for ray in rays.iter().flatten() {
+            let direction = (*ray - center).normalized();
+            let length = ray.distance(center).max(Self::PROTRACTOR_RADIUS + 20.0);
+            painter.line_segment([center, center + direction * length], stroke);
         }
 
-        // 绘制旋转锚点（在顶部中间锚点上方）
-        let rotation_anchor_pos = Pos2::new(
-            object_rect.center().x,
-            object_rect.min.y - ROTATION_ANCHOR_DISTANCE,
+        let [Some(first), Some(second)] = rays else {
+            return;
+        };
+        let (start, sweep) = Self::ray_sweep(center, first, second);
+        let arc: Vec<Pos2> = (0..=ARC_SEGMENTS)
+            .map(|i| {
+                center + Vec2::angled(start + sweep * i as f32 / ARC_SEGMENTS as f32) * ARC_RADIUS
+            })
+            .collect();
+        painter.add(egui::Shape::line(arc, stroke));
+        painter.text(
+            center + Vec2::angled(start + sweep / 2.0) * (ARC_RADIUS + 18.0),
+            egui::Align2::CENTER_CENTER,
+            format!("{:.1}°", sweep.abs().to_degrees()),
+            egui::FontId::proportional(16.0),
+            color,
         );
+    }
 
-        let rotation_color = if rotation_anchor_hovered {
-            Color32::YELLOW
-        } else {
-            Color32::WHITE
-        };
+    // 直尺尺寸（画布坐标）
+    pub const RULER_LENGTH: f32 = 800.0;
+    pub const RULER_WIDTH: f32 = 60.0;
+    pub const RULER_HANDLE_RADIUS: f32 = 10.0;
+
+    // 直尺的四个角：前两个角之间是吸附画笔的边缘，尺身位于边缘的一侧
+    pub fn ruler_corners(anchor: Pos2, angle: f32) -> [Pos2; 4] {
+        let direction = Vec2::angled(angle);
+        let along = direction * Self::RULER_LENGTH / 2.0;
+        let across = direction.rot90() * Self::RULER_WIDTH;
+        [
+            anchor - along,
+            anchor + along,
+            anchor + along + across,
+            anchor - along + across,
+        ]
+    }
+
+    // 直尺旋转手柄位置（在边缘延长线上，直尺右端之外）
+    pub fn ruler_handle_position(anchor: Pos2, angle: f32) -> Pos2 {
+        anchor + Vec2::angled(angle) * (Self::RULER_LENGTH / 2.0 + Self::ROTATION_ANCHOR_DISTANCE)
+    }
 
-        painter.circle_filled(rotation_anchor_pos, ANCHOR_SIZE, rotation_color);
+    // 将点投影到直尺边缘上，点离边缘超过 max_distance 或超出直尺两端时返回 None
+    pub fn project_onto_ruler(
+        pos: Pos2,
+        anchor: Pos2,
+        angle: f32,
+        max_distance: f32,
+    ) -> Option<Pos2> {
+        let direction = Vec2::angled(angle);
+        let offset = pos - anchor;
+        let along = offset.dot(direction);
+        let across = offset.dot(direction.rot90());
+        (along.abs() <= Self::RULER_LENGTH / 2.0 && across.abs() <= max_distance)
+            .then(|| anchor + direction * along)
+    }
+
+    // 绘制半透明的直尺：每 10 像素一个刻度，每 100 像素标注一次长度
+    pub fn draw_ruler(
+        painter: &Painter,
+        anchor: Pos2,
+        angle: f32,
+        zoom: f32,
+        handle_hovered: bool,
+    ) {
+        const TICK_SPACING: f32 = 10.0;
+
+        let corners = Self::ruler_corners(anchor, angle);
+        let direction = Vec2::angled(angle);
+        let normal = direction.rot90();
+        let ink = Color32::from_rgb(90, 70, 20);
+        let stroke = Stroke::new(1.0 / zoom, ink);
+        painter.add(egui::Shape::convex_polygon(
+            corners.to_vec(),
+            Color32::from_rgba_unmultiplied(235, 210, 130, 150),
+            stroke,
+        ));
+
+        let font_id = egui::FontId::proportional(11.0);
+        let ticks = (Self::RULER_LENGTH / TICK_SPACING) as u32;
+        for i in 0..=ticks {
+            let start = corners[0] + direction * (i as f32 * TICK_SPACING);
+            let length = match i {
+                _ if i % 10 == 0 => 18.0,
+                _ if i % 5 == 0 => 12.0,
+                _ => 6.0,
+            };
+            painter.line_segment([start, start + normal * length], stroke);
+            if i % 10 == 0 {
+                let galley = painter.layout_no_wrap((i * 10).to_string(), font_id.clone(), ink);
+                let label_pos = start + normal * (length + 2.0) + direction * 2.0;
+                painter.add(egui::epaint::TextShape::new(label_pos, galley, ink).with_angle(angle));
+            }
+        }
+
+        let handle = Self::ruler_handle_position(anchor, angle);
+        painter.line_segment([corners[1], handle], Stroke::new(2.0 / zoom, ink));
+        painter.circle_filled(
+            handle,
+            Self::RULER_HANDLE_RADIUS,
+            if handle_hovered {
+                Color32::YELLOW
+            } else {
+                Color32::WHITE
+            },
+        );
         painter.circle_stroke(
-            rotation_anchor_pos,
-            ANCHOR_SIZE,
-            Stroke::new(2.0, Color32::BLACK),
+            handle,
+            Self::RULER_HANDLE_RADIUS,
+            Stroke::new(2.0 / zoom, ink),
+        );
+    }
+
+    // 计算折线的总长度
+    pub fn calculate_path_length(points: &[Pos2]) -> f32 {
+        points
+            .windows(2)
+            .map(|w| match w {
+                [a, b] => a.distance(*b),
+                _ => 0.0,
+            })
+            .sum()
+    }
+
+    // 计算笔画的边界框
+    pub fn calculate_stroke_bounding_box(stroke: &crate::state::CanvasStroke) -> Rect {
+        stroke.bounds()
+    }
+
+    // 计算任意画布对象的边界框
+    pub fn calculate_object_bounding_box(
+        painter: &Painter,
+        object: &crate::state::CanvasObject,
+    ) -> Rect {
+        match object {
+            crate::state::CanvasObject::Image(img) => Rect::from_min_size(img.pos, img.size),
+            crate::state::CanvasObject::Text(text) => {
+                Rect::from_min_size(text.pos, painter.text_size(text))
+            }
+            crate::state::CanvasObject::Shape(shape) => Self::calculate_shape_bounding_box(shape),
+            crate::state::CanvasObject::Stroke(stroke) => {
+                Self::calculate_stroke_bounding_box(stroke)
+            }
+        }
+    }
+
+    // 查找内容包含关键词的文本对象（不区分大小写），返回对象索引
+    pub fn find_text_matches(objects: &[crate::state::CanvasObject], query: &str) -> Vec<usize> {
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        let query = query.to_lowercase();
+        objects
+            .iter()
+            .enumerate()
+            .filter_map(|(i, object)| match object {
+                crate::state::CanvasObject::Text(text)
+                    if text.text.to_lowercase().contains(&query) =>
+                {
+                    Some(i)
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    // 计算让对象至少有 min_visible 像素留在画布内所需的偏移
+    pub fn keep_rect_visible(object_rect: Rect, canvas_rect: Rect, min_visible: f32) -> Vec2 {
+        let axis_offset = |min: f32, max: f32, canvas_min: f32, canvas_max: f32| {
+            let visible = min_visible.min(max - min);
+            if max < canvas_min + visible {
+                canvas_min + visible - max
+            } else if min > canvas_max - visible {
+                canvas_max - visible - min
+            } else {
+                0.0
+            }
+        };
+        Vec2::new(
+            axis_offset(
+                object_rect.min.x,
+                object_rect.max.x,
+                canvas_rect.min.x,
+                canvas_rect.max.x,
+            ),
+            axis_offset(
+                object_rect.min.y,
+                object_rect.max.y,
+                canvas_rect.min.y,
+                canvas_rect.max.y,
+            ),
+        )
+    }
+
+    // 计算对象吸附到画布中心与边缘所需的偏移，以及需要显示的参考线
+    pub fn snap_rect_to_canvas(
+        moving: Rect,
+        canvas: Rect,
+        threshold: f32,
+    ) -> (Vec2, Vec<[Pos2; 2]>) {
+        // 在候选参考线中找出与对象边缘/中心距离最近的一条
+        fn nearest(edges: [f32; 3], references: [f32; 3], threshold: f32) -> Option<(f32, f32)> {
+            let mut best: Option<(f32, f32)> = None;
+            for edge in edges {
+                for reference in references {
+                    let diff = reference - edge;
+                    if diff.abs() <= threshold
+                        && best.is_none_or(|(best_diff, _)| diff.abs() < best_diff.abs())
+                    {
+                        best = Some((diff, reference));
+                    }
+                }
+            }
+            best
+        }
+
+        let mut offset = Vec2::ZERO;
+        let mut guides = Vec::new();
+
+        if let Some((diff, x)) = nearest(
+            [moving.min.x, moving.center().x, moving.max.x],
+            [canvas.min.x, canvas.center().x, canvas.max.x],
+            threshold,
+        ) {
+            offset.x = diff;
+            guides.push([Pos2::new(x, canvas.min.y), Pos2::new(x, canvas.max.y)]);
+        }
+
+        if let Some((diff, y)) = nearest(
+            [moving.min.y, moving.center().y, moving.max.y],
+            [canvas.min.y, canvas.center().y, canvas.max.y],
+            threshold,
+        ) {
+            offset.y = diff;
+            guides.push([Pos2::new(canvas.min.x, y), Pos2::new(canvas.max.x, y)]);
+        }
+
+        (offset, guides)
+    }
+
+    // 计算对象吸附到其他对象边缘与中心所需的偏移，以及连接已对齐对象的参考线
+    pub fn snap_rect_to_objects(
+        moving: Rect,
+        others: &[Rect],
+        threshold: f32,
+    ) -> (Vec2, Vec<[Pos2; 2]>) {
+        const ALIGNED_EPSILON: f32 = 0.5;
+
+        fn xs(rect: Rect) -> [f32; 3] {
+            [rect.min.x, rect.center().x, rect.max.x]
+        }
+        fn ys(rect: Rect) -> [f32; 3] {
+            [rect.min.y, rect.center().y, rect.max.y]
+        }
+        // 所有其他对象中距离最近的对齐偏移
+        let nearest = |coords: fn(Rect) -> [f32; 3]| {
+            let edges = coords(moving);
+            others
+                .iter()
+                .flat_map(|other| coords(*other))
+                .flat_map(|reference| edges.map(|edge| reference - edge))
+                .filter(|diff| diff.abs() <= threshold)
+                .min_by(|a, b| a.abs().total_cmp(&b.abs()))
+        };
+
+        let offset = Vec2::new(nearest(xs).unwrap_or(0.0), nearest(ys).unwrap_or(0.0));
+        let snapped = moving.translate(offset);
+
+        // 每个对齐位置画一条参考线，覆盖移动对象与对齐的对象
+        let mut guides = Vec::new();
+        for other in others {
+            for x in xs(*other) {
+                if xs(snapped)
+                    .iter()
+                    .any(|edge| (edge - x).abs() <= ALIGNED_EPSILON)
+                {
+                    guides.push([
+                        Pos2::new(x, snapped.min.y.min(other.min.y)),
+                        Pos2::new(x, snapped.max.y.max(other.max.y)),
+                    ]);
+                }
+            }
+            for y in ys(*other) {
+                if ys(snapped)
+                    .iter()
+                    .any(|edge| (edge - y).abs() <= ALIGNED_EPSILON)
+                {
+                    guides.push([
+                        Pos2::new(snapped.min.x.min(other.min.x), y),
+                        Pos2::new(snapped.max.x.max(other.max.x), y),
+                    ]);
+                }
+            }
+        }
+
+        (offset, guides)
+    }
+
+    // 计算形状的轮廓折线（已应用旋转）
+    pub fn shape_outline(shape: &crate::state::CanvasShape) -> Vec<Vec<Pos2>> {
+        const CIRCLE_SEGMENTS: usize = 64;
+
+        let polylines: Vec<Vec<Pos2>> = match shape.shape_type {
+            crate::state::CanvasShapeType::Line => {
+                vec![vec![
+                    shape.pos,
+                    Pos2::new(shape.pos.x + shape.size, shape.pos.y),
+                ]]
+            }
+            crate::state::CanvasShapeType::Arrow => {
+                let end_point = Pos2::new(shape.pos.x + shape.size, shape.pos.y);
+                let mut polylines = vec![
+                    vec![shape.pos, end_point],
+                    Self::arrow_head(shape.pos, end_point, shape.stroke_width),
+                ];
+                if shape.arrow_heads == crate::state::ArrowHeads::Both {
+                    polylines.push(Self::arrow_head(end_point, shape.pos, shape.stroke_width));
+                }
+                polylines
+            }
+            crate::state::CanvasShapeType::Rectangle => {
+                let rect = Rect::from_min_size(shape.pos, shape.rect_size());
+                vec![Self::rounded_rect_outline(rect, shape.corner_radius)]
+            }
+            crate::state::CanvasShapeType::Triangle => {
+                let half_size = shape.size / 2.0;
+                vec![vec![
+                    shape.pos,
+                    Pos2::new(shape.pos.x + shape.size, shape.pos.y),
+                    Pos2::new(shape.pos.x + half_size, shape.pos.y + half_size),
+                    shape.pos,
+                ]]
+            }
+            crate::state::CanvasShapeType::Circle => {
+                let radii = shape.rect_size() / 2.0;
+                vec![
+                    (0..=CIRCLE_SEGMENTS)
+                        .map(|i| {
+                            let angle = i as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+                            shape.pos + radii * Vec2::angled(angle)
+                        })
+                        .collect(),
+                ]
+            }
+            crate::state::CanvasShapeType::Polygon { sides } => {
+                vec![Self::radial_outline(shape.pos, &[shape.size], sides.max(3))]
+            }
+            crate::state::CanvasShapeType::Star { points } => {
+                const STAR_INNER_RATIO: f32 = 0.4;
+
+                vec![Self::radial_outline(
+                    shape.pos,
+                    &[shape.size, shape.size * STAR_INNER_RATIO],
+                    points.max(3) * 2,
+                )]
+            }
+            crate::state::CanvasShapeType::Region => {
+                let frame = Rect::from_min_size(shape.pos, shape.rect_size());
+                let mut points: Vec<Pos2> = shape
+                    .outline
+                    .iter()
+                    .map(|p| frame.min + *p * frame.size())
+                    .collect();
+                points.extend(points.first().copied());
+                vec![points]
+            }
+        };
+
+        if shape.rotation == 0.0 {
+            return polylines;
+        }
+
+        let center = Self::shape_center(shape);
+        polylines
+            .into_iter()
+            .map(|polyline| {
+                polyline
+                    .into_iter()
+                    .map(|p| Self::rotate_point(p, center, shape.rotation))
+                    .collect()
+            })
+            .collect()
+    }
+
+    // 绕中心均匀分布的顶点组成的闭合轮廓（首尾点相同），第一个顶点朝上
+    // 各顶点依次循环使用 radii 中的半径：正多边形只有一个半径，星形交替使用外侧与内侧半径
+    fn radial_outline(center: Pos2, radii: &[f32], count: u32) -> Vec<Pos2> {
+        (0..=count)
+            .zip(radii.iter().cycle())
+            .map(|(i, radius)| {
+                let angle =
+                    i as f32 / count as f32 * std::f32::consts::TAU - std::f32::consts::FRAC_PI_2;
+                center + *radius * Vec2::angled(angle)
+            })
+            .collect()
+    }
+
+    // 用耳切法将简单多边形（不闭合的顶点序列）三角化为网格，用于填充非凸的区域
+    pub fn polygon_mesh(points: &[Pos2], color: Color32) -> egui::Mesh {
+        let mut mesh = egui::Mesh::default();
+        for point in points {
+            mesh.colored_vertex(*point, color);
+        }
+
+        // 三点的转向（叉积），与多边形方向同号时为凸角
+        let turn = |a: Pos2, b: Pos2, c: Pos2| (b - a).x * (c - b).y - (b - a).y * (c - b).x;
+        let mut twice_area = 0.0;
+        for (a, b) in points.iter().zip(points.iter().cycle().skip(1)) {
+            twice_area += a.x * b.y - b.x * a.y;
+        }
+        let orientation = twice_area.signum();
+        let vertex = |i: u32| points.get(i as usize).copied().unwrap_or(Pos2::ZERO);
+
+        let mut ring: Vec<u32> = (0..u32::try_from(points.len()).unwrap_or(0)).collect();
+        while ring.len() > 3 {
+            let count = ring.len();
+            let corner = |k: usize| {
+                [(k + count - 1) % count, k, (k + 1) % count]
+                    .map(|j| ring.get(j).copied().unwrap_or(0))
+            };
+            // 耳朵：凸角且三角形内没有其他顶点；找不到时（自相交等退化情况）直接切去第一个角，保证结束
+            let ear = (0..count)
+                .find(|&k| {
+                    let [a, b, c] = corner(k).map(vertex);
+                    turn(a, b, c) * orientation > 0.0
+                        && !ring.iter().any(|&i| {
+                            let p = vertex(i);
+                            p != a
+                                && p != b
+                                && p != c
+                                && turn(a, b, p) * orientation >= 0.0
+                                && turn(b, c, p) * orientation >= 0.0
+                                && turn(c, a, p) * orientation >= 0.0
+                        })
+                })
+                .unwrap_or(0);
+            let [a, b, c] = corner(ear);
+            mesh.add_triangle(a, b, c);
+            ring.remove(ear);
+        }
+        if let &[a, b, c] = ring.as_slice() {
+            mesh.add_triangle(a, b, c);
+        }
+        mesh
+    }
+
+    // 以中心为公共顶点的扇形三角网格，用于填充星形等相对中心可见的多边形
+    pub fn fan_mesh(center: Pos2, points: &[Pos2], color: Color32) -> egui::Mesh {
+        let mut mesh = egui::Mesh::default();
+        mesh.colored_vertex(center, color);
+        for point in points {
+            mesh.colored_vertex(*point, color);
+        }
+        let count = points.len() as u32;
+        for i in 0..count {
+            mesh.add_triangle(0, i + 1, (i + 1) % count + 1);
+        }
+        mesh
+    }
+
+    // 圆角矩形的闭合轮廓（首尾点相同），圆角半径不超过短边的一半
+    fn rounded_rect_outline(rect: Rect, corner_radius: f32) -> Vec<Pos2> {
+        const CORNER_SEGMENTS: usize = 8;
+
+        let radius = corner_radius.clamp(0.0, rect.size().min_elem() / 2.0);
+        if radius <= 0.0 {
+            return vec![
+                rect.left_top(),
+                rect.right_top(),
+                rect.right_bottom(),
+                rect.left_bottom(),
+                rect.left_top(),
+            ];
+        }
+
+        // 从左上角开始顺时针，每个圆角为四分之一圆弧
+        let corners = [
+            (rect.left_top() + egui::vec2(radius, radius), 0.5),
+            (rect.right_top() + egui::vec2(-radius, radius), 0.75),
+            (rect.right_bottom() + egui::vec2(-radius, -radius), 0.0),
+            (rect.left_bottom() + egui::vec2(radius, -radius), 0.25),
+        ];
+        let mut points: Vec<Pos2> = corners
+            .iter()
+            .flat_map(|&(center, start)| {
+                (0..=CORNER_SEGMENTS).map(move |i| {
+                    let turn = start + i as f32 / CORNER_SEGMENTS as f32 * 0.25;
+                    center + radius * Vec2::angled(turn * std::f32::consts::TAU)
+                })
+            })
+            .collect();
+        points.extend(points.first().copied());
+        points
+    }
+
+    // 按锚点调整矩形形状的范围：侧边锚点只改变一个方向，角锚点同时改变宽高
+    // rect 为形状自身（未旋转）的范围，拖动量换算到形状的坐标系中，对边保持不动
+    // keep_aspect 时角锚点按变化较大的方向等比缩放，保持原有宽高比
+    pub fn resize_shape_rect(
+        rect: Rect,
+        rotation: f32,
+        anchor: ResizeAnchor,
+        delta: Vec2,
+        min_size: f32,
+        keep_aspect: bool,
+    ) -> Rect {
+        let local = egui::emath::Rot2::from_angle(-rotation) * delta;
+        let (mut min, mut max) = (rect.min, rect.max);
+        if matches!(
+            anchor,
+            ResizeAnchor::Left | ResizeAnchor::TopLeft | ResizeAnchor::BottomLeft
+        ) {
+            min.x = (min.x + local.x).min(max.x - min_size);
+        }
+        if matches!(
+            anchor,
+            ResizeAnchor::Right | ResizeAnchor::TopRight | ResizeAnchor::BottomRight
+        ) {
+            max.x = (max.x + local.x).max(min.x + min_size);
+        }
+        if matches!(
+            anchor,
+            ResizeAnchor::Top | ResizeAnchor::TopLeft | ResizeAnchor::TopRight
+        ) {
+            min.y = (min.y + local.y).min(max.y - min_size);
+        }
+        if matches!(
+            anchor,
+            ResizeAnchor::Bottom | ResizeAnchor::BottomLeft | ResizeAnchor::BottomRight
+        ) {
+            max.y = (max.y + local.y).max(min.y + min_size);
+        }
+
+        let moves_left = matches!(anchor, ResizeAnchor::TopLeft | ResizeAnchor::BottomLeft);
+        let moves_top = matches!(anchor, ResizeAnchor::TopLeft | ResizeAnchor::TopRight);
+        let is_corner = !matches!(
+            anchor,
+            ResizeAnchor::Top | ResizeAnchor::Bottom | ResizeAnchor::Left | ResizeAnchor::Right
+        );
+        if keep_aspect && is_corner && rect.width() > 0.0 && rect.height() > 0.0 {
+            let scale = ((max.x - min.x) / rect.width()).max((max.y - min.y) / rect.height());
+            let size = rect.size() * scale;
+            if moves_left {
+                min.x = max.x - size.x;
+            } else {
+                max.x = min.x + size.x;
+            }
+            if moves_top {
+                min.y = max.y - size.y;
+            } else {
+                max.y = min.y + size.y;
+            }
+        }
+
+        // 形状绕自身中心旋转：按旋转后的中心偏移放置，使对边在画布上的位置不变
+        let resized = Rect::from_min_max(min, max);
+        let center = rect.center()
+            + egui::emath::Rot2::from_angle(rotation) * (resized.center() - rect.center());
+        Rect::from_center_size(center, resized.size())
+    }
+
+    // 箭头头部的折线：长度随轮廓宽度变化，但不超过箭身长度的一半
+    fn arrow_head(from: Pos2, tip: Pos2, stroke_width: f32) -> Vec<Pos2> {
+        const HEAD_ANGLE: f32 = std::f32::consts::PI / 6.0;
+
+        let length = (stroke_width * 5.0).max(10.0).min(from.distance(tip) / 2.0);
+        let back = (from - tip).normalized() * length;
+        vec![
+            tip + egui::emath::Rot2::from_angle(HEAD_ANGLE) * back,
+            tip,
+            tip + egui::emath::Rot2::from_angle(-HEAD_ANGLE) * back,
+        ]
+    }
+
+    // 直线与箭头的起点和终点（形状以位置、长度和绕中点的旋转角度表示）
+    pub fn line_endpoints(shape: &crate::state::CanvasShape) -> (Pos2, Pos2) {
+        let center = Self::shape_center(shape);
+        let half = Vec2::angled(shape.rotation) * (shape.size / 2.0);
+        (center - half, center + half)
+    }
+
+    // 按起点和终点设置直线或箭头的位置、长度与旋转角度
+    pub fn set_line_endpoints(shape: &mut crate::state::CanvasShape, start: Pos2, end: Pos2) {
+        const MIN_LENGTH: f32 = 1.0;
+
+        let delta = end - start;
+        shape.size = delta.length().max(MIN_LENGTH);
+        shape.rotation = delta.angle();
+        shape.pos = start.lerp(end, 0.5) - egui::vec2(shape.size / 2.0, 0.0);
+    }
+
+    // 计算形状自身的几何中心（形状绕此点旋转）
+    pub fn shape_center(shape: &crate::state::CanvasShape) -> Pos2 {
+        match shape.shape_type {
+            crate::state::CanvasShapeType::Line | crate::state::CanvasShapeType::Arrow => {
+                shape.pos + egui::vec2(shape.size / 2.0, 0.0)
+            }
+            crate::state::CanvasShapeType::Rectangle | crate::state::CanvasShapeType::Region => {
+                shape.pos + shape.rect_size() / 2.0
+            }
+            crate::state::CanvasShapeType::Triangle => {
+                shape.pos + egui::vec2(shape.size / 2.0, shape.size / 4.0)
+            }
+            crate::state::CanvasShapeType::Circle
+            | crate::state::CanvasShapeType::Polygon { .. }
+            | crate::state::CanvasShapeType::Star { .. } => shape.pos,
+        }
+    }
+
+    // 按手绘笔画的范围生成识别出的形状（矩形与圆形保留宽高，圆形可能成为椭圆）
+    pub fn fit_recognized_shape(
+        shape_type: crate::state::CanvasShapeType,
+        points: &[Pos2],
+        color: Color32,
+        stroke_width: f32,
+    ) -> crate::state::CanvasShape {
+        use crate::state::CanvasShapeType;
+
+        let bounds = Rect::from_points(points);
+        let (first, last) = (
+            points.first().copied().unwrap_or(bounds.min),
+            points.last().copied().unwrap_or(bounds.max),
+        );
+        let (center, size, rotation) = match shape_type {
+            CanvasShapeType::Line | CanvasShapeType::Arrow => {
+                let delta = last - first;
+                (first.lerp(last, 0.5), delta.length(), delta.angle())
+            }
+            CanvasShapeType::Rectangle | CanvasShapeType::Circle | CanvasShapeType::Region => {
+                (bounds.center(), bounds.width(), 0.0)
+            }
+            CanvasShapeType::Polygon { .. } | CanvasShapeType::Star { .. } => {
+                (bounds.center(), bounds.size().max_elem() / 2.0, 0.0)
+            }
+            CanvasShapeType::Triangle => {
+                // 三角形形状的底边在上方；笔迹整体偏下时说明顶点朝上，旋转 180°
+                let mean_y = points.iter().map(|p| p.y).sum::<f32>() / points.len() as f32;
+                let rotation = if mean_y > bounds.center().y {
+                    std::f32::consts::PI
+                } else {
+                    0.0
+                };
+                (bounds.center(), bounds.width(), rotation)
+            }
+        };
+
+        let mut shape = crate::state::CanvasShape {
+            shape_type,
+            pos: center,
+            size,
+            color,
+            rotation,
+            stroke_width,
+            fill: None,
+            arrow_heads: crate::state::ArrowHeads::End,
+            height: matches!(
+                shape_type,
+                CanvasShapeType::Rectangle | CanvasShapeType::Circle | CanvasShapeType::Region
+            )
+            .then(|| bounds.height()),
+            corner_radius: 0.0,
+            outline: Vec::new(),
+        };
+        shape.pos += center - Self::shape_center(&shape);
+        shape
+    }
+
+    // 将点绕中心旋转指定角度（弧度）
+    pub fn rotate_point(p: Pos2, center: Pos2, angle: f32) -> Pos2 {
+        center + egui::emath::Rot2::from_angle(angle) * (p - center)
+    }
+
+    // 将线段终点吸附到以起点为中心、按固定角度间隔（弧度）划分的方向上，保持线段长度不变
+    pub fn snap_line_angle(start: Pos2, end: Pos2, step: f32) -> Pos2 {
+        let delta = end - start;
+        let angle = (delta.angle() / step).round() * step;
+        start + Vec2::angled(angle) * delta.length()
+    }
+
+    // 根据设置计算旋转操作的旋转中心
+    pub fn rotation_pivot_position(
+        pivot: crate::state::RotationPivot,
+        custom_pivot: Vec2,
+        object_rect: Rect,
+        object: &crate::state::CanvasObject,
+    ) -> Pos2 {
+        match pivot {
+            crate::state::RotationPivot::Center => object_rect.center(),
+            crate::state::RotationPivot::Origin => match object {
+                crate::state::CanvasObject::Shape(shape) => {
+                    Self::rotate_point(shape.pos, Self::shape_center(shape), shape.rotation)
+                }
+                _ => object_rect.min,
+            },
+            crate::state::RotationPivot::TopLeft => object_rect.left_top(),
+            crate::state::RotationPivot::TopRight => object_rect.right_top(),
+            crate::state::RotationPivot::BottomLeft => object_rect.left_bottom(),
+            crate::state::RotationPivot::BottomRight => object_rect.right_bottom(),
+            crate::state::RotationPivot::Custom => Pos2::new(
+                egui::lerp(object_rect.x_range(), custom_pivot.x),
+                egui::lerp(object_rect.y_range(), custom_pivot.y),
+            ),
+        }
+    }
+
+    // 填充工具：在可见范围 bounds 内将笔画与形状轮廓栅格化为边界，从 seed 所在的格子开始漫水填充，
+    // 返回填充区域外轮廓的顶点（区域内部被包围的其他笔迹不会挖空）
+    // 点击位置在笔迹上或区域延伸到 bounds 边缘（未封闭）时返回错误提示
+    pub fn flood_fill_region(
+        objects: &[crate::state::CanvasObject],
+        seed: Pos2,
+        bounds: Rect,
+    ) -> Result<Vec<Pos2>, String> {
+        // 栅格最长边的格数，限制内存与计算量
+        const MAX_GRID_SIZE: f32 = 600.0;
+
+        if !bounds.is_positive() || !bounds.contains(seed) {
+            return Err(String::from("请在画布的可见范围内点击"));
+        }
+        let cell = bounds.size().max_elem() / MAX_GRID_SIZE;
+        let grid = FillGrid {
+            origin: bounds.min,
+            cell,
+            width: (bounds.width() / cell).ceil() as usize,
+            height: (bounds.height() / cell).ceil() as usize,
+        };
+
+        let barrier = Self::fill_barriers(objects, &grid);
+        let is_barrier = |x: usize, y: usize| {
+            grid.index(x, y)
+                .and_then(|i| barrier.get(i).copied())
+                .unwrap_or(true)
+        };
+        let (seed_x, seed_y) = grid.cell_of(seed);
+        if is_barrier(seed_x, seed_y) {
+            return Err(String::from("请点击笔迹围成的区域内部"));
+        }
+
+        // 四连通漫水填充，碰到可见范围边缘说明区域未封闭
+        let mut filled = vec![false; grid.width * grid.height];
+        let mut stack = vec![(seed_x, seed_y)];
+        while let Some((x, y)) = stack.pop() {
+            if x == 0 || y == 0 || x + 1 == grid.width || y + 1 == grid.height {
+                return Err(String::from("区域未封闭，无法填充"));
+            }
+            for (nx, ny) in [(x, y), (x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+                if !is_barrier(nx, ny)
+                    && let Some(flag) = grid.index(nx, ny).and_then(|i| filled.get_mut(i))
+                    && !*flag
+                {
+                    *flag = true;
+                    stack.push((nx, ny));
+                }
+            }
+        }
+
+        // 向边界内扩展一格，使填充与笔迹之间不留缝隙（填充位于笔迹下方，重叠部分被覆盖）
+        let is_filled = |x: usize, y: usize| {
+            grid.index(x, y)
+                .and_then(|i| filled.get(i).copied())
+                .unwrap_or(false)
+        };
+        let region: Vec<bool> = (0..grid.width * grid.height)
+            .map(|i| {
+                let (x, y) = (i % grid.width, i / grid.width);
+                is_filled(x, y)
+                    || (is_barrier(x, y)
+                        && FillGrid::neighbors(x, y)
+                            .into_iter()
+                            .any(|(nx, ny)| is_filled(nx, ny)))
+            })
+            .collect();
+
+        // 简化锯齿状的格子边缘
+        let mut outline = Self::trace_region_outline(&region, &grid);
+        outline.extend(outline.first().copied());
+        let kept = Self::simplify_stroke(&outline, cell * 0.75);
+        let mut simplified: Vec<Pos2> = kept
+            .iter()
+            .filter_map(|&i| outline.get(i).copied())
+            .collect();
+        simplified.pop();
+        if simplified.len() < 3 {
+            return Err(String::from("区域太小，无法填充"));
+        }
+        Ok(simplified)
+    }
+
+    // 填充边界：与笔画或形状轮廓中心线的距离不超过半宽的格子
+    fn fill_barriers(objects: &[crate::state::CanvasObject], grid: &FillGrid) -> Vec<bool> {
+        use crate::state::{CanvasObject, CanvasShapeType};
+
+        let mut barrier = vec![false; grid.width * grid.height];
+        let mut mark_segment = |a: Pos2, b: Pos2, half_width: f32| {
+            let reach = half_width + grid.cell / 2.0;
+            let area = Rect::from_two_pos(a, b).expand(reach);
+            let ((x0, y0), (x1, y1)) = (grid.cell_of(area.min), grid.cell_of(area.max));
+            for y in y0..=y1 {
+                for x in x0..=x1 {
+                    if Self::point_to_line_segment_distance(grid.center(x, y), a, b) <= reach
+                        && let Some(flag) = grid.index(x, y).and_then(|i| barrier.get_mut(i))
+                    {
+                        *flag = true;
+                    }
+                }
+            }
+        };
+        for object in objects {
+            match object {
+                CanvasObject::Stroke(stroke) => match stroke.points.as_slice() {
+                    [point] => mark_segment(*point, *point, Self::stroke_width_at(stroke, 0) / 2.0),
+                    points => {
+                        for (i, segment) in points.windows(2).enumerate() {
+                            if let &[a, b] = segment {
+                                let width = Self::stroke_width_at(stroke, i)
+                                    .max(Self::stroke_width_at(stroke, i + 1));
+                                mark_segment(a, b, width / 2.0);
+                            }
+                        }
+                    }
+                },
+                // 已有的填充区域不作为边界，可以在其上重新填充
+                CanvasObject::Shape(shape)
+                    if !matches!(shape.shape_type, CanvasShapeType::Region) =>
+                {
+                    for polyline in Self::shape_outline(shape) {
+                        for segment in polyline.windows(2) {
+                            if let &[a, b] = segment {
+                                mark_segment(a, b, shape.stroke_width / 2.0);
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        barrier
+    }
+
+    // 沿格子边缘收集区域边界（顺时针方向的有向边），串成闭合的环，返回面积最大的环（外轮廓）
+    fn trace_region_outline(region: &[bool], grid: &FillGrid) -> Vec<Pos2> {
+        let in_region = |x: usize, y: usize| {
+            grid.index(x, y)
+                .and_then(|i| region.get(i).copied())
+                .unwrap_or(false)
+        };
+
+        let mut edges: std::collections::HashMap<(usize, usize), Vec<(usize, usize)>> =
+            std::collections::HashMap::new();
+        for y in 0..grid.height {
+            for x in 0..grid.width {
+                if !in_region(x, y) {
+                    continue;
+                }
+                let mut add = |from, to| edges.entry(from).or_default().push(to);
+                if y == 0 || !in_region(x, y - 1) {
+                    add((x, y), (x + 1, y));
+                }
+                if !in_region(x + 1, y) {
+                    add((x + 1, y), (x + 1, y + 1));
+                }
+                if !in_region(x, y + 1) {
+                    add((x + 1, y + 1), (x, y + 1));
+                }
+                if x == 0 || !in_region(x - 1, y) {
+                    add((x, y + 1), (x, y));
+                }
+            }
+        }
+
+        let mut outline: Vec<Pos2> = Vec::new();
+        while let Some(&start) = edges.keys().next() {
+            let mut ring = Vec::new();
+            let mut current = start;
+            while let Some(next) = edges.get_mut(&current).and_then(Vec::pop) {
+                if edges.get(&current).is_some_and(Vec::is_empty) {
+                    edges.remove(&current);
+                }
+                ring.push(grid.corner(current.0, current.1));
+                current = next;
+            }
+            edges.remove(&current);
+            if Self::polygon_area(&ring) > Self::polygon_area(&outline) {
+                outline = ring;
+            }
+        }
+        outline
+    }
+
+    // 由轮廓顶点（画布坐标）生成填充区域形状
+    pub fn region_shape(outline: &[Pos2], color: Color32) -> crate::state::CanvasShape {
+        let frame = Rect::from_points(outline);
+        let size = frame.size().max(Vec2::splat(f32::EPSILON));
+        crate::state::CanvasShape {
+            shape_type: crate::state::CanvasShapeType::Region,
+            pos: frame.min,
+            size: frame.width(),
+            color,
+            rotation: 0.0,
+            stroke_width: 0.0,
+            fill: Some(color),
+            arrow_heads: crate::state::ArrowHeads::End,
+            height: Some(frame.height()),
+            corner_radius: 0.0,
+            outline: outline.iter().map(|p| (*p - frame.min) / size).collect(),
+        }
+    }
+
+    // 将形状的轮廓转换为等效的笔画（用于像素橡皮擦局部擦除形状）
+    pub fn shape_to_strokes(shape: &crate::state::CanvasShape) -> Vec<crate::state::CanvasStroke> {
+        // 加密轮廓上的点，使橡皮擦只擦除被经过的部分而不是整条边
+        const DENSIFY_FREQUENCY: f32 = 0.5;
+
+        let polylines = Self::shape_outline(shape);
+
+        polylines
+            .into_iter()
+            .map(|points| {
+                let widths = vec![shape.stroke_width; points.len()];
+                let (points, widths) =
+                    Self::apply_point_interpolation(&points, &widths, DENSIFY_FREQUENCY);
+                crate::state::CanvasStroke {
+                    points,
+                    widths,
+                    forces: Vec::new(),
+                    color: shape.color,
+                    base_width: shape.stroke_width,
+                    follow_background: false,
+                    input_type: crate::state::PointerInputType::Pen,
+                    highlighter: false,
+                    fade: None,
+                    cache: Default::default(),
+                }
+            })
+            .collect()
+    }
+
+    // 擦去笔画中与橡皮擦（圆心 pos、半径 radius）相交的线段，返回剩余的各段笔画
+    // - 未被擦到的笔画原样返回（保留绘制缓存）
+    // - 只剩一个点的片段保留为圆点，圆点本身被擦到时才删除
+    pub fn split_stroke_at(
+        stroke: crate::state::CanvasStroke,
+        pos: Pos2,
+        radius: f32,
+    ) -> Vec<crate::state::CanvasStroke> {
+        let survives = |points: &[Pos2], widths: &[f32]| match points {
+            [] => false,
+            [point] => {
+                let width = widths.first().copied().unwrap_or(stroke.base_width);
+                point.distance(pos) > radius + width / 2.0
+            }
+            _ => true,
+        };
+
+        if stroke.points.len() < 2 {
+            return if survives(&stroke.points, &stroke.widths) {
+                vec![stroke]
+            } else {
+                Vec::new()
+            };
+        }
+
+        let mut pieces = Vec::new();
+        let mut cut = false;
+        let mut current_points = Vec::new();
+        let mut current_widths = Vec::new();
+        let mut current_forces = Vec::new();
+
+        current_points.extend(stroke.points.first());
+        current_widths.extend(stroke.widths.first());
+        current_forces.extend(stroke.forces.first());
+
+        for (i, segment) in stroke.points.windows(2).enumerate() {
+            let &[p1, p2] = segment else {
+                continue;
+            };
+            let start = (p1, Self::stroke_width_at(&stroke, i));
+            let end = (p2, Self::stroke_width_at(&stroke, i + 1));
+            // 被擦到的线段断开，后一段从该线段的终点重新开始
+            if Self::segment_hit(pos, radius, start, end) {
+                cut = true;
+                if survives(&current_points, &current_widths) {
+                    pieces.push(crate::state::CanvasStroke {
+                        points: std::mem::take(&mut current_points),
+                        widths: std::mem::take(&mut current_widths),
+                        forces: std::mem::take(&mut current_forces),
+                        cache: Default::default(),
+                        ..stroke.clone()
+                    });
+                }
+                current_points.clear();
+                current_widths.clear();
+                current_forces.clear();
+            }
+            current_points.push(p2);
+            current_widths.extend(stroke.widths.get(i + 1).or(stroke.widths.last()));
+            current_forces.extend(stroke.forces.get(i + 1));
+        }
+
+        if !cut {
+            return vec![stroke];
+        }
+        if survives(&current_points, &current_widths) {
+            pieces.push(crate::state::CanvasStroke {
+                points: current_points,
+                widths: current_widths,
+                forces: current_forces,
+                cache: Default::default(),
+                ..stroke
+            });
+        }
+        pieces
+    }
+
+    // 估算圆形覆盖矩形面积的比例（0.0 到 1.0），通过网格采样计算
+    pub fn circle_rect_coverage(center: Pos2, radius: f32, rect: Rect) -> f32 {
+        const SAMPLES: usize = 10;
+
+        if rect.width() <= 0.0 || rect.height() <= 0.0 {
+            return if rect.center().distance(center) <= radius {
+                1.0
+            } else {
+                0.0
+            };
+        }
+
+        let mut covered = 0;
+        for ix in 0..SAMPLES {
+            for iy in 0..SAMPLES {
+                let sample = Pos2::new(
+                    rect.min.x + (ix as f32 + 0.5) / SAMPLES as f32 * rect.width(),
+                    rect.min.y + (iy as f32 + 0.5) / SAMPLES as f32 * rect.height(),
+                );
+                if sample.distance(center) <= radius {
+                    covered += 1;
+                }
+            }
+        }
+        covered as f32 / (SAMPLES * SAMPLES) as f32
+    }
+
+    // 查找与给定颜色最接近的命名颜色（用于快捷颜色的标签）
+    pub fn nearest_color_name(color: Color32) -> &'static str {
+        const NAMED_COLORS: [(&str, [u8; 3]); 16] = [
+            ("红", [255, 0, 0]),
+            ("橙", [255, 165, 0]),
+            ("黄", [255, 255, 0]),
+            ("绿", [0, 255, 0]),
+            ("深绿", [0, 128, 0]),
+            ("青", [0, 255, 255]),
+            ("蓝", [0, 0, 255]),
+            ("深蓝", [0, 0, 128]),
+            ("紫", [128, 0, 128]),
+            ("品红", [255, 0, 255]),
+            ("粉", [255, 192, 203]),
+            ("棕", [139, 69, 19]),
+            ("灰", [128, 128, 128]),
+            ("银", [192, 192, 192]),
+            ("黑", [0, 0, 0]),
+            ("白", [255, 255, 255]),
+        ];
+
+        // 使用 "redmean" 加权距离，比直接的 RGB 欧氏距离更接近人眼感知
+        let distance = |[r, g, b]: [u8; 3]| {
+            let mean_r = f32::midpoint(f32::from(color.r()), f32::from(r));
+            let dr = f32::from(color.r()) - f32::from(r);
+            let dg = f32::from(color.g()) - f32::from(g);
+            let db = f32::from(color.b()) - f32::from(b);
+            (2.0 + mean_r / 256.0) * dr * dr
+                + 4.0 * dg * dg
+                + (2.0 + (255.0 - mean_r) / 256.0) * db * db
+        };
+
+        NAMED_COLORS
+            .iter()
+            .min_by(|(_, a), (_, b)| distance(*a).total_cmp(&distance(*b)))
+            .map(|(name, _)| *name)
+            .unwrap_or("自定义")
+    }
+
+    pub fn draw_size_preview(
+        painter: &Painter,
+        pos: Pos2,
+        size: f32,
+        style: crate::state::SizePreviewStyle,
+    ) {
+        const SIZE_PREVIEW_BORDER_WIDTH: f32 = 2.0;
+        let radius = size / SIZE_PREVIEW_BORDER_WIDTH;
+        match style {
+            crate::state::SizePreviewStyle::Brush(color) => {
+                painter.circle_filled(pos, radius, color);
+                painter.circle_stroke(
+                    pos,
+                    radius,
+                    Stroke::new(SIZE_PREVIEW_BORDER_WIDTH, Self::contrast_color(color)),
+                );
+            }
+            crate::state::SizePreviewStyle::Eraser => {
+                painter.circle_filled(
+                    pos,
+                    radius,
+                    Color32::from_rgba_unmultiplied(255, 255, 255, 80),
+                );
+                painter.circle_stroke(
+                    pos,
+                    radius,
+                    Stroke::new(SIZE_PREVIEW_BORDER_WIDTH, Color32::BLACK),
+                );
+                let crosshair = Stroke::new(1.0, Color32::BLACK);
+                let arm = (radius * 0.5).max(3.0);
+                painter.hline((pos.x - arm)..=(pos.x + arm), pos.y, crosshair);
+                painter.vline(pos.x, (pos.y - arm)..=(pos.y + arm), crosshair);
+            }
+            crate::state::SizePreviewStyle::Neutral => {
+                painter.circle_filled(pos, radius, Color32::WHITE);
+                painter.circle_stroke(
+                    pos,
+                    radius,
+                    Stroke::new(SIZE_PREVIEW_BORDER_WIDTH, Color32::BLACK),
+                );
+            }
+        }
+    }
+
+    // 绘制颜色切换色轮：快捷颜色均匀排列在中心周围，当前颜色放大显示
+    pub fn draw_color_wheel(painter: &Painter, center: Pos2, colors: &[Color32], selected: usize) {
+        const WHEEL_RADIUS: f32 = 50.0;
+        const SWATCH_RADIUS: f32 = 12.0;
+
+        if colors.is_empty() {
+            return;
+        }
+
+        painter.circle_filled(
+            center,
+            WHEEL_RADIUS + SWATCH_RADIUS * 2.0,
+            Color32::from_rgba_unmultiplied(40, 40, 40, 180),
+        );
+
+        let span = std::f32::consts::TAU / colors.len() as f32;
+        for (index, color) in colors.iter().enumerate() {
+            let dir = Vec2::angled(index as f32 * span - std::f32::consts::FRAC_PI_2);
+            let swatch_pos = center + dir * WHEEL_RADIUS;
+            let radius = if index == selected {
+                SWATCH_RADIUS * 1.5
+            } else {
+                SWATCH_RADIUS
+            };
+            painter.circle_filled(swatch_pos, radius, *color);
+            painter.circle_stroke(swatch_pos, radius, Stroke::new(2.0, Color32::WHITE));
+        }
+
+        if let Some(color) = colors.get(selected) {
+            painter.circle_filled(center, SWATCH_RADIUS, *color);
+        }
+    }
+
+    // 旋转锚点到对象顶部的距离
+    pub const ROTATION_ANCHOR_DISTANCE: f32 = 30.0;
+
+    // 对象边界框上的调整大小锚点位置
+    pub fn resize_anchor_positions(rect: Rect) -> [(ResizeAnchor, Pos2); 8] {
+        [
+            (ResizeAnchor::TopLeft, rect.left_top()),
+            (ResizeAnchor::TopRight, rect.right_top()),
+            (ResizeAnchor::BottomLeft, rect.left_bottom()),
+            (ResizeAnchor::BottomRight, rect.right_bottom()),
+            (ResizeAnchor::Top, rect.center_top()),
+            (ResizeAnchor::Bottom, rect.center_bottom()),
+            (ResizeAnchor::Left, rect.left_center()),
+            (ResizeAnchor::Right, rect.right_center()),
+        ]
+    }
+
+    // 旋转锚点位置（在顶部中间锚点上方）
+    pub fn rotation_anchor_position(rect: Rect) -> Pos2 {
+        Pos2::new(rect.center().x, rect.min.y - Self::ROTATION_ANCHOR_DISTANCE)
+    }
+
+    // 锚点尺寸以逻辑点为单位，会随屏幕缩放比例（DPI）自动缩放
+    pub fn draw_resize_and_rotation_anchors(
+        painter: &egui::Painter,
+        object_rect: egui::Rect,
+        anchor_size: f32,
+        resize_anchor_hovered: Option<ResizeAnchor>,
+        rotation_anchor_hovered: bool,
+    ) {
+        // 绘制调整大小锚点
+        let anchors = Self::resize_anchor_positions(object_rect);
+
+        for (anchor_type, pos) in anchors {
+            // 绘制锚点
+            let anchor_color = if let Some(hovered_anchor) = resize_anchor_hovered {
+                if hovered_anchor == anchor_type {
+                    Color32::YELLOW
+                } else {
+                    Color32::WHITE
+                }
+            } else {
+                Color32::WHITE
+            };
+
+            painter.circle_filled(pos, anchor_size, anchor_color);
+            painter.circle_stroke(pos, anchor_size, Stroke::new(2.0, Color32::BLACK));
+        }
+
+        // 绘制旋转锚点
+        let rotation_anchor_pos = Self::rotation_anchor_position(object_rect);
+
+        let rotation_color = if rotation_anchor_hovered {
+            Color32::YELLOW
+        } else {
+            Color32::WHITE
+        };
+
+        painter.circle_filled(rotation_anchor_pos, anchor_size, rotation_color);
+        painter.circle_stroke(
+            rotation_anchor_pos,
+            anchor_size,
+            Stroke::new(2.0, Color32::BLACK),
         );
 
         // 绘制连接线
@@ -354,4 +2303,430 @@ impl AppUtils {
             Stroke::new(2.0, Color32::WHITE),
         );
     }
+
+    // 径向菜单：内圈为工具，外圈为快捷颜色
+    const RADIAL_MENU_INNER_RADIUS: f32 = 30.0;
+    const RADIAL_MENU_MIDDLE_RADIUS: f32 = 100.0;
+    const RADIAL_MENU_OUTER_RADIUS: f32 = 140.0;
+
+    // 计算径向菜单中位于指定位置的菜单项
+    pub fn radial_menu_item_at(
+        center: Pos2,
+        pos: Pos2,
+        colors: &[Color32],
+    ) -> Option<RadialMenuItem> {
+        let offset = pos - center;
+        let distance = offset.length();
+
+        // 从正上方开始顺时针计算角度
+        let angle = (offset.y.atan2(offset.x) + std::f32::consts::FRAC_PI_2)
+            .rem_euclid(std::f32::consts::TAU);
+        let segment = |count: usize| {
+            ((angle / std::f32::consts::TAU * count as f32) as usize).min(count.saturating_sub(1))
+        };
+
+        if (Self::RADIAL_MENU_INNER_RADIUS..Self::RADIAL_MENU_MIDDLE_RADIUS).contains(&distance) {
+            CanvasTool::ALL
+                .get(segment(CanvasTool::ALL.len()))
+                .map(|tool| RadialMenuItem::Tool(*tool))
+        } else if (Self::RADIAL_MENU_MIDDLE_RADIUS..Self::RADIAL_MENU_OUTER_RADIUS)
+            .contains(&distance)
+        {
+            colors
+                .get(segment(colors.len()))
+                .map(|color| RadialMenuItem::Color(*color))
+        } else {
+            None
+        }
+    }
+
+    // 绘制径向菜单
+    pub fn draw_radial_menu(
+        painter: &Painter,
+        center: Pos2,
+        colors: &[Color32],
+        hovered: Option<RadialMenuItem>,
+    ) {
+        // 构建圆环扇区的网格（圆环扇区不是凸多边形，无法直接使用 convex_polygon）
+        fn ring_segment(
+            center: Pos2,
+            inner_radius: f32,
+            outer_radius: f32,
+            start_angle: f32,
+            end_angle: f32,
+            color: Color32,
+        ) -> egui::Mesh {
+            const STEPS: u32 = 16;
+            let mut mesh = egui::Mesh::default();
+            for i in 0..=STEPS {
+                let t = i as f32 / STEPS as f32;
+                let angle =
+                    start_angle + (end_angle - start_angle) * t - std::f32::consts::FRAC_PI_2;
+                let dir = Vec2::angled(angle);
+                mesh.colored_vertex(center + dir * inner_radius, color);
+                mesh.colored_vertex(center + dir * outer_radius, color);
+                if i > 0 {
+                    let base = (i - 1) * 2;
+                    mesh.add_triangle(base, base + 1, base + 2);
+                    mesh.add_triangle(base + 1, base + 3, base + 2);
+                }
+            }
+            mesh
+        }
+
+        let separator = Stroke::new(1.0, Color32::BLACK);
+        let tool_count = CanvasTool::ALL.len();
+        let tool_span = std::f32::consts::TAU / tool_count as f32;
+        for (index, tool) in CanvasTool::ALL.iter().enumerate() {
+            let start_angle = index as f32 * tool_span;
+            let is_hovered = hovered == Some(RadialMenuItem::Tool(*tool));
+            let fill = if is_hovered {
+                Color32::from_rgb(70, 110, 200)
+            } else {
+                Color32::from_rgba_unmultiplied(40, 40, 40, 230)
+            };
+            painter.add(ring_segment(
+                center,
+                Self::RADIAL_MENU_INNER_RADIUS,
+                Self::RADIAL_MENU_MIDDLE_RADIUS,
+                start_angle,
+                start_angle + tool_span,
+                fill,
+            ));
+
+            let label_radius = f32::midpoint(
+                Self::RADIAL_MENU_INNER_RADIUS,
+                Self::RADIAL_MENU_MIDDLE_RADIUS,
+            );
+            let label_dir =
+                Vec2::angled(start_angle + tool_span / 2.0 - std::f32::consts::FRAC_PI_2);
+            painter.text(
+                center + label_dir * label_radius,
+                egui::Align2::CENTER_CENTER,
+                tool.label(),
+                egui::FontId::proportional(13.0),
+                Color32::WHITE,
+            );
+        }
+
+        if !colors.is_empty() {
+            let color_span = std::f32::consts::TAU / colors.len() as f32;
+            for (index, color) in colors.iter().enumerate() {
+                let start_angle = index as f32 * color_span;
+                let outer_radius = if hovered == Some(RadialMenuItem::Color(*color)) {
+                    Self::RADIAL_MENU_OUTER_RADIUS + 10.0
+                } else {
+                    Self::RADIAL_MENU_OUTER_RADIUS
+                };
+                painter.add(ring_segment(
+                    center,
+                    Self::RADIAL_MENU_MIDDLE_RADIUS,
+                    outer_radius,
+                    start_angle,
+                    start_angle + color_span,
+                    *color,
+                ));
+            }
+        }
+
+        painter.circle_stroke(center, Self::RADIAL_MENU_INNER_RADIUS, separator);
+        painter.circle_stroke(center, Self::RADIAL_MENU_MIDDLE_RADIUS, separator);
+        painter.circle_stroke(center, Self::RADIAL_MENU_OUTER_RADIUS, separator);
+    }
+}
+
+// 填充工具使用的栅格：覆盖可见范围，每个格子边长为 cell
+struct FillGrid {
+    origin: Pos2,  // 左上角（画布坐标）
+    cell: f32,     // 格子边长
+    width: usize,  // 列数
+    height: usize, // 行数
+}
+
+impl FillGrid {
+    // 格子在栅格数据中的索引，超出范围时为 None
+    fn index(&self, x: usize, y: usize) -> Option<usize> {
+        (x < self.width && y < self.height).then(|| y * self.width + x)
+    }
+
+    // 包含指定位置的格子（超出范围时取最近的格子）
+    fn cell_of(&self, pos: Pos2) -> (usize, usize) {
+        let offset = (pos - self.origin) / self.cell;
+        (
+            (offset.x.max(0.0) as usize).min(self.width.saturating_sub(1)),
+            (offset.y.max(0.0) as usize).min(self.height.saturating_sub(1)),
+        )
+    }
+
+    // 格子中心
+    fn center(&self, x: usize, y: usize) -> Pos2 {
+        self.origin + egui::vec2(x as f32 + 0.5, y as f32 + 0.5) * self.cell
+    }
+
+    // 格子的左上角（格点）
+    fn corner(&self, x: usize, y: usize) -> Pos2 {
+        self.origin + egui::vec2(x as f32, y as f32) * self.cell
+    }
+
+    // 四连通的相邻格子（越界的坐标由 index 排除）
+    fn neighbors(x: usize, y: usize) -> [(usize, usize); 4] {
+        [
+            (x.wrapping_sub(1), y),
+            (x + 1, y),
+            (x, y.wrapping_sub(1)),
+            (x, y + 1),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{CanvasStroke, PointerInputType, StrokeCache};
+
+    fn stroke(points: &[(f32, f32)], width: f32) -> CanvasStroke {
+        CanvasStroke {
+            points: points.iter().map(|&(x, y)| Pos2::new(x, y)).collect(),
+            widths: vec![width; points.len()],
+            forces: Vec::new(),
+            color: Color32::BLACK,
+            base_width: width,
+            follow_background: false,
+            input_type: PointerInputType::Pen,
+            highlighter: false,
+            fade: None,
+            cache: StrokeCache::default(),
+        }
+    }
+
+    #[test]
+    fn adjust_stroke_widths_clamps_to_brush_range() {
+        let mut thick = stroke(&[(0.0, 0.0)], 10.0);
+        AppUtils::adjust_stroke_widths(&mut thick, Pos2::ZERO, 20.0, 10.0, 1.0, 12.0);
+        assert_eq!(
+            thick.widths,
+            vec![12.0],
+            "thickened width should not exceed the maximum"
+        );
+
+        let mut thin = stroke(&[(0.0, 0.0)], 2.0);
+        AppUtils::adjust_stroke_widths(&mut thin, Pos2::ZERO, 20.0, 0.1, 1.5, 12.0);
+        assert_eq!(
+            thin.widths,
+            vec![1.5],
+            "thinned width should not go below the minimum"
+        );
+    }
+
+    // 沿 x 轴每隔 10 个单位取一个点的直线
+    fn line(from: f32, to: f32, y: f32) -> Vec<(f32, f32)> {
+        let step = 10.0 * (to - from).signum();
+        let mut points = vec![(from, y)];
+        let mut x = from;
+        while (to - x).abs() > f32::EPSILON {
+            x += step;
+            points.push((x, y));
+        }
+        points
+    }
+
+    #[test]
+    fn split_stroke_at_middle_leaves_two_pieces() {
+        let pieces = AppUtils::split_stroke_at(
+            stroke(&line(0.0, 100.0, 0.0), 2.0),
+            Pos2::new(50.0, 0.0),
+            5.0,
+        );
+        assert_eq!(
+            pieces.len(),
+            2,
+            "erasing the middle should leave two pieces"
+        );
+        assert_eq!(
+            pieces.first().and_then(|piece| piece.points.first()),
+            Some(&Pos2::new(0.0, 0.0)),
+            "first piece should keep the stroke start"
+        );
+        assert_eq!(
+            pieces.last().and_then(|piece| piece.points.last()),
+            Some(&Pos2::new(100.0, 0.0)),
+            "last piece should keep the stroke end"
+        );
+    }
+
+    #[test]
+    fn split_stroke_at_two_crossings_leaves_three_pieces() {
+        // U 形笔画：橡皮擦同时经过上下两条边，右侧的竖边不受影响
+        let mut points = line(0.0, 100.0, 0.0);
+        points.extend(line(100.0, 0.0, 20.0));
+        let pieces = AppUtils::split_stroke_at(stroke(&points, 2.0), Pos2::new(50.0, 10.0), 12.0);
+        assert_eq!(
+            pieces.len(),
+            3,
+            "crossing the stroke twice should leave three pieces"
+        );
+    }
+
+    #[test]
+    fn split_stroke_at_covering_eraser_removes_stroke() {
+        let pieces = AppUtils::split_stroke_at(
+            stroke(&line(0.0, 20.0, 0.0), 2.0),
+            Pos2::new(10.0, 0.0),
+            30.0,
+        );
+        assert!(
+            pieces.is_empty(),
+            "an eraser covering the stroke should remove it"
+        );
+    }
+
+    #[test]
+    fn split_stroke_at_miss_keeps_stroke() {
+        let pieces = AppUtils::split_stroke_at(
+            stroke(&line(0.0, 100.0, 0.0), 2.0),
+            Pos2::new(50.0, 50.0),
+            5.0,
+        );
+        assert_eq!(
+            pieces.first().map(|piece| piece.points.len()),
+            Some(11),
+            "a missed stroke should be returned with all its points"
+        );
+        assert_eq!(pieces.len(), 1, "a missed stroke should not be split");
+    }
+
+    #[test]
+    fn erasing_middle_of_three_point_stroke_leaves_two_dots() {
+        let pieces = AppUtils::split_stroke_at(
+            stroke(&[(0.0, 0.0), (50.0, 0.0), (100.0, 0.0)], 2.0),
+            Pos2::new(50.0, 0.0),
+            5.0,
+        );
+        let points: Vec<&[Pos2]> = pieces.iter().map(|piece| piece.points.as_slice()).collect();
+        assert_eq!(
+            points,
+            vec![
+                [Pos2::new(0.0, 0.0)].as_slice(),
+                [Pos2::new(100.0, 0.0)].as_slice()
+            ],
+            "the outer points should remain as dots"
+        );
+        assert!(
+            pieces.iter().all(|piece| piece.widths.len() == 1),
+            "each dot should keep its width"
+        );
+    }
+
+    #[test]
+    fn erasing_within_two_point_stroke_keeps_end_dots() {
+        let pieces = AppUtils::split_stroke_at(
+            stroke(&[(0.0, 0.0), (100.0, 0.0)], 2.0),
+            Pos2::new(50.0, 0.0),
+            5.0,
+        );
+        assert_eq!(pieces.len(), 2, "both end points should remain as dots");
+        assert!(
+            pieces.iter().all(|piece| piece.points.len() == 1),
+            "the fragments should be single points"
+        );
+    }
+
+    #[test]
+    fn erasing_end_of_two_point_stroke_keeps_other_dot() {
+        let pieces = AppUtils::split_stroke_at(
+            stroke(&[(0.0, 0.0), (100.0, 0.0)], 2.0),
+            Pos2::new(98.0, 0.0),
+            5.0,
+        );
+        assert_eq!(
+            pieces.first().map(|piece| piece.points.as_slice()),
+            Some([Pos2::new(0.0, 0.0)].as_slice()),
+            "only the start point should remain"
+        );
+        assert_eq!(pieces.len(), 1, "the erased end point should be dropped");
+    }
+
+    #[test]
+    fn interpolation_handles_degenerate_input() {
+        let (points, widths) = AppUtils::apply_point_interpolation(&[], &[], 1.0);
+        assert!(
+            points.is_empty() && widths.is_empty(),
+            "empty input should stay empty"
+        );
+
+        let dot = [Pos2::new(1.0, 2.0)];
+        let (points, widths) = AppUtils::apply_point_interpolation(&dot, &[3.0], 1.0);
+        assert_eq!(points, dot, "a single point should be returned as is");
+        assert_eq!(widths, vec![3.0], "a single width should be returned as is");
+
+        let line = [Pos2::new(0.0, 0.0), Pos2::new(10.0, 0.0)];
+        let (points, widths) = AppUtils::apply_point_interpolation(&line, &[], 1.0);
+        assert_eq!(
+            points, line,
+            "points without widths should not be interpolated"
+        );
+        assert!(widths.is_empty(), "missing widths should stay empty");
+    }
+
+    #[test]
+    fn smoothing_handles_degenerate_input() {
+        assert!(
+            AppUtils::apply_stroke_smoothing(&[], 3).is_empty(),
+            "smoothing an empty stroke should stay empty"
+        );
+        assert!(
+            AppUtils::smooth_trailing_points(&[], 4).is_empty(),
+            "live smoothing an empty stroke should stay empty"
+        );
+
+        let dot = [Pos2::new(1.0, 2.0)];
+        assert_eq!(
+            AppUtils::apply_stroke_smoothing(&dot, 3),
+            dot,
+            "smoothing a dot should keep it"
+        );
+        assert_eq!(
+            AppUtils::smooth_trailing_points(&dot, 4),
+            dot,
+            "live smoothing a dot should keep it"
+        );
+    }
+
+    #[test]
+    fn resampling_and_meshing_handle_degenerate_input() {
+        assert!(
+            AppUtils::resample_widths(&[], 3).is_empty(),
+            "resampling no widths should give no widths"
+        );
+        assert_eq!(
+            AppUtils::resample_widths(&[2.0], 3),
+            vec![2.0; 3],
+            "a single width should be repeated"
+        );
+        assert!(
+            AppUtils::build_stroke_mesh(&[], &[], Color32::BLACK).is_empty(),
+            "an empty stroke should give an empty mesh"
+        );
+        let center = Pos2::new(1.0, 2.0);
+        let dot = AppUtils::build_stroke_mesh(&[center], &[4.0], Color32::BLACK);
+        assert!(!dot.is_empty(), "a single point should be meshed as a dot");
+        assert!(
+            dot.vertices
+                .iter()
+                .all(|vertex| vertex.pos.distance(center)
+                    <= 2.0 + AppUtils::STROKE_FEATHER / 2.0 + 1e-3),
+            "the dot should stay within half the width (plus feathering) of its center"
+        );
+    }
+
+    #[test]
+    fn remove_indices_removes_each_index_once() {
+        let mut items: Vec<usize> = (0..6).collect();
+        AppUtils::remove_indices(&mut items, &[4, 0, 4, 10, 2]);
+        assert_eq!(
+            items,
+            vec![1, 3, 5],
+            "duplicate and out-of-range indices should be ignored"
+        );
+    }
 }